@@ -0,0 +1,507 @@
+mod _assets {
+    use crate::{
+        self as vk_utils,
+        buffer::Buffer,
+        image::{Image, ImageView},
+        model::{Model, SubMesh},
+        renderer::RenderContext,
+        texture::{Sampler, TexturePixels, TextureRole},
+    };
+
+    use ash::vk;
+    use std::{
+        collections::HashMap,
+        hash::Hash,
+        path::{Path, PathBuf},
+    };
+
+    // Handles stay valid until the last reference is released. Freed slots are reused
+    // with a new generation, so stale handles resolve to nothing instead of another asset
+    #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+    pub struct TextureHandle {
+        index: usize,
+        generation: u32,
+    }
+
+    #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+    pub struct MeshHandle {
+        index: usize,
+        generation: u32,
+    }
+
+    // Sampled image with as many mip levels as its format allows
+    pub struct Texture {
+        // Views and samplers go before the image
+        sampler: Sampler,
+        image_view: ImageView,
+        _image: Image,
+        format: vk::Format,
+        mip_levels: u32,
+    }
+
+    impl Texture {
+        pub fn format(&self) -> vk::Format {
+            self.format
+        }
+
+        pub fn image_view(&self) -> vk::ImageView {
+            self.image_view.handle()
+        }
+
+        pub fn sampler(&self) -> vk::Sampler {
+            self.sampler.handle()
+        }
+
+        pub fn mip_levels(&self) -> u32 {
+            self.mip_levels
+        }
+    }
+
+    // Vertex and index buffers of a Model, drawn submesh by submesh
+    pub struct Mesh {
+        vertex_buffer: Buffer,
+        index_buffer: Buffer,
+        submeshes: Vec<SubMesh>,
+    }
+
+    impl Mesh {
+        pub fn vertex_buffer(&self) -> &Buffer {
+            &self.vertex_buffer
+        }
+
+        pub fn index_buffer(&self) -> &Buffer {
+            &self.index_buffer
+        }
+
+        pub fn submeshes(&self) -> &[SubMesh] {
+            &self.submeshes
+        }
+    }
+
+    struct Entry<K, T> {
+        asset: T,
+        // None for assets that weren't loaded from a file, those are never shared
+        key: Option<K>,
+        reference_count: usize,
+    }
+
+    struct Slot<K, T> {
+        generation: u32,
+        entry: Option<Entry<K, T>>,
+    }
+
+    // Reference counted assets of one type, looked up by (index, generation).
+    // Keys are the paths they were loaded from (along with anything else that sets them apart)
+    struct Registry<K, T> {
+        slots: Vec<Slot<K, T>>,
+        free_slots: Vec<usize>,
+        keys: HashMap<K, usize>,
+    }
+
+    impl<K: Clone + Eq + Hash, T> Registry<K, T> {
+        fn new() -> Self {
+            Self {
+                slots: Vec::new(),
+                free_slots: Vec::new(),
+                keys: HashMap::new(),
+            }
+        }
+
+        // Another reference to the asset loaded with `key`, if there is one
+        fn acquire(&mut self, key: &K) -> Option<(usize, u32)> {
+            let index = *self.keys.get(key)?;
+            let slot = &mut self.slots[index];
+
+            slot.entry.as_mut()?.reference_count += 1;
+
+            Some((index, slot.generation))
+        }
+
+        fn insert(&mut self, key: Option<K>, asset: T) -> (usize, u32) {
+            let index = match self.free_slots.pop() {
+                Some(index) => index,
+                None => {
+                    self.slots.push(Slot {
+                        generation: 0,
+                        entry: None,
+                    });
+
+                    self.slots.len() - 1
+                }
+            };
+
+            if let Some(key) = key.as_ref() {
+                self.keys.insert(key.clone(), index);
+            }
+
+            let slot = &mut self.slots[index];
+            slot.entry = Some(Entry {
+                asset,
+                key,
+                reference_count: 1,
+            });
+
+            (index, slot.generation)
+        }
+
+        fn entry(&self, index: usize, generation: u32) -> Option<&Entry<K, T>> {
+            self.slots
+                .get(index)
+                .filter(|slot| slot.generation == generation)
+                .and_then(|slot| slot.entry.as_ref())
+        }
+
+        // Returns the asset once its last reference is gone, for the caller to drop
+        fn release(&mut self, index: usize, generation: u32) -> Option<T> {
+            let slot = self
+                .slots
+                .get_mut(index)
+                .filter(|slot| slot.generation == generation)?;
+            let entry = slot.entry.as_mut()?;
+
+            entry.reference_count -= 1;
+            if entry.reference_count > 0 {
+                return None;
+            }
+
+            let entry = slot.entry.take()?;
+            slot.generation = slot.generation.wrapping_add(1);
+            if let Some(key) = entry.key.as_ref() {
+                self.keys.remove(key);
+            }
+            self.free_slots.push(index);
+
+            Some(entry.asset)
+        }
+
+        fn len(&self) -> usize {
+            self.slots.len() - self.free_slots.len()
+        }
+
+        fn clear(&mut self) {
+            for slot in self.slots.iter_mut() {
+                if slot.entry.take().is_some() {
+                    slot.generation = slot.generation.wrapping_add(1);
+                }
+            }
+            self.free_slots = (0..self.slots.len()).collect();
+            self.keys.clear();
+        }
+    }
+
+    // Paths that name the same file share one entry
+    fn asset_key(path: &Path) -> PathBuf {
+        std::fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf())
+    }
+
+    // Textures and meshes by path: loading a file again hands out the asset already loaded
+    // and counts the reference. GPU resources are freed with the last release, so like
+    // anything else destroyed, no pending frame may be using them by then.
+    // A file loaded as a color and as a mask gives two textures
+    pub struct AssetManager {
+        textures: Registry<(PathBuf, TextureRole), Texture>,
+        meshes: Registry<PathBuf, Mesh>,
+    }
+
+    impl Default for AssetManager {
+        fn default() -> Self {
+            Self::new()
+        }
+    }
+
+    impl AssetManager {
+        pub fn new() -> Self {
+            Self {
+                textures: Registry::new(),
+                meshes: Registry::new(),
+            }
+        }
+
+        pub fn load_texture(
+            &mut self,
+            context: &RenderContext,
+            path: &Path,
+            role: TextureRole,
+        ) -> vk_utils::Result<TextureHandle> {
+            if let Some(texture) = self.acquire_texture(path, role) {
+                return Ok(texture);
+            }
+
+            let pixels = vk_utils::texture::load_image_pixels(path, role)?;
+
+            self.insert_texture(context, Some(path), role, &pixels)
+        }
+
+        // Cache lookup only, for pixels decoded elsewhere (e.g. on an AssetLoader)
+        pub fn acquire_texture(&mut self, path: &Path, role: TextureRole) -> Option<TextureHandle> {
+            self.textures
+                .acquire(&(asset_key(path), role))
+                .map(|(index, generation)| TextureHandle { index, generation })
+        }
+
+        // Uploads pixels decoded for `role`. With a path, a texture already loaded from it
+        // for the same role is shared instead; without one, the texture is never shared
+        pub fn insert_texture(
+            &mut self,
+            context: &RenderContext,
+            path: Option<&Path>,
+            role: TextureRole,
+            pixels: &TexturePixels,
+        ) -> vk_utils::Result<TextureHandle> {
+            if let Some(texture) = path.and_then(|path| self.acquire_texture(path, role)) {
+                return Ok(texture);
+            }
+
+            // 16 and 32 bit formats the device can't handle are narrowed to 8 bits
+            let supported = vk_utils::texture::to_supported_format(
+                context.instance,
+                context.physical_device,
+                pixels,
+            );
+            let pixels = supported.as_ref().unwrap_or(pixels);
+            let format = pixels.format;
+            let mip_levels = vk_utils::texture::texture_mip_levels(
+                context.instance,
+                context.physical_device,
+                format,
+                pixels.width,
+                pixels.height,
+            );
+            let image = context
+                .uploads
+                .upload_texture(context.allocator, pixels, mip_levels)?;
+            let image_view = vk_utils::texture::create_texture_image_view(
+                context.device,
+                image.handle(),
+                format,
+                mip_levels,
+            )?;
+            let sampler = vk_utils::texture::create_texture_sampler(
+                context.instance,
+                context.device,
+                context.physical_device,
+                format,
+                mip_levels,
+            )?;
+
+            let (index, generation) = self.textures.insert(
+                path.map(|path| (asset_key(path), role)),
+                Texture {
+                    sampler,
+                    image_view,
+                    _image: image,
+                    format,
+                    mip_levels,
+                },
+            );
+
+            Ok(TextureHandle { index, generation })
+        }
+
+        pub fn texture(&self, texture: TextureHandle) -> Option<&Texture> {
+            self.textures
+                .entry(texture.index, texture.generation)
+                .map(|entry| &entry.asset)
+        }
+
+        // True if this was the last reference and the texture was freed
+        pub fn release_texture(&mut self, texture: TextureHandle) -> bool {
+            self.textures
+                .release(texture.index, texture.generation)
+                .is_some()
+        }
+
+        // OBJ, or glTF flattened into one mesh
+        pub fn load_mesh(
+            &mut self,
+            context: &RenderContext,
+            path: &Path,
+        ) -> vk_utils::Result<MeshHandle> {
+            if let Some(mesh) = self.acquire_mesh(path) {
+                return Ok(mesh);
+            }
+
+            let extension = path
+                .extension()
+                .and_then(|extension| extension.to_str())
+                .map(|extension| extension.to_ascii_lowercase());
+            let model = match extension.as_deref() {
+                Some("gltf") | Some("glb") => vk_utils::model::load_gltf(path)?.to_model(),
+                _ => vk_utils::model::load_model(path)?,
+            };
+
+            self.insert_mesh(context, Some(path), &model)
+        }
+
+        pub fn acquire_mesh(&mut self, path: &Path) -> Option<MeshHandle> {
+            self.meshes
+                .acquire(&asset_key(path))
+                .map(|(index, generation)| MeshHandle { index, generation })
+        }
+
+        // Same sharing rules as insert_texture
+        pub fn insert_mesh(
+            &mut self,
+            context: &RenderContext,
+            path: Option<&Path>,
+            model: &Model,
+        ) -> vk_utils::Result<MeshHandle> {
+            if let Some(mesh) = path.and_then(|path| self.acquire_mesh(path)) {
+                return Ok(mesh);
+            }
+
+            let mesh = Self::upload_mesh(
+                context,
+                &model.vertices,
+                &model.indices,
+                model.submeshes.clone(),
+            )?;
+            let (index, generation) = self.meshes.insert(path.map(asset_key), mesh);
+
+            Ok(MeshHandle { index, generation })
+        }
+
+        // Vertices of any layout (e.g. Vertex2D), never shared
+        pub fn insert_geometry<V: Copy>(
+            &mut self,
+            context: &RenderContext,
+            vertices: &[V],
+            indices: &[u32],
+            submeshes: Vec<SubMesh>,
+        ) -> vk_utils::Result<MeshHandle> {
+            let mesh = Self::upload_mesh(context, vertices, indices, submeshes)?;
+            let (index, generation) = self.meshes.insert(None, mesh);
+
+            Ok(MeshHandle { index, generation })
+        }
+
+        fn upload_mesh<V: Copy>(
+            context: &RenderContext,
+            vertices: &[V],
+            indices: &[u32],
+            submeshes: Vec<SubMesh>,
+        ) -> vk_utils::Result<Mesh> {
+            let vertex_buffer = context.uploads.upload_buffer(
+                context.allocator,
+                vertices,
+                vk::BufferUsageFlags::VERTEX_BUFFER,
+            )?;
+            let index_buffer = context.uploads.upload_buffer(
+                context.allocator,
+                indices,
+                vk::BufferUsageFlags::INDEX_BUFFER,
+            )?;
+
+            Ok(Mesh {
+                vertex_buffer,
+                index_buffer,
+                submeshes,
+            })
+        }
+
+        pub fn mesh(&self, mesh: MeshHandle) -> Option<&Mesh> {
+            self.meshes
+                .entry(mesh.index, mesh.generation)
+                .map(|entry| &entry.asset)
+        }
+
+        pub fn release_mesh(&mut self, mesh: MeshHandle) -> bool {
+            self.meshes.release(mesh.index, mesh.generation).is_some()
+        }
+
+        // Assets currently loaded, whatever their reference counts
+        pub fn texture_count(&self) -> usize {
+            self.textures.len()
+        }
+
+        pub fn mesh_count(&self) -> usize {
+            self.meshes.len()
+        }
+
+        // Frees everything regardless of outstanding references (before the device goes)
+        pub fn clear(&mut self) {
+            self.textures.clear();
+            self.meshes.clear();
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        #[test]
+        fn loading_a_key_again_counts_a_reference() {
+            let mut registry = Registry::new();
+            let (index, generation) = registry.insert(Some("room.obj"), 1);
+
+            assert_eq!(registry.acquire(&"room.obj"), Some((index, generation)));
+            assert_eq!(registry.acquire(&"cube.obj"), None);
+            assert_eq!(registry.len(), 1);
+
+            assert_eq!(registry.release(index, generation), None);
+            assert_eq!(
+                registry.entry(index, generation).map(|entry| entry.asset),
+                Some(1)
+            );
+            // The last release hands the asset back and forgets the key
+            assert_eq!(registry.release(index, generation), Some(1));
+            assert!(registry.entry(index, generation).is_none());
+            assert_eq!(registry.acquire(&"room.obj"), None);
+            assert_eq!(registry.len(), 0);
+        }
+
+        #[test]
+        fn assets_without_a_key_are_never_shared() {
+            let mut registry: Registry<&str, i32> = Registry::new();
+            let first = registry.insert(None, 1);
+            let second = registry.insert(None, 2);
+
+            assert_ne!(first, second);
+            assert!(registry.keys.is_empty());
+            assert_eq!(registry.release(first.0, first.1), Some(1));
+        }
+
+        #[test]
+        fn stale_handles_miss_reused_slots() {
+            let mut registry = Registry::new();
+            let (index, generation) = registry.insert(Some("room.obj"), 1);
+            registry.release(index, generation);
+
+            let (reused_index, reused_generation) = registry.insert(Some("cube.obj"), 2);
+
+            assert_eq!(reused_index, index);
+            assert_ne!(reused_generation, generation);
+            assert!(registry.entry(index, generation).is_none());
+            assert_eq!(registry.release(index, generation), None);
+            // Releasing through the stale handle left the new asset alone
+            assert_eq!(
+                registry
+                    .entry(reused_index, reused_generation)
+                    .map(|entry| (entry.asset, entry.reference_count)),
+                Some((2, 1))
+            );
+        }
+
+        #[test]
+        fn clear_invalidates_every_handle() {
+            let mut registry = Registry::new();
+            let room = registry.insert(Some("room.obj"), 1);
+            let cube = registry.insert(Some("cube.obj"), 2);
+            registry.release(cube.0, cube.1);
+
+            registry.clear();
+
+            assert_eq!(registry.len(), 0);
+            assert!(registry.entry(room.0, room.1).is_none());
+            assert_eq!(registry.acquire(&"room.obj"), None);
+
+            // Every slot is free again, none of the old handles resolve
+            let (index, generation) = registry.insert(Some("room.obj"), 3);
+            assert!(index < 2);
+            assert_ne!((index, generation), room);
+            assert_ne!((index, generation), cube);
+        }
+    }
+}
+
+pub use _assets::{AssetManager, Mesh, MeshHandle, Texture, TextureHandle};
@@ -3,12 +3,38 @@ pub trait Vertex {
     fn get_attribute_descriptions() -> Vec<ash::vk::VertexInputAttributeDescription>;
 }
 
-pub trait Pipeline {
-    fn create_graphics_pipeline(
-        device: &ash::Device,
-        msaa_samples: ash::vk::SampleCountFlags,
-        swapchain_extent: ash::vk::Extent2D,
-        render_pass: ash::vk::RenderPass,
-        descriptor_set_layout: ash::vk::DescriptorSetLayout,
-    ) -> (ash::vk::Pipeline, ash::vk::PipelineLayout);
+// Per-instance attributes from binding 1, stepped once per instance instead of per vertex
+pub trait InstanceData {
+    fn get_binding_description() -> Vec<ash::vk::VertexInputBindingDescription>;
+    // Locations continue right after the vertex attributes
+    fn get_attribute_descriptions(
+        first_location: u32,
+    ) -> Vec<ash::vk::VertexInputAttributeDescription>;
+}
+
+// Nothing per instance, every instance only sees the vertex attributes
+impl InstanceData for () {
+    fn get_binding_description() -> Vec<ash::vk::VertexInputBindingDescription> {
+        vec![]
+    }
+
+    fn get_attribute_descriptions(
+        _first_location: u32,
+    ) -> Vec<ash::vk::VertexInputAttributeDescription> {
+        vec![]
+    }
+}
+
+// Vertex attributes of `V` followed by the instance attributes of `I`
+pub fn input_attribute_descriptions<V: Vertex, I: InstanceData>(
+) -> Vec<ash::vk::VertexInputAttributeDescription> {
+    let mut attributes = V::get_attribute_descriptions();
+    let first_location = attributes
+        .iter()
+        .map(|attribute| attribute.location + 1)
+        .max()
+        .unwrap_or(0);
+
+    attributes.extend(I::get_attribute_descriptions(first_location));
+    attributes
 }
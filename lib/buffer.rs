@@ -1,177 +1,493 @@
 mod _buffer {
+    use crate as vk_utils;
     use ash::vk;
 
+    // Buffer bound to a range of an allocator block, both released when dropped
+    pub struct Buffer {
+        device: ash::Device,
+        buffer: vk::Buffer,
+        allocation: vk_utils::memory::Allocation,
+        size: vk::DeviceSize,
+    }
+
+    impl Buffer {
+        pub fn handle(&self) -> vk::Buffer {
+            self.buffer
+        }
+
+        pub fn allocation(&self) -> &vk_utils::memory::Allocation {
+            &self.allocation
+        }
+
+        pub fn size(&self) -> vk::DeviceSize {
+            self.size
+        }
+
+        // Host visible buffers only, the memory stays mapped
+        pub fn write<T: Copy>(&self, data: &[T]) -> vk_utils::Result<()> {
+            self.write_at(0, data)
+        }
+
+        pub fn write_at<T: Copy>(
+            &self,
+            offset: vk::DeviceSize,
+            data: &[T],
+        ) -> vk_utils::Result<()> {
+            let data_size = std::mem::size_of_val(data) as vk::DeviceSize;
+            let mapped = self.mapped_range(offset, data_size)?;
+
+            unsafe {
+                mapped.copy_from_nonoverlapping(data.as_ptr() as *const u8, data_size as usize);
+            }
+
+            Ok(())
+        }
+
+        pub fn read<T: Copy>(&self, data: &mut [T]) -> vk_utils::Result<()> {
+            let data_size = std::mem::size_of_val(data) as vk::DeviceSize;
+            let mapped = self.mapped_range(0, data_size)?;
+
+            unsafe {
+                (data.as_mut_ptr() as *mut u8).copy_from_nonoverlapping(mapped, data_size as usize);
+            }
+
+            Ok(())
+        }
+
+        fn mapped_range(
+            &self,
+            offset: vk::DeviceSize,
+            data_size: vk::DeviceSize,
+        ) -> vk_utils::Result<*mut u8> {
+            if offset + data_size > self.size {
+                return Err(vk_utils::Error::BufferTooSmall(
+                    offset + data_size,
+                    self.size,
+                ));
+            }
+
+            let mapped = self
+                .allocation
+                .mapped_ptr()
+                .ok_or(vk_utils::Error::MemoryNotMapped)?;
+
+            Ok(unsafe { mapped.add(offset as usize) })
+        }
+    }
+
+    impl Drop for Buffer {
+        fn drop(&mut self) {
+            // The allocation goes back to the allocator right after
+            unsafe {
+                self.device.destroy_buffer(self.buffer, None);
+            }
+        }
+    }
+
+    // Host visible buffer split into one region per frame in flight.
+    // Each frame hands out aligned chunks of its region (one per draw, say),
+    // which get bound with dynamic offsets instead of a buffer and descriptor set each
+    pub struct RingBuffer {
+        buffer: Buffer,
+        regions: RingRegions,
+    }
+
+    // Offset bookkeeping of a RingBuffer
+    struct RingRegions {
+        alignment: vk::DeviceSize,
+        region_size: vk::DeviceSize,
+        region_count: usize,
+        // Region of the frame being written and the next free byte in it
+        region: usize,
+        head: vk::DeviceSize,
+    }
+
+    impl RingRegions {
+        fn new(
+            region_size: vk::DeviceSize,
+            alignment: vk::DeviceSize,
+            region_count: usize,
+        ) -> Self {
+            Self {
+                alignment,
+                region_size: align_up(region_size, alignment),
+                region_count: region_count.max(1),
+                region: 0,
+                head: 0,
+            }
+        }
+
+        fn buffer_size(&self) -> vk::DeviceSize {
+            self.region_size * self.region_count as vk::DeviceSize
+        }
+
+        fn begin_frame(&mut self, frame_index: usize) {
+            self.region = frame_index % self.region_count;
+            self.head = 0;
+        }
+
+        // Offset of `data_size` bytes in the current region
+        fn reserve(&mut self, data_size: vk::DeviceSize) -> vk_utils::Result<vk::DeviceSize> {
+            if self.head + data_size > self.region_size {
+                return Err(vk_utils::Error::BufferTooSmall(
+                    self.head + data_size,
+                    self.region_size,
+                ));
+            }
+
+            let offset = self.region as vk::DeviceSize * self.region_size + self.head;
+            self.head = align_up(self.head + data_size, self.alignment);
+
+            Ok(offset)
+        }
+    }
+
+    impl RingBuffer {
+        pub fn new(
+            instance: &ash::Instance,
+            device: &ash::Device,
+            physical_device: vk::PhysicalDevice,
+            allocator: &vk_utils::memory::Allocator,
+            usage: vk::BufferUsageFlags,
+            region_size: vk::DeviceSize,
+            region_count: usize,
+        ) -> vk_utils::Result<Self> {
+            // Dynamic offsets have to be multiples of the device's offset alignment
+            let limits = unsafe { instance.get_physical_device_properties(physical_device) }.limits;
+            let mut alignment = 1;
+            if usage.contains(vk::BufferUsageFlags::UNIFORM_BUFFER) {
+                alignment = alignment.max(limits.min_uniform_buffer_offset_alignment);
+            }
+            if usage.contains(vk::BufferUsageFlags::STORAGE_BUFFER) {
+                alignment = alignment.max(limits.min_storage_buffer_offset_alignment);
+            }
+
+            let regions = RingRegions::new(region_size, alignment, region_count);
+            let buffer = create_buffer(
+                device,
+                allocator,
+                regions.buffer_size(),
+                usage,
+                vk::MemoryPropertyFlags::HOST_VISIBLE | vk::MemoryPropertyFlags::HOST_COHERENT,
+            )?;
+
+            Ok(Self { buffer, regions })
+        }
+
+        // Once per frame, after waiting for its fence. Each frame in flight owns the region
+        // of its index, so skipped or repeated updates can't hand out a region still in use
+        pub fn begin_frame(&mut self, frame_index: usize) {
+            self.regions.begin_frame(frame_index);
+        }
+
+        // Copies `data` into the current region, returns the dynamic offset to bind it with
+        pub fn push<T: Copy>(&mut self, data: &[T]) -> vk_utils::Result<u32> {
+            let offset = self
+                .regions
+                .reserve(std::mem::size_of_val(data) as vk::DeviceSize)?;
+            self.buffer.write_at(offset, data)?;
+
+            Ok(offset as u32)
+        }
+
+        pub fn buffer(&self) -> &Buffer {
+            &self.buffer
+        }
+
+        pub fn handle(&self) -> vk::Buffer {
+            self.buffer.handle()
+        }
+
+        pub fn alignment(&self) -> vk::DeviceSize {
+            self.regions.alignment
+        }
+
+        // Bytes each frame can push
+        pub fn region_size(&self) -> vk::DeviceSize {
+            self.regions.region_size
+        }
+    }
+
+    fn align_up(size: vk::DeviceSize, alignment: vk::DeviceSize) -> vk::DeviceSize {
+        size.div_ceil(alignment) * alignment
+    }
+
     pub fn create_buffer(
         device: &ash::Device,
+        allocator: &vk_utils::memory::Allocator,
+        size: vk::DeviceSize,
+        usage: vk::BufferUsageFlags,
+        properties: vk::MemoryPropertyFlags,
+    ) -> vk_utils::Result<Buffer> {
+        create_shared_buffer(device, allocator, size, usage, properties, &[])
+    }
+
+    // Buffer used by queues of all of `queue_families` without ownership transfers.
+    // With fewer than two distinct families it's the same as create_buffer
+    pub fn create_shared_buffer(
+        device: &ash::Device,
+        allocator: &vk_utils::memory::Allocator,
         size: vk::DeviceSize,
         usage: vk::BufferUsageFlags,
         properties: vk::MemoryPropertyFlags,
-        device_memory_properties: &vk::PhysicalDeviceMemoryProperties,
-    ) -> (vk::Buffer, vk::DeviceMemory) {
+        queue_families: &[u32],
+    ) -> vk_utils::Result<Buffer> {
+        let mut queue_families = queue_families.to_vec();
+        queue_families.sort_unstable();
+        queue_families.dedup();
+
         // Abstracting buffer creation
-        let buffer_info = vk::BufferCreateInfo::builder()
-            .size(size)
-            .usage(usage)
-            .sharing_mode(vk::SharingMode::EXCLUSIVE);
+        let buffer_info = if queue_families.len() > 1 {
+            vk::BufferCreateInfo::builder()
+                .size(size)
+                .usage(usage)
+                .sharing_mode(vk::SharingMode::CONCURRENT)
+                .queue_family_indices(&queue_families)
+        } else {
+            vk::BufferCreateInfo::builder()
+                .size(size)
+                .usage(usage)
+                .sharing_mode(vk::SharingMode::EXCLUSIVE)
+        };
         let buffer = unsafe {
             device
                 .create_buffer(&buffer_info, None)
-                .expect("failed to create vertex buffer!")
+                .map_err(vk_utils::Error::vulkan("failed to create buffer!"))?
         };
 
         let mem_requirements = unsafe { device.get_buffer_memory_requirements(buffer) };
-        let alloc_info = vk::MemoryAllocateInfo::builder()
-            .allocation_size(mem_requirements.size)
-            .memory_type_index(find_memory_type(
-                mem_requirements.memory_type_bits,
-                properties,
-                device_memory_properties,
-            ));
-        let buffer_memory = unsafe {
-            device
-                .allocate_memory(&alloc_info, None)
-                .expect("failed to allocate buffer memory!")
+        let allocation = match allocator.allocate(mem_requirements, properties, true) {
+            Ok(allocation) => allocation,
+            Err(err) => {
+                unsafe {
+                    device.destroy_buffer(buffer, None);
+                }
+
+                return Err(err);
+            }
+        };
+        let buffer = Buffer {
+            device: device.clone(),
+            buffer,
+            allocation,
+            size,
         };
 
         unsafe {
             device
-                .bind_buffer_memory(buffer, buffer_memory, 0)
-                .expect("failed to bind buffer memory!");
+                .bind_buffer_memory(
+                    buffer.buffer,
+                    buffer.allocation.memory(),
+                    buffer.allocation.offset(),
+                )
+                .map_err(vk_utils::Error::vulkan("failed to bind buffer memory!"))?;
         }
 
-        (buffer, buffer_memory)
+        Ok(buffer)
     }
 
-    pub fn create_vertex_buffer<T>(
-        instance: &ash::Instance,
+    pub fn create_vertex_buffer<T: Copy>(
         device: &ash::Device,
-        physical_device: vk::PhysicalDevice,
+        allocator: &vk_utils::memory::Allocator,
         command_pool: vk::CommandPool,
         graphics_queue: vk::Queue,
         vertices: &[T],
-    ) -> (vk::Buffer, vk::DeviceMemory) {
+    ) -> vk_utils::Result<Buffer> {
         // Buffer creation
         use std::mem::size_of_val;
 
         // Using a stagin buffer
         let buffer_size = size_of_val(vertices) as vk::DeviceSize;
-        let device_mem_properties =
-            unsafe { instance.get_physical_device_memory_properties(physical_device) };
 
-        let (staging_buffer, staging_buffer_memory) = create_buffer(
+        let staging_buffer = create_buffer(
             device,
+            allocator,
             buffer_size,
             vk::BufferUsageFlags::TRANSFER_SRC,
             vk::MemoryPropertyFlags::HOST_VISIBLE | vk::MemoryPropertyFlags::HOST_COHERENT,
-            &device_mem_properties,
-        );
+        )?;
 
         // Filling the vertex buffer
-        let data = unsafe {
-            device
-                .map_memory(
-                    staging_buffer_memory,
-                    0,
-                    buffer_size,
-                    vk::MemoryMapFlags::empty(),
-                )
-                .expect("failed to map memory!") as *mut T
-        };
+        staging_buffer.write(vertices)?;
 
-        unsafe {
-            data.copy_from_nonoverlapping(vertices.as_ptr(), vertices.len());
-
-            device.unmap_memory(staging_buffer_memory);
-        }
-
-        let (vertex_buffer, vertex_buffer_memory) = create_buffer(
+        let vertex_buffer = create_buffer(
             device,
+            allocator,
             buffer_size,
             vk::BufferUsageFlags::TRANSFER_DST | vk::BufferUsageFlags::VERTEX_BUFFER,
             vk::MemoryPropertyFlags::DEVICE_LOCAL,
-            &device_mem_properties,
-        );
+        )?;
 
         copy_buffer(
             device,
             graphics_queue,
             command_pool,
-            staging_buffer,
-            vertex_buffer,
+            staging_buffer.buffer,
+            vertex_buffer.buffer,
             buffer_size,
-        );
+        )?;
 
-        // Cleaning up staging buffer
-        unsafe {
-            device.destroy_buffer(staging_buffer, None);
-            device.free_memory(staging_buffer_memory, None);
-        }
+        // The staging buffer cleans itself up when dropped
+        Ok(vertex_buffer)
+    }
 
-        (vertex_buffer, vertex_buffer_memory)
+    // Static per-instance data, read through binding 1 of an instanced pipeline.
+    // Data changing every frame can go through a RingBuffer with VERTEX_BUFFER usage instead
+    pub fn create_instance_buffer<T: vk_utils::attributes::InstanceData + Copy>(
+        device: &ash::Device,
+        allocator: &vk_utils::memory::Allocator,
+        command_pool: vk::CommandPool,
+        graphics_queue: vk::Queue,
+        instances: &[T],
+    ) -> vk_utils::Result<Buffer> {
+        // Same usage as vertices, only the input rate of the binding differs
+        create_vertex_buffer(device, allocator, command_pool, graphics_queue, instances)
     }
 
     pub fn create_index_buffer(
-        instance: &ash::Instance,
         device: &ash::Device,
-        physical_device: vk::PhysicalDevice,
+        allocator: &vk_utils::memory::Allocator,
         command_pool: vk::CommandPool,
         graphics_queue: vk::Queue,
         indices: &[u32],
-    ) -> (vk::Buffer, vk::DeviceMemory) {
+    ) -> vk_utils::Result<Buffer> {
         // Index buffer creation
         use std::mem::size_of_val;
 
         let buffer_size = size_of_val(indices) as vk::DeviceSize;
-        let device_mem_properties =
-            unsafe { instance.get_physical_device_memory_properties(physical_device) };
 
-        let (staging_buffer, staging_buffer_memory) = create_buffer(
+        let staging_buffer = create_buffer(
             device,
+            allocator,
             buffer_size,
             vk::BufferUsageFlags::TRANSFER_SRC,
             vk::MemoryPropertyFlags::HOST_VISIBLE | vk::MemoryPropertyFlags::HOST_COHERENT,
-            &device_mem_properties,
-        );
-
-        let data = unsafe {
-            device
-                .map_memory(
-                    staging_buffer_memory,
-                    0,
-                    buffer_size,
-                    vk::MemoryMapFlags::empty(),
-                )
-                .expect("failed to map memory!") as *mut u32
-        };
+        )?;
 
-        unsafe {
-            data.copy_from_nonoverlapping(indices.as_ptr(), indices.len());
-            device.unmap_memory(staging_buffer_memory);
-        }
+        staging_buffer.write(indices)?;
 
-        let (index_buffer, index_buffer_memory) = create_buffer(
+        let index_buffer = create_buffer(
             device,
+            allocator,
             buffer_size,
             vk::BufferUsageFlags::TRANSFER_DST | vk::BufferUsageFlags::INDEX_BUFFER,
             vk::MemoryPropertyFlags::DEVICE_LOCAL,
-            &device_mem_properties,
-        );
+        )?;
 
         copy_buffer(
             device,
             graphics_queue,
             command_pool,
-            staging_buffer,
-            index_buffer,
+            staging_buffer.buffer,
+            index_buffer.buffer,
             buffer_size,
-        );
+        )?;
 
-        unsafe {
-            device.destroy_buffer(staging_buffer, None);
-            device.free_memory(staging_buffer_memory, None);
+        Ok(index_buffer)
+    }
+
+    // Device local buffer for compute shaders to read and write, filled with `data`.
+    // Can be copied from for readback (read_buffer) and bound as vertex input as well.
+    // Shared by the queues of `queue_families` (say compute and graphics), so results
+    // need no ownership transfer. `queue` has to be of one of them
+    pub fn create_storage_buffer<T: Copy>(
+        device: &ash::Device,
+        allocator: &vk_utils::memory::Allocator,
+        command_pool: vk::CommandPool,
+        queue: vk::Queue,
+        queue_families: &[u32],
+        data: &[T],
+    ) -> vk_utils::Result<Buffer> {
+        use std::mem::size_of_val;
+
+        let buffer_size = size_of_val(data) as vk::DeviceSize;
+
+        if buffer_size == 0 {
+            return Err(vk_utils::Error::EmptyBuffer);
         }
 
-        (index_buffer, index_buffer_memory)
+        let staging_buffer = create_buffer(
+            device,
+            allocator,
+            buffer_size,
+            vk::BufferUsageFlags::TRANSFER_SRC,
+            vk::MemoryPropertyFlags::HOST_VISIBLE | vk::MemoryPropertyFlags::HOST_COHERENT,
+        )?;
+
+        staging_buffer.write(data)?;
+
+        let storage_buffer = create_shared_buffer(
+            device,
+            allocator,
+            buffer_size,
+            vk::BufferUsageFlags::TRANSFER_SRC
+                | vk::BufferUsageFlags::TRANSFER_DST
+                | vk::BufferUsageFlags::STORAGE_BUFFER
+                | vk::BufferUsageFlags::VERTEX_BUFFER,
+            vk::MemoryPropertyFlags::DEVICE_LOCAL,
+            queue_families,
+        )?;
+
+        copy_buffer(
+            device,
+            queue,
+            command_pool,
+            staging_buffer.buffer,
+            storage_buffer.buffer,
+            buffer_size,
+        )?;
+
+        Ok(storage_buffer)
+    }
+
+    // Copies the start of a device local buffer back into `data` through a staging buffer.
+    // `data` has to be non-empty and no larger than the buffer
+    pub fn read_buffer<T: Copy>(
+        device: &ash::Device,
+        allocator: &vk_utils::memory::Allocator,
+        command_pool: vk::CommandPool,
+        queue: vk::Queue,
+        buffer: &Buffer,
+        data: &mut [T],
+    ) -> vk_utils::Result<()> {
+        use std::mem::size_of_val;
+
+        let buffer_size = size_of_val(data) as vk::DeviceSize;
+
+        check_copy_size(buffer_size, buffer.size())?;
+
+        let staging_buffer = create_buffer(
+            device,
+            allocator,
+            buffer_size,
+            vk::BufferUsageFlags::TRANSFER_DST,
+            vk::MemoryPropertyFlags::HOST_VISIBLE | vk::MemoryPropertyFlags::HOST_COHERENT,
+        )?;
+
+        copy_buffer(
+            device,
+            queue,
+            command_pool,
+            buffer.buffer,
+            staging_buffer.buffer,
+            buffer_size,
+        )?;
+
+        staging_buffer.read(data)
+    }
+
+    // Copies have to copy something and stay inside the source buffer
+    fn check_copy_size(
+        copy_size: vk::DeviceSize,
+        buffer_size: vk::DeviceSize,
+    ) -> vk_utils::Result<()> {
+        if copy_size == 0 {
+            Err(vk_utils::Error::EmptyBuffer)
+        } else if copy_size > buffer_size {
+            Err(vk_utils::Error::BufferTooSmall(copy_size, buffer_size))
+        } else {
+            Ok(())
+        }
     }
 
     pub fn copy_buffer(
@@ -181,9 +497,9 @@ mod _buffer {
         src_buffer: vk::Buffer,
         dst_buffer: vk::Buffer,
         size: vk::DeviceSize,
-    ) {
+    ) -> vk_utils::Result<()> {
         // Using a staging buffer
-        let command_buffer = crate::command::begin_single_time_commands(device, command_pool);
+        let command_buffer = crate::command::begin_single_time_commands(device, command_pool)?;
 
         let copy_regions = [vk::BufferCopy::builder()
             .src_offset(0) // Optional
@@ -200,24 +516,89 @@ mod _buffer {
             command_pool,
             command_buffer,
             graphics_queue,
-        );
+        )
     }
 
     pub fn find_memory_type(
         type_filter: u32,
         properties: vk::MemoryPropertyFlags,
         mem_properties: &vk::PhysicalDeviceMemoryProperties,
-    ) -> u32 {
+    ) -> vk_utils::Result<u32> {
         for (i, memory_type) in mem_properties.memory_types.iter().enumerate() {
             if (type_filter & (1 << i)) > 0 && memory_type.property_flags.contains(properties) {
-                return i as u32;
+                return Ok(i as u32);
+            }
+        }
+
+        Err(vk_utils::Error::NoSuitableMemoryType)
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        #[test]
+        fn region_size_is_rounded_to_the_alignment() {
+            let regions = RingRegions::new(200, 256, 3);
+
+            assert_eq!(regions.region_size, 256);
+            assert_eq!(regions.buffer_size(), 768);
+            assert_eq!(RingRegions::new(200, 64, 0).buffer_size(), 256);
+        }
+
+        #[test]
+        fn pushes_start_on_aligned_offsets() {
+            let mut regions = RingRegions::new(1024, 256, 2);
+            regions.begin_frame(0);
+
+            assert_eq!(regions.reserve(128).unwrap(), 0);
+            assert_eq!(regions.reserve(1).unwrap(), 256);
+            assert_eq!(regions.reserve(256).unwrap(), 512);
+            assert_eq!(regions.reserve(256).unwrap(), 768);
+            // The region is full, the next one belongs to another frame
+            assert!(matches!(
+                regions.reserve(1),
+                Err(vk_utils::Error::BufferTooSmall(1025, 1024))
+            ));
+        }
+
+        #[test]
+        fn frames_wrap_around_to_their_own_region() {
+            let mut regions = RingRegions::new(256, 256, 3);
+
+            for frame_index in 0..7 {
+                regions.begin_frame(frame_index);
+
+                assert_eq!(
+                    regions.reserve(64).unwrap(),
+                    (frame_index % 3) as vk::DeviceSize * 256
+                );
             }
+
+            // The same frame twice in a row reuses its region from the start
+            regions.begin_frame(1);
+            regions.reserve(64).unwrap();
+            regions.begin_frame(1);
+            assert_eq!(regions.reserve(64).unwrap(), 256);
         }
 
-        panic!("failed to find suitable memory type!");
+        #[test]
+        fn readback_stays_inside_the_buffer() {
+            assert!(check_copy_size(256, 256).is_ok());
+            assert!(check_copy_size(4, 256).is_ok());
+            assert!(matches!(
+                check_copy_size(260, 256),
+                Err(vk_utils::Error::BufferTooSmall(260, 256))
+            ));
+            assert!(matches!(
+                check_copy_size(0, 256),
+                Err(vk_utils::Error::EmptyBuffer)
+            ));
+        }
     }
 }
 
 pub use _buffer::{
-    copy_buffer, create_buffer, create_index_buffer, create_vertex_buffer, find_memory_type,
+    copy_buffer, create_buffer, create_index_buffer, create_instance_buffer, create_shared_buffer,
+    create_storage_buffer, create_vertex_buffer, find_memory_type, read_buffer, Buffer, RingBuffer,
 };
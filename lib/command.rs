@@ -1,73 +1,117 @@
 mod _command {
-    use crate::{self as vk_utils, constants::hello_triangle};
+    use crate as vk_utils;
 
     use ash::vk;
 
     pub fn create_command_pool(
         device: &ash::Device,
         queue_families: &vk_utils::QueueFamilyIndices,
-    ) -> vk::CommandPool {
+    ) -> vk_utils::Result<vk::CommandPool> {
+        create_command_pool_for_family(device, queue_families.graphics_family.unwrap())
+    }
+
+    // Command buffers from the pool can only be submitted to queues of this family
+    pub fn create_command_pool_for_family(
+        device: &ash::Device,
+        queue_family_index: u32,
+    ) -> vk_utils::Result<vk::CommandPool> {
         // Command pools
         let pool_info = vk::CommandPoolCreateInfo::builder()
             .flags(vk::CommandPoolCreateFlags::RESET_COMMAND_BUFFER)
-            .queue_family_index(queue_families.graphics_family.unwrap());
+            .queue_family_index(queue_family_index);
 
         unsafe {
             device
                 .create_command_pool(&pool_info, None)
-                .expect("failed to create command pool!")
+                .map_err(vk_utils::Error::vulkan("failed to create command pool!"))
         }
     }
 
-    pub fn create_command_buffers(
-        device: &ash::Device,
+    // One pool per frame in flight: resetting the pool is cheaper than
+    // resetting command buffers one by one, and nothing recorded outlives a frame
+    pub struct FrameCommandPool {
+        device: ash::Device,
         command_pool: vk::CommandPool,
-        graphics_pipeline: vk::Pipeline,
-        framebuffers: &Vec<vk::Framebuffer>,
-        render_pass: vk::RenderPass,
-        surface_extent: vk::Extent2D,
-        vertex_buffer: vk::Buffer,
-        index_buffer: vk::Buffer,
-        pipeline_layout: vk::PipelineLayout,
-        descriptor_sets: &Vec<vk::DescriptorSet>,
-        indices: &[u32],
-    ) -> Vec<vk::CommandBuffer> {
-        // Command buffer allocation
-        let command_buffer_allocate_info = vk::CommandBufferAllocateInfo::builder()
-            .command_buffer_count(framebuffers.len() as u32)
-            .command_pool(command_pool)
-            .level(vk::CommandBufferLevel::PRIMARY);
+        command_buffer: vk::CommandBuffer,
+    }
 
-        let command_buffers = unsafe {
-            device
-                .allocate_command_buffers(&command_buffer_allocate_info)
-                .expect("failed to allocate command buffers!")
-        };
-
-        for (image_index, &command_buffer) in command_buffers.iter().enumerate() {
-            record_command_buffer(
-                device,
-                command_buffer,
-                image_index as u32,
-                render_pass,
-                graphics_pipeline,
-                framebuffers,
-                surface_extent,
-                vertex_buffer,
-                index_buffer,
-                pipeline_layout,
-                descriptor_sets,
-                indices,
-            );
+    impl FrameCommandPool {
+        pub fn new(device: &ash::Device, queue_family_index: u32) -> vk_utils::Result<Self> {
+            let pool_info = vk::CommandPoolCreateInfo::builder()
+                .flags(vk::CommandPoolCreateFlags::TRANSIENT)
+                .queue_family_index(queue_family_index);
+            let command_pool = unsafe {
+                device
+                    .create_command_pool(&pool_info, None)
+                    .map_err(vk_utils::Error::vulkan("failed to create command pool!"))?
+            };
+            // Owns the pool from here on, so it gets destroyed on errors
+            let mut frame_pool = Self {
+                device: device.clone(),
+                command_pool,
+                command_buffer: vk::CommandBuffer::null(),
+            };
+
+            let command_buffer_allocate_info = vk::CommandBufferAllocateInfo::builder()
+                .command_buffer_count(1)
+                .command_pool(command_pool)
+                .level(vk::CommandBufferLevel::PRIMARY);
+            frame_pool.command_buffer = unsafe {
+                device
+                    .allocate_command_buffers(&command_buffer_allocate_info)
+                    .map_err(vk_utils::Error::vulkan(
+                        "failed to allocate command buffers!",
+                    ))?
+            }[0];
+
+            Ok(frame_pool)
+        }
+
+        // The previous submission from this pool has to be done (its fence waited on)
+        pub fn record<F>(&self, record_commands: F) -> vk_utils::Result<vk::CommandBuffer>
+        where
+            F: FnOnce(vk::CommandBuffer) -> vk_utils::Result<()>,
+        {
+            let begin_info = vk::CommandBufferBeginInfo::builder()
+                .flags(vk::CommandBufferUsageFlags::ONE_TIME_SUBMIT);
+
+            unsafe {
+                self.device
+                    .reset_command_pool(self.command_pool, vk::CommandPoolResetFlags::empty())
+                    .map_err(vk_utils::Error::vulkan("failed to reset command pool!"))?;
+                self.device
+                    .begin_command_buffer(self.command_buffer, &begin_info)
+                    .map_err(vk_utils::Error::vulkan(
+                        "failed to begin recording command buffer!",
+                    ))?;
+            }
+
+            // Left recording on errors, the next reset of the pool takes care of it
+            record_commands(self.command_buffer)?;
+
+            unsafe {
+                self.device
+                    .end_command_buffer(self.command_buffer)
+                    .map_err(vk_utils::Error::vulkan("failed to record command buffer!"))?;
+            }
+
+            Ok(self.command_buffer)
         }
+    }
 
-        command_buffers
+    impl Drop for FrameCommandPool {
+        fn drop(&mut self) {
+            // Command buffers are freed along with their pool
+            unsafe {
+                self.device.destroy_command_pool(self.command_pool, None);
+            }
+        }
     }
 
     pub fn begin_single_time_commands(
         device: &ash::Device,
         command_pool: vk::CommandPool,
-    ) -> vk::CommandBuffer {
+    ) -> vk_utils::Result<vk::CommandBuffer> {
         // Layout transitions
         let alloc_info = vk::CommandBufferAllocateInfo::builder()
             .level(vk::CommandBufferLevel::PRIMARY)
@@ -77,7 +121,9 @@ mod _command {
         let command_buffer = unsafe {
             device
                 .allocate_command_buffers(&alloc_info)
-                .expect("failed to allocate command buffers!")
+                .map_err(vk_utils::Error::vulkan(
+                    "failed to allocate command buffers!",
+                ))?
         }[0];
 
         let begin_info = vk::CommandBufferBeginInfo::builder()
@@ -86,10 +132,10 @@ mod _command {
         unsafe {
             device
                 .begin_command_buffer(command_buffer, &begin_info)
-                .expect("failed to begin command buffer!");
+                .map_err(vk_utils::Error::vulkan("failed to begin command buffer!"))?;
         }
 
-        command_buffer
+        Ok(command_buffer)
     }
 
     pub fn end_single_time_commands(
@@ -97,11 +143,11 @@ mod _command {
         command_pool: vk::CommandPool,
         command_buffer: vk::CommandBuffer,
         graphics_queue: vk::Queue,
-    ) {
+    ) -> vk_utils::Result<()> {
         unsafe {
             device
                 .end_command_buffer(command_buffer)
-                .expect("failed to end command buffer!");
+                .map_err(vk_utils::Error::vulkan("failed to end command buffer!"))?;
         }
 
         let command_buffers = [command_buffer];
@@ -112,136 +158,18 @@ mod _command {
         unsafe {
             device
                 .queue_submit(graphics_queue, &submit_info, vk::Fence::null())
-                .expect("failed to submit queue!");
+                .map_err(vk_utils::Error::vulkan("failed to submit queue!"))?;
             device
                 .queue_wait_idle(graphics_queue)
-                .expect("queue failed to wait idle!");
+                .map_err(vk_utils::Error::vulkan("queue failed to wait idle!"))?;
             device.free_command_buffers(command_pool, &command_buffers);
         }
-    }
 
-    pub fn record_command_buffer(
-        device: &ash::Device,
-        command_buffer: vk::CommandBuffer,
-        image_index: u32,
-        render_pass: vk::RenderPass,
-        graphics_pipeline: vk::Pipeline,
-        framebuffers: &Vec<vk::Framebuffer>,
-        swapchain_extent: vk::Extent2D,
-        vertex_buffer: vk::Buffer,
-        index_buffer: vk::Buffer,
-        pipeline_layout: vk::PipelineLayout,
-        descriptor_sets: &Vec<vk::DescriptorSet>,
-        indices: &[u32],
-    ) {
-        // Command buffer recording
-        let begin_info = vk::CommandBufferBeginInfo::builder()
-            .flags(vk::CommandBufferUsageFlags::SIMULTANEOUS_USE);
-
-        unsafe {
-            device
-                .begin_command_buffer(command_buffer, &begin_info)
-                .expect("failed to begin recording command buffer!");
-        }
-
-        // Starting a render pass
-        // Clear values (Depth buffering)
-        let clear_values = [
-            vk::ClearValue {
-                color: vk::ClearColorValue {
-                    float32: [0.0, 0.0, 0.0, 1.0],
-                },
-            },
-            vk::ClearValue {
-                depth_stencil: vk::ClearDepthStencilValue {
-                    depth: 1.0,
-                    stencil: 0,
-                },
-            },
-        ];
-
-        let render_pass_info = vk::RenderPassBeginInfo::builder()
-            .render_pass(render_pass)
-            .framebuffer(framebuffers[image_index as usize])
-            .render_area(vk::Rect2D {
-                offset: vk::Offset2D { x: 0, y: 0 },
-                extent: swapchain_extent,
-            })
-            .clear_values(&clear_values);
-
-        unsafe {
-            device.cmd_begin_render_pass(
-                command_buffer,
-                &render_pass_info,
-                vk::SubpassContents::INLINE,
-            );
-        }
-
-        // Basic drawing commands
-        unsafe {
-            device.cmd_bind_pipeline(
-                command_buffer,
-                vk::PipelineBindPoint::GRAPHICS,
-                graphics_pipeline,
-            );
-        }
-
-        let viewports = [vk::Viewport::builder()
-            .x(0.0)
-            .y(0.0)
-            .width(swapchain_extent.width as f32)
-            .height(swapchain_extent.height as f32)
-            .min_depth(0.0)
-            .max_depth(1.0)
-            .build()];
-        let scissors = [vk::Rect2D::builder()
-            .offset(vk::Offset2D { x: 0, y: 0 })
-            .extent(swapchain_extent)
-            .build()];
-
-        unsafe {
-            device.cmd_set_viewport(command_buffer, 0, &viewports);
-            device.cmd_set_scissor(command_buffer, 0, &scissors);
-        }
-
-        // Binding the vertex buffer
-        let vertex_buffers = [vertex_buffer];
-        let offsets = [0_u64];
-
-        unsafe {
-            device.cmd_bind_vertex_buffers(command_buffer, 0, &vertex_buffers, &offsets);
-            // Using an index buffer
-            device.cmd_bind_index_buffer(command_buffer, index_buffer, 0, vk::IndexType::UINT32);
-        }
-
-        // Using descriptor sets
-        let descriptor_sets_to_bind = [descriptor_sets[image_index as usize]];
-        unsafe {
-            device.cmd_bind_descriptor_sets(
-                command_buffer,
-                vk::PipelineBindPoint::GRAPHICS,
-                pipeline_layout,
-                0,
-                &descriptor_sets_to_bind,
-                &[],
-            );
-        }
-
-        unsafe {
-            device.cmd_draw_indexed(command_buffer, indices.len() as u32, 1, 0, 0, 0);
-        }
-
-        // Finishing up
-        unsafe {
-            device.cmd_end_render_pass(command_buffer);
-            device
-                .end_command_buffer(command_buffer)
-                .expect("failed to record command buffer!");
-        }
+        Ok(())
     }
 }
 
 pub use _command::{
-    begin_single_time_commands, create_command_buffers, create_command_pool,
-    end_single_time_commands, record_command_buffer,
+    begin_single_time_commands, create_command_pool, create_command_pool_for_family,
+    end_single_time_commands, FrameCommandPool,
 };
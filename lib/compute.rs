@@ -0,0 +1,471 @@
+mod _compute {
+    use crate::{
+        self as vk_utils,
+        buffer::Buffer,
+        reflection::{DescriptorBinding, ShaderReflection},
+    };
+
+    use ash::vk;
+    use std::path::Path;
+
+    // Compute pipeline along with the descriptor set layouts its shader declares
+    // and a pool to allocate sets of those layouts from
+    pub struct ComputePipeline {
+        device: ash::Device,
+        pipeline: vk::Pipeline,
+        layout: vk::PipelineLayout,
+        set_layouts: Vec<vk::DescriptorSetLayout>,
+        descriptor_pool: vk::DescriptorPool,
+        bindings: Vec<DescriptorBinding>,
+        push_constant_ranges: Vec<vk::PushConstantRange>,
+    }
+
+    impl ComputePipeline {
+        // Up to `max_sets` descriptor sets of each layout can be created
+        pub fn new(
+            device: &ash::Device,
+            source_path: &Path,
+            pipeline_cache: vk::PipelineCache,
+            max_sets: u32,
+        ) -> vk_utils::Result<Self> {
+            let code = vk_utils::shader::load_shader(source_path)?;
+            let reflection = ShaderReflection::new(&code)?;
+            let reflections = std::slice::from_ref(&reflection);
+
+            if reflection.stage != vk::ShaderStageFlags::COMPUTE {
+                return Err(vk_utils::Error::ShaderInterfaceMismatch(format!(
+                    "{} is not a compute shader",
+                    source_path.display()
+                )));
+            }
+
+            // Owns everything from here on, so Drop cleans up after failures
+            let mut compute_pipeline = Self {
+                device: device.clone(),
+                pipeline: vk::Pipeline::null(),
+                layout: vk::PipelineLayout::null(),
+                set_layouts: vec![],
+                descriptor_pool: vk::DescriptorPool::null(),
+                bindings: vk_utils::reflection::merge_descriptor_bindings(reflections)?,
+                push_constant_ranges: vk_utils::reflection::merge_push_constant_ranges(reflections),
+            };
+
+            compute_pipeline.set_layouts =
+                vk_utils::reflection::create_descriptor_set_layouts(device, reflections)?;
+            compute_pipeline.layout = vk_utils::reflection::create_pipeline_layout(
+                device,
+                &compute_pipeline.set_layouts,
+                &compute_pipeline.push_constant_ranges,
+            )?;
+
+            if !compute_pipeline.bindings.is_empty() {
+                let pool_sizes = compute_pipeline
+                    .bindings
+                    .iter()
+                    .map(|binding| {
+                        vk::DescriptorPoolSize::builder()
+                            .ty(binding.descriptor_type)
+                            .descriptor_count(binding.count * max_sets)
+                            .build()
+                    })
+                    .collect::<Vec<_>>();
+                let pool_info = vk::DescriptorPoolCreateInfo::builder()
+                    .pool_sizes(&pool_sizes)
+                    .max_sets(max_sets * compute_pipeline.set_layouts.len() as u32);
+
+                compute_pipeline.descriptor_pool = unsafe {
+                    device
+                        .create_descriptor_pool(&pool_info, None)
+                        .map_err(vk_utils::Error::vulkan("failed to create descriptor pool!"))?
+                };
+            }
+
+            let shader_module = vk_utils::pipeline::create_shader_module(device, &code)?;
+            let main_function_name = std::ffi::CString::new("main").unwrap();
+            let stage = vk::PipelineShaderStageCreateInfo::builder()
+                .stage(vk::ShaderStageFlags::COMPUTE)
+                .module(shader_module)
+                .name(&main_function_name)
+                .build();
+            let pipeline_infos = [vk::ComputePipelineCreateInfo::builder()
+                .stage(stage)
+                .layout(compute_pipeline.layout)
+                .build()];
+
+            let result =
+                unsafe { device.create_compute_pipelines(pipeline_cache, &pipeline_infos, None) };
+
+            unsafe {
+                device.destroy_shader_module(shader_module, None);
+            }
+
+            compute_pipeline.pipeline = match result {
+                Ok(pipelines) => pipelines[0],
+                Err((_, vk_result)) => {
+                    return Err(vk_utils::Error::Vulkan(
+                        "failed to create compute pipeline!",
+                        vk_result,
+                    ))
+                }
+            };
+
+            Ok(compute_pipeline)
+        }
+
+        pub fn handle(&self) -> vk::Pipeline {
+            self.pipeline
+        }
+
+        pub fn layout(&self) -> vk::PipelineLayout {
+            self.layout
+        }
+
+        // Buffers by binding number, written with the descriptor types the shader declares
+        // (storage buffers, uniform buffers). Freed along with the pipeline
+        pub fn create_descriptor_set(
+            &self,
+            set: u32,
+            buffers: &[(u32, &Buffer)],
+        ) -> vk_utils::Result<vk::DescriptorSet> {
+            let set_layouts = [*self.set_layouts.get(set as usize).ok_or_else(|| {
+                vk_utils::Error::ShaderInterfaceMismatch(format!(
+                    "the compute shader declares no descriptor set {}",
+                    set
+                ))
+            })?];
+
+            let mut descriptor_types = vec![];
+            for &(binding, _) in buffers.iter() {
+                let descriptor_binding = self
+                    .bindings
+                    .iter()
+                    .find(|descriptor_binding| {
+                        (descriptor_binding.set, descriptor_binding.binding) == (set, binding)
+                    })
+                    .ok_or_else(|| {
+                        vk_utils::Error::ShaderInterfaceMismatch(format!(
+                            "the compute shader declares no binding {} in set {}",
+                            binding, set
+                        ))
+                    })?;
+
+                descriptor_types.push(descriptor_binding.descriptor_type);
+            }
+
+            let alloc_info = vk::DescriptorSetAllocateInfo::builder()
+                .descriptor_pool(self.descriptor_pool)
+                .set_layouts(&set_layouts);
+            let descriptor_set = unsafe {
+                self.device.allocate_descriptor_sets(&alloc_info).map_err(
+                    vk_utils::Error::vulkan("failed to allocate descriptor sets!"),
+                )?
+            }[0];
+
+            // Kept alive until the descriptors are updated
+            let buffer_infos = buffers
+                .iter()
+                .map(|(_, buffer)| {
+                    [vk::DescriptorBufferInfo::builder()
+                        .buffer(buffer.handle())
+                        .offset(0)
+                        .range(vk::WHOLE_SIZE)
+                        .build()]
+                })
+                .collect::<Vec<_>>();
+            let descriptor_writes = buffers
+                .iter()
+                .zip(descriptor_types.iter())
+                .zip(buffer_infos.iter())
+                .map(|((&(binding, _), &descriptor_type), buffer_info)| {
+                    vk::WriteDescriptorSet::builder()
+                        .dst_set(descriptor_set)
+                        .dst_binding(binding)
+                        .dst_array_element(0)
+                        .descriptor_type(descriptor_type)
+                        .buffer_info(buffer_info)
+                        .build()
+                })
+                .collect::<Vec<_>>();
+
+            unsafe {
+                self.device.update_descriptor_sets(&descriptor_writes, &[]);
+            }
+
+            Ok(descriptor_set)
+        }
+
+        // Same as Pipeline::push_constants, only the compute stage reads them
+        pub fn push_constants<T: Copy>(
+            &self,
+            command_buffer: vk::CommandBuffer,
+            offset: u32,
+            data: &T,
+        ) -> vk_utils::Result<()> {
+            let size = std::mem::size_of::<T>() as u32;
+            let is_covered = self
+                .push_constant_ranges
+                .iter()
+                .any(|range| range.offset <= offset && offset + size <= range.offset + range.size);
+
+            if !is_covered {
+                return Err(vk_utils::Error::ShaderInterfaceMismatch(format!(
+                    "no push constant range covers bytes {} to {}",
+                    offset,
+                    offset + size
+                )));
+            }
+
+            unsafe {
+                let bytes =
+                    std::slice::from_raw_parts(data as *const T as *const u8, size as usize);
+
+                self.device.cmd_push_constants(
+                    command_buffer,
+                    self.layout,
+                    vk::ShaderStageFlags::COMPUTE,
+                    offset,
+                    bytes,
+                );
+            }
+
+            Ok(())
+        }
+
+        // Records the dispatch of `group_counts` workgroups along x, y and z
+        pub fn dispatch(
+            &self,
+            command_buffer: vk::CommandBuffer,
+            descriptor_sets: &[vk::DescriptorSet],
+            group_counts: [u32; 3],
+        ) {
+            let [x, y, z] = group_counts;
+
+            unsafe {
+                self.device.cmd_bind_pipeline(
+                    command_buffer,
+                    vk::PipelineBindPoint::COMPUTE,
+                    self.pipeline,
+                );
+                if !descriptor_sets.is_empty() {
+                    self.device.cmd_bind_descriptor_sets(
+                        command_buffer,
+                        vk::PipelineBindPoint::COMPUTE,
+                        self.layout,
+                        0,
+                        descriptor_sets,
+                        &[],
+                    );
+                }
+                self.device.cmd_dispatch(command_buffer, x, y, z);
+            }
+        }
+    }
+
+    impl Drop for ComputePipeline {
+        fn drop(&mut self) {
+            unsafe {
+                self.device.destroy_pipeline(self.pipeline, None);
+                self.device.destroy_pipeline_layout(self.layout, None);
+                // Frees the descriptor sets allocated from it too
+                self.device
+                    .destroy_descriptor_pool(self.descriptor_pool, None);
+                for &set_layout in self.set_layouts.iter() {
+                    self.device.destroy_descriptor_set_layout(set_layout, None);
+                }
+            }
+        }
+    }
+
+    // Submits whatever `record_commands` dispatches (push constants, ComputePipeline::dispatch)
+    // on `compute_queue` with a pool of its family, and waits for it.
+    // Afterwards the results can be read from mapped buffers or copied back (buffer::read_buffer)
+    pub fn dispatch_and_wait<F>(
+        device: &ash::Device,
+        command_pool: vk::CommandPool,
+        compute_queue: vk::Queue,
+        record_commands: F,
+    ) -> vk_utils::Result<()>
+    where
+        F: FnOnce(vk::CommandBuffer) -> vk_utils::Result<()>,
+    {
+        let command_buffer = vk_utils::command::begin_single_time_commands(device, command_pool)?;
+
+        if let Err(err) = record_commands(command_buffer) {
+            unsafe {
+                device.free_command_buffers(command_pool, &[command_buffer]);
+            }
+
+            return Err(err);
+        }
+
+        // Shader writes have to be made visible to the host and to copies
+        let memory_barriers = [vk::MemoryBarrier::builder()
+            .src_access_mask(vk::AccessFlags::SHADER_WRITE)
+            .dst_access_mask(vk::AccessFlags::HOST_READ | vk::AccessFlags::TRANSFER_READ)
+            .build()];
+
+        unsafe {
+            device.cmd_pipeline_barrier(
+                command_buffer,
+                vk::PipelineStageFlags::COMPUTE_SHADER,
+                vk::PipelineStageFlags::HOST | vk::PipelineStageFlags::TRANSFER,
+                vk::DependencyFlags::empty(),
+                &memory_barriers,
+                &[],
+                &[],
+            );
+        }
+
+        vk_utils::command::end_single_time_commands(
+            device,
+            command_pool,
+            command_buffer,
+            compute_queue,
+        )
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use crate::{
+            constants::shader::{DOUBLE_COMP, DOUBLE_WORKGROUP_SIZE},
+            renderer::{RenderContext, Renderer, RendererOptions, Scene},
+        };
+        use ash::Device;
+
+        const VALUES: u32 = 100; // not a multiple of the workgroup size
+
+        #[test]
+        fn doubling_shader_interface() {
+            let code = vk_utils::shader::load_shader(Path::new(DOUBLE_COMP)).unwrap();
+            let reflection = ShaderReflection::new(&code).unwrap();
+
+            assert_eq!(reflection.stage, vk::ShaderStageFlags::COMPUTE);
+            assert_eq!(
+                reflection
+                    .descriptor_bindings
+                    .iter()
+                    .map(|binding| (binding.set, binding.binding, binding.descriptor_type))
+                    .collect::<Vec<_>>(),
+                vec![(0, 0, vk::DescriptorType::STORAGE_BUFFER)]
+            );
+            assert_eq!(reflection.push_constant_ranges[0].size, 4);
+        }
+
+        // Runs the compute round trip once the renderer hands out its queues
+        #[derive(Default)]
+        struct DoublingScene {
+            output: Vec<u32>,
+        }
+
+        impl Scene for DoublingScene {
+            type FrameData = ();
+
+            fn application_name(&self) -> &str {
+                "compute test"
+            }
+
+            fn application_version(&self) -> u32 {
+                0
+            }
+
+            fn create_resources(&mut self, context: &RenderContext) -> vk_utils::Result<()> {
+                let input = (0..VALUES).collect::<Vec<_>>();
+                let pipeline = ComputePipeline::new(
+                    context.device,
+                    Path::new(DOUBLE_COMP),
+                    context.pipeline_cache,
+                    1,
+                )?;
+                // Written on the compute queue, read back on the graphics queue
+                let buffer = vk_utils::buffer::create_storage_buffer(
+                    context.device,
+                    context.allocator,
+                    context.compute_command_pool,
+                    context.compute_queue,
+                    &[context.compute_family, context.graphics_family],
+                    &input,
+                )?;
+                let descriptor_set = pipeline.create_descriptor_set(0, &[(0, &buffer)])?;
+
+                dispatch_and_wait(
+                    context.device,
+                    context.compute_command_pool,
+                    context.compute_queue,
+                    |command_buffer| {
+                        pipeline.push_constants(command_buffer, 0, &VALUES)?;
+                        pipeline.dispatch(
+                            command_buffer,
+                            &[descriptor_set],
+                            [VALUES.div_ceil(DOUBLE_WORKGROUP_SIZE), 1, 1],
+                        );
+
+                        Ok(())
+                    },
+                )?;
+
+                self.output = vec![0; VALUES as usize];
+                vk_utils::buffer::read_buffer(
+                    context.device,
+                    context.allocator,
+                    context.command_pool,
+                    context.graphics_queue,
+                    &buffer,
+                    &mut self.output,
+                )
+            }
+
+            fn create_frame_data(&mut self, _context: &RenderContext) -> vk_utils::Result<()> {
+                Ok(())
+            }
+
+            fn create_pipeline(&mut self, _context: &RenderContext) -> vk_utils::Result<()> {
+                Ok(())
+            }
+
+            fn update(
+                &mut self,
+                _device: &Device,
+                _frame: &mut (),
+                _frame_index: usize,
+                _delta_time: f32,
+            ) -> vk_utils::Result<()> {
+                Ok(())
+            }
+
+            fn record_commands(
+                &self,
+                _device: &Device,
+                _command_buffer: vk::CommandBuffer,
+                _frame: &(),
+            ) -> vk_utils::Result<()> {
+                Ok(())
+            }
+
+            fn destroy_pipeline(&mut self, _device: &Device) {}
+            fn destroy_resources(&mut self, _device: &Device) {}
+        }
+
+        #[test]
+        #[ignore = "requires a Vulkan device"]
+        fn doubles_a_storage_buffer() {
+            let app = Renderer::new_headless(
+                vk::Extent2D {
+                    width: 64,
+                    height: 64,
+                },
+                vk_utils::constants::offscreen::COLOR_FORMAT,
+                DoublingScene::default(),
+                RendererOptions::default(),
+            )
+            .unwrap_or_else(|err| panic!("{}", err));
+
+            assert_eq!(
+                app.scene().output,
+                (0..VALUES).map(|value| value * 2).collect::<Vec<_>>()
+            );
+        }
+    }
+}
+
+pub use _compute::{dispatch_and_wait, ComputePipeline};
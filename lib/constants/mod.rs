@@ -12,7 +12,8 @@ mod _constants {
     pub const ENGINE_NAME: &str = "No Engine";
     pub const ENGINE_VERSION: u32 = make_api_version(0, 1, 0, 0);
 
-    pub const MAX_FRAMES_IN_FLIGHT: usize = 2;
+    // Frames the CPU may record ahead of the GPU, unless the renderer is told otherwise
+    pub const DEFAULT_FRAMES_IN_FLIGHT: usize = 2;
 
     pub const VK_VALIDATION_LAYER_NAMES: VkValidationInfo = VkValidationInfo {
         is_enable: true,
@@ -31,7 +32,7 @@ pub use _constants::{HEIGHT, WIDTH};
 // minimal/maximum window size
 pub use _constants::{MAXIMUM_HEIGHT, MAXIMUM_WIDTH, MINIMAL_HEIGHT, MINIMAL_WIDTH};
 
-pub use _constants::MAX_FRAMES_IN_FLIGHT;
+pub use _constants::DEFAULT_FRAMES_IN_FLIGHT;
 
 pub use _constants::{
     VK_DEVICE_EXTENSIONS,      // vulkan device extensions
@@ -88,7 +89,7 @@ pub mod texture {
     pub const APPLICATION_VERSION: u32 = // app version
         ash::vk::make_api_version(0, 2, 1, 0);
 
-    pub const TEXTURE_PATH: &'static str = "assets/texture.jpg";
+    pub const TEXTURE_PATH: &str = "assets/texture.jpg";
 
     pub const INDICES: [u32; 12] = [0, 1, 2, 2, 3, 0, 4, 5, 6, 6, 7, 4];
 
@@ -99,21 +100,25 @@ pub mod texture {
             pos: [-0.5, -0.5, 0.0, 1.0],
             color: [1.0, 0.0, 0.0],
             tex_coord: [0.0, 1.0],
+            normal: [0.0, 0.0, 1.0],
         },
         crate::types::VertexWithTexture3D {
             pos: [0.5, -0.5, 0.0, 1.0],
             color: [0.0, 1.0, 0.0],
             tex_coord: [0.0, 0.0],
+            normal: [0.0, 0.0, 1.0],
         },
         crate::types::VertexWithTexture3D {
             pos: [0.5, 0.5, 0.0, 1.0],
             color: [0.0, 0.0, 1.0],
             tex_coord: [1.0, 0.0],
+            normal: [0.0, 0.0, 1.0],
         },
         crate::types::VertexWithTexture3D {
             pos: [-0.5, 0.5, 0.0, 1.0],
             color: [1.0, 1.0, 1.0],
             tex_coord: [1.0, 1.0],
+            normal: [0.0, 0.0, 1.0],
         },
     ];
 
@@ -122,41 +127,49 @@ pub mod texture {
             pos: [-0.5, -0.5, 0.0, 1.0],
             color: [1.0, 0.0, 0.0],
             tex_coord: [0.0, 0.0],
+            normal: [0.0, 0.0, 1.0],
         },
         crate::types::VertexWithTexture3D {
             pos: [0.5, -0.5, 0.0, 1.0],
             color: [0.0, 1.0, 0.0],
             tex_coord: [1.0, 0.0],
+            normal: [0.0, 0.0, 1.0],
         },
         crate::types::VertexWithTexture3D {
             pos: [0.5, 0.5, 0.0, 1.0],
             color: [0.0, 0.0, 1.0],
             tex_coord: [1.0, 1.0],
+            normal: [0.0, 0.0, 1.0],
         },
         crate::types::VertexWithTexture3D {
             pos: [-0.5, 0.5, 0.0, 1.0],
             color: [1.0, 1.0, 1.0],
             tex_coord: [0.0, 1.0],
+            normal: [0.0, 0.0, 1.0],
         },
         crate::types::VertexWithTexture3D {
             pos: [-0.5, -0.5, -0.5, 1.0],
             color: [1.0, 0.0, 0.0],
             tex_coord: [0.0, 0.0],
+            normal: [0.0, 0.0, 1.0],
         },
         crate::types::VertexWithTexture3D {
             pos: [0.5, -0.5, -0.5, 1.0],
             color: [0.0, 1.0, 0.0],
             tex_coord: [1.0, 0.0],
+            normal: [0.0, 0.0, 1.0],
         },
         crate::types::VertexWithTexture3D {
             pos: [0.5, 0.5, -0.5, 1.0],
             color: [0.0, 0.0, 1.0],
             tex_coord: [1.0, 1.0],
+            normal: [0.0, 0.0, 1.0],
         },
         crate::types::VertexWithTexture3D {
             pos: [-0.5, 0.5, -0.5, 1.0],
             color: [1.0, 1.0, 1.0],
             tex_coord: [0.0, 1.0],
+            normal: [0.0, 0.0, 1.0],
         },
     ];
 }
@@ -167,6 +180,44 @@ pub mod model {
     pub const APPLICATION_VERSION: u32 = // app version
         ash::vk::make_api_version(0, 3, 1, 0);
 
-    pub const MODEL_PATH: &'static str = "assets/viking_room.obj";
-    pub const TEXTURE_PATH: &'static str = "assets/viking_room.png";
+    pub const MODEL_PATH: &str = "assets/viking_room.obj";
+    pub const TEXTURE_PATH: &str = "assets/viking_room.png";
+}
+
+pub mod offscreen {
+    pub const COLOR_FORMAT: ash::vk::Format = ash::vk::Format::R8G8B8A8_SRGB;
+
+    pub const OUTPUT_DIR: &str = "results/headless";
+    pub const FRAME_COUNT: u32 = 10;
+    pub const FRAME_DELTA_TIME: f32 = 1.0 / 60.0; // fixed time step for reproducible frames
+}
+
+pub mod memory {
+    pub const BLOCK_SIZE: ash::vk::DeviceSize = 64 * 1024 * 1024; // 64 MiB per memory block
+    pub const MIN_BLOCKS_PER_HEAP: ash::vk::DeviceSize = 8; // keeps blocks small on tiny heaps
+}
+
+pub mod shader {
+    pub const SOURCE_DIR: &str = "shaders/src";
+    pub const CACHE_DIR: &str = "shaders/spv/cache"; // compiled SPIR-V keyed by source hash
+    pub const CACHE_VERSION: u32 = 1; // bump when cached SPIR-V changes for the same source
+    pub const NAGA_VERSION: &str = "24.0.0"; // pinned in Cargo.toml, part of the cache key
+
+    pub const HELLO_TRIANGLE_VERT: &str = "shaders/src/hello-triangle.vert";
+    pub const HELLO_TRIANGLE_FRAG: &str = "shaders/src/hello-triangle.frag";
+    pub const VERTEX_COLOR_VERT: &str = "shaders/src/vertex-color.vert"; // Vertex2D, untextured
+    pub const VERTEX_COLOR_FRAG: &str = "shaders/src/vertex-color.frag";
+    pub const DOUBLE_COMP: &str = "shaders/src/double.comp"; // doubles a storage buffer of uints
+    pub const DOUBLE_WORKGROUP_SIZE: u32 = 64; // local_size_x of DOUBLE_COMP
+
+    pub const WATCH_INTERVAL: std::time::Duration = std::time::Duration::from_millis(500);
+}
+
+pub mod pipeline {
+    pub const CACHE_PATH: &str = "cache/pipeline_cache.bin"; // reused between runs
+}
+
+pub mod loader {
+    pub const WORKER_COUNT: usize = 2; // decoding threads, uploads stay on the render thread
+    pub const PLACEHOLDER_TEXTURE_SIZE: u32 = 8; // checkerboard shown until the texture arrives
 }
@@ -8,60 +8,72 @@ mod _physical_dev {
 
     pub fn pick_physical_device(
         instance: &Instance,
-        surface_info: &vk_utils::VkSurfaceInfo,
-    ) -> vk::PhysicalDevice {
+        surface_info: Option<&vk_utils::VkSurfaceInfo>,
+    ) -> vk_utils::Result<vk::PhysicalDevice> {
         let physical_devices = unsafe {
             instance
                 .enumerate_physical_devices()
-                .expect("failed to find GPUs with Vulkan support!")
+                .map_err(vk_utils::Error::vulkan(
+                    "failed to find GPUs with Vulkan support!",
+                ))?
         };
 
         let mut result = None;
         for &physical_device in physical_devices.iter() {
-            if is_device_suitable(instance, physical_device, surface_info) && result.is_none() {
+            if is_device_suitable(instance, physical_device, surface_info)? && result.is_none() {
                 result = Some(physical_device)
             }
         }
 
-        match result {
-            None => panic!("failed to find a suitable GPU!"),
-            Some(physical_device) => physical_device,
-        }
+        result.ok_or(vk_utils::Error::NoSuitableDevice)
     }
 
     fn is_device_suitable(
         instance: &Instance,
         physical_device: vk::PhysicalDevice,
-        surface_info: &vk_utils::VkSurfaceInfo,
-    ) -> bool {
-        let indices = find_queue_family(instance, physical_device, surface_info);
-        let extensions_supported = check_device_extension_support(instance, physical_device);
+        surface_info: Option<&vk_utils::VkSurfaceInfo>,
+    ) -> vk_utils::Result<bool> {
+        let indices = find_queue_family(instance, physical_device, surface_info)?;
+        let supported_features = unsafe { instance.get_physical_device_features(physical_device) };
+
+        let surface_info = match surface_info {
+            Some(surface_info) => surface_info,
+            // Headless rendering only needs a graphics queue
+            None => {
+                return Ok(
+                    indices.graphics_family.is_some() && supported_features.sampler_anisotropy == 1
+                )
+            }
+        };
+
+        let extensions_supported = check_device_extension_support(instance, physical_device)?;
         let swap_chain_adequate = if extensions_supported {
             let swap_chain_support =
-                crate::swapchain::query_swapchain_support(physical_device, surface_info);
+                crate::swapchain::query_swapchain_support(physical_device, surface_info)?;
 
             !(swap_chain_support.formats.is_empty() || swap_chain_support.present_modes.is_empty())
         } else {
             false
         };
-        let supported_features = unsafe { instance.get_physical_device_features(physical_device) };
 
-        indices.is_complete()
+        Ok(indices.is_complete()
             && extensions_supported
             && swap_chain_adequate
-            && supported_features.sampler_anisotropy == 1
+            && supported_features.sampler_anisotropy == 1)
     }
 
     fn check_device_extension_support(
         instance: &Instance,
         physical_device: vk::PhysicalDevice,
-    ) -> bool {
+    ) -> vk_utils::Result<bool> {
         use std::collections::HashSet;
 
         let available_extensions = unsafe {
             instance
                 .enumerate_device_extension_properties(physical_device)
-                .expect("failed to get device extension properties.")
+                .map_err(vk_utils::Error::vulkan(
+                    "failed to get device extension properties.",
+                ))?
         };
         let available_extension_names: Vec<String> = available_extensions
             .iter()
@@ -76,17 +88,17 @@ mod _physical_dev {
             required_extensions.remove(extension_name);
         }
 
-        required_extensions.is_empty()
+        Ok(required_extensions.is_empty())
     }
 
     pub fn find_queue_family(
         instance: &Instance,
         physical_device: vk::PhysicalDevice,
-        surface_info: &vk_utils::VkSurfaceInfo,
-    ) -> vk_utils::QueueFamilyIndices {
+        surface_info: Option<&vk_utils::VkSurfaceInfo>,
+    ) -> vk_utils::Result<vk_utils::QueueFamilyIndices> {
         let queue_families =
             unsafe { instance.get_physical_device_queue_family_properties(physical_device) };
-        let mut queue_family_indices = vk_utils::QueueFamilyIndices::new(None, None);
+        let mut queue_family_indices = vk_utils::QueueFamilyIndices::new(None, None, None, None);
 
         let mut index: u32 = 0;
         for queue_family in queue_families.iter() {
@@ -96,15 +108,30 @@ mod _physical_dev {
                 queue_family_indices.graphics_family = Some(index)
             }
 
+            let surface_info = match surface_info {
+                Some(surface_info) => surface_info,
+                // Nothing to present to when rendering headless
+                None => {
+                    if queue_family_indices.graphics_family.is_some() {
+                        break;
+                    }
+
+                    index += 1;
+                    continue;
+                }
+            };
+
             let is_present_support = unsafe {
                 surface_info
                     .surface_loader
                     .get_physical_device_surface_support(
                         physical_device,
-                        index as u32,
+                        index,
                         surface_info.surface,
                     )
-                    .unwrap()
+                    .map_err(vk_utils::Error::vulkan(
+                        "failed to query for surface present support!",
+                    ))?
             };
 
             if queue_family.queue_count > 0 && is_present_support {
@@ -118,24 +145,94 @@ mod _physical_dev {
             index += 1;
         }
 
-        queue_family_indices
+        // Compute work on a family without graphics can overlap with rendering
+        let supports_compute = |queue_family: &vk::QueueFamilyProperties| {
+            queue_family.queue_count > 0
+                && queue_family.queue_flags.contains(vk::QueueFlags::COMPUTE)
+        };
+        queue_family_indices.compute_family = queue_families
+            .iter()
+            .position(|queue_family| {
+                supports_compute(queue_family)
+                    && !queue_family.queue_flags.contains(vk::QueueFlags::GRAPHICS)
+            })
+            .or_else(|| {
+                queue_family_indices
+                    .graphics_family
+                    .map(|graphics_family| graphics_family as usize)
+                    .filter(|&graphics_family| supports_compute(&queue_families[graphics_family]))
+            })
+            .or_else(|| queue_families.iter().position(supports_compute))
+            .map(|compute_family| compute_family as u32);
+
+        // Copies on a transfer-only family (DMA engine) don't take time from rendering.
+        // Graphics and compute families support transfers even without the flag
+        let is_dedicated_transfer = |queue_family: &vk::QueueFamilyProperties| {
+            queue_family.queue_count > 0
+                && queue_family.queue_flags.contains(vk::QueueFlags::TRANSFER)
+                && !queue_family.queue_flags.contains(vk::QueueFlags::GRAPHICS)
+        };
+        queue_family_indices.transfer_family = queue_families
+            .iter()
+            .position(|queue_family| {
+                is_dedicated_transfer(queue_family)
+                    && !queue_family.queue_flags.contains(vk::QueueFlags::COMPUTE)
+            })
+            .or_else(|| queue_families.iter().position(is_dedicated_transfer))
+            .map(|transfer_family| transfer_family as u32)
+            .or(queue_family_indices.graphics_family);
+
+        Ok(queue_family_indices)
+    }
+
+    // Dynamic rendering is core in Vulkan 1.3, both the instance and the device need it
+    pub fn supports_dynamic_rendering(
+        entry: &ash::Entry,
+        instance: &Instance,
+        physical_device: vk::PhysicalDevice,
+    ) -> bool {
+        let properties = unsafe { instance.get_physical_device_properties(physical_device) };
+        if vk_utils::instance::instance_api_version(entry) < vk::API_VERSION_1_3
+            || properties.api_version < vk::API_VERSION_1_3
+        {
+            return false;
+        }
+
+        let mut vulkan_13_features = vk::PhysicalDeviceVulkan13Features::default();
+        let mut features =
+            vk::PhysicalDeviceFeatures2::builder().push_next(&mut vulkan_13_features);
+
+        unsafe {
+            instance.get_physical_device_features2(physical_device, &mut features);
+        }
+
+        vulkan_13_features.dynamic_rendering == vk::TRUE
     }
 
     pub fn create_logical_device(
         instance: &Instance,
         physical_device: vk::PhysicalDevice,
-        surface_info: &vk_utils::VkSurfaceInfo,
-    ) -> (ash::Device, vk_utils::QueueFamilyIndices) {
+        surface_info: Option<&vk_utils::VkSurfaceInfo>,
+        dynamic_rendering: bool,
+    ) -> vk_utils::Result<(ash::Device, vk_utils::QueueFamilyIndices)> {
         use ash::extensions::khr::Swapchain;
         #[cfg(any(target_os = "macos", target_os = "ios"))]
         use ash::vk::KhrPortabilitySubsetFn;
         use std::collections::HashSet;
 
-        let indices = vk_utils::device::find_queue_family(instance, physical_device, surface_info);
+        let indices = vk_utils::device::find_queue_family(instance, physical_device, surface_info)?;
 
         let mut unique_queue_families = HashSet::new();
         unique_queue_families.insert(indices.graphics_family.unwrap());
-        unique_queue_families.insert(indices.present_family.unwrap());
+        if let Some(present_family) = indices.present_family {
+            unique_queue_families.insert(present_family);
+        }
+        if let Some(compute_family) = indices.compute_family {
+            unique_queue_families.insert(compute_family);
+        }
+        if let Some(transfer_family) = indices.transfer_family {
+            unique_queue_families.insert(transfer_family);
+        }
 
         let queue_priorities = [1.0_f32];
         let queue_create_infos: Vec<vk::DeviceQueueCreateInfo> = unique_queue_families
@@ -148,10 +245,14 @@ mod _physical_dev {
             })
             .collect();
 
+        let supported_features = unsafe { instance.get_physical_device_features(physical_device) };
         let device_features = vk::PhysicalDeviceFeatures::builder()
             // Anistropy device feature
             .sampler_anisotropy(true)
             .sample_rate_shading(true)
+            // Wireframe and wide line pipelines, where available
+            .fill_mode_non_solid(supported_features.fill_mode_non_solid == vk::TRUE)
+            .wide_lines(supported_features.wide_lines == vk::TRUE)
             .build();
 
         let required_validation_layers_raw: Vec<CString> = VK_VALIDATION_LAYER_NAMES
@@ -164,13 +265,19 @@ mod _physical_dev {
             .map(|layer_name| layer_name.as_ptr())
             .collect();
 
-        let device_extensions = [
-            Swapchain::name().as_ptr(),
-            #[cfg(any(target_os = "macos", target_os = "ios"))]
-            KhrPortabilitySubsetFn::name().as_ptr(),
-        ];
+        let mut device_extensions: Vec<*const c_char> = Vec::new();
 
-        let create_info = if VK_VALIDATION_LAYER_NAMES.is_enable {
+        // Swap chain is only needed when presenting to a surface
+        if surface_info.is_some() {
+            device_extensions.push(Swapchain::name().as_ptr());
+        }
+        #[cfg(any(target_os = "macos", target_os = "ios"))]
+        device_extensions.push(KhrPortabilitySubsetFn::name().as_ptr());
+
+        let mut vulkan_13_features =
+            vk::PhysicalDeviceVulkan13Features::builder().dynamic_rendering(true);
+
+        let mut create_info = if VK_VALIDATION_LAYER_NAMES.is_enable {
             vk::DeviceCreateInfo::builder()
                 .queue_create_infos(&queue_create_infos)
                 .enabled_features(&device_features)
@@ -182,14 +289,18 @@ mod _physical_dev {
                 .enabled_features(&device_features)
                 .enabled_extension_names(&device_extensions)
         };
+        // Check supports_dynamic_rendering first
+        if dynamic_rendering {
+            create_info = create_info.push_next(&mut vulkan_13_features);
+        }
 
         let device = unsafe {
             instance
                 .create_device(physical_device, &create_info, None)
-                .expect("failed to create logical device!")
+                .map_err(vk_utils::Error::vulkan("failed to create logical device!"))?
         };
 
-        (device, indices)
+        Ok((device, indices))
     }
 
     pub fn get_max_usable_sample_count(
@@ -227,4 +338,5 @@ mod _physical_dev {
 
 pub use _physical_dev::{
     create_logical_device, find_queue_family, get_max_usable_sample_count, pick_physical_device,
+    supports_dynamic_rendering,
 };
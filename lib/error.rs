@@ -0,0 +1,141 @@
+mod _error {
+    use ash::vk;
+    use std::fmt;
+
+    #[derive(Debug)]
+    pub enum Error {
+        // Vulkan call failed (what was being done, returned result)
+        Vulkan(&'static str, vk::Result),
+        Io(std::io::Error),
+        Image(::image::ImageError),
+        Obj(tobj::LoadError),
+        Gltf(::gltf::Error),
+        // Compiler diagnostics
+        ShaderCompilation(String),
+        InvalidSpirv(&'static str),
+        // Shader interface doesn't match what the application provides
+        ShaderInterfaceMismatch(String),
+        NoSuitableDevice,
+        NoSuitableMemoryType,
+        // Host access to memory that isn't host visible
+        MemoryNotMapped,
+        // (bytes accessed, buffer size)
+        BufferTooSmall(vk::DeviceSize, vk::DeviceSize),
+        // Vulkan has no zero sized buffers or copies
+        EmptyBuffer,
+        ValidationLayersUnavailable,
+        // None of the candidate formats support the requested features
+        MissingFormat(Vec<vk::Format>),
+        UnsupportedFormat(vk::Format, vk::FormatFeatureFlags),
+        UnsupportedLayoutTransition(vk::ImageLayout, vk::ImageLayout),
+        InvalidImage(&'static str),
+        InvalidModel(&'static str),
+        // Passes that can't be turned into render passes and framebuffers
+        InvalidRenderGraph(String),
+        NoOffscreenTarget,
+        ImageMismatch(String),
+        // A loader job panicked on its worker thread
+        AssetLoadPanicked,
+    }
+
+    pub type Result<T> = std::result::Result<T, Error>;
+
+    impl Error {
+        // Used as `.map_err(vk_utils::Error::vulkan("failed to ...!"))`
+        pub fn vulkan(message: &'static str) -> impl FnOnce(vk::Result) -> Self {
+            move |result| Self::Vulkan(message, result)
+        }
+    }
+
+    impl fmt::Display for Error {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            match self {
+                Self::Vulkan(message, result) => write!(f, "{} ({})", message, result),
+                Self::Io(err) => write!(f, "I/O error: {}", err),
+                Self::Image(err) => write!(f, "failed to decode image: {}", err),
+                Self::Obj(err) => write!(f, "failed to load model object: {}", err),
+                Self::Gltf(err) => write!(f, "failed to load glTF scene: {}", err),
+                Self::ShaderCompilation(message) => {
+                    write!(f, "failed to compile shader!\n{}", message)
+                }
+                Self::NoSuitableDevice => write!(f, "failed to find a suitable GPU!"),
+                Self::NoSuitableMemoryType => write!(f, "failed to find suitable memory type!"),
+                Self::MemoryNotMapped => write!(f, "memory is not host visible!"),
+                Self::BufferTooSmall(accessed, size) => write!(
+                    f,
+                    "buffer access out of range! ({} bytes, buffer size: {})",
+                    accessed, size
+                ),
+                Self::EmptyBuffer => write!(f, "buffer or copy of zero bytes!"),
+                Self::ValidationLayersUnavailable => {
+                    write!(f, "validation layers requested, but not available!")
+                }
+                Self::MissingFormat(candidates) => write!(
+                    f,
+                    "failed to find supported format! (candidates: {:?})",
+                    candidates
+                ),
+                Self::UnsupportedFormat(format, features) => {
+                    write!(f, "{:?} does not support {:?}!", format, features)
+                }
+                Self::UnsupportedLayoutTransition(old_layout, new_layout) => write!(
+                    f,
+                    "unsupported layout transition! ({:?} -> {:?})",
+                    old_layout, new_layout
+                ),
+                Self::InvalidSpirv(message) => write!(f, "invalid SPIR-V: {}", message),
+                Self::ShaderInterfaceMismatch(message) => {
+                    write!(f, "shader interface mismatch! ({})", message)
+                }
+                Self::InvalidImage(message) | Self::InvalidModel(message) => {
+                    write!(f, "{}", message)
+                }
+                Self::InvalidRenderGraph(message) => {
+                    write!(f, "invalid render graph! ({})", message)
+                }
+                Self::NoOffscreenTarget => write!(f, "renderer has no offscreen render target!"),
+                Self::ImageMismatch(message) => write!(f, "{}", message),
+                Self::AssetLoadPanicked => write!(f, "asset loader job panicked!"),
+            }
+        }
+    }
+
+    impl std::error::Error for Error {
+        fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+            match self {
+                Self::Vulkan(_, result) => Some(result),
+                Self::Io(err) => Some(err),
+                Self::Image(err) => Some(err),
+                Self::Obj(err) => Some(err),
+                Self::Gltf(err) => Some(err),
+                _ => None,
+            }
+        }
+    }
+
+    impl From<std::io::Error> for Error {
+        fn from(err: std::io::Error) -> Self {
+            Self::Io(err)
+        }
+    }
+
+    impl From<::image::ImageError> for Error {
+        fn from(err: ::image::ImageError) -> Self {
+            Self::Image(err)
+        }
+    }
+
+    impl From<tobj::LoadError> for Error {
+        fn from(err: tobj::LoadError) -> Self {
+            Self::Obj(err)
+        }
+    }
+
+    impl From<::gltf::Error> for Error {
+        fn from(err: ::gltf::Error) -> Self {
+            Self::Gltf(err)
+        }
+    }
+}
+
+pub use _error::{Error, Result};
@@ -3,7 +3,7 @@
  * LICENSED UNDER MIT
  */
 
-mod _FPSLimiter {
+mod _fps_limiter {
     use std::thread;
     use std::time::{Duration, Instant};
 
@@ -18,13 +18,19 @@ mod _FPSLimiter {
         delta_frame: u32,
     }
 
+    impl Default for FPSLimiter {
+        fn default() -> Self {
+            Self::new()
+        }
+    }
+
     impl FPSLimiter {
         pub fn new() -> Self {
             const DEFAULT_PREFER_FPS: f32 = 60.0;
 
             Self {
                 counter: Instant::now(),
-                frame_time_prefer: (1000_000.0_f32 / DEFAULT_PREFER_FPS) as u32,
+                frame_time_prefer: (1_000_000.0_f32 / DEFAULT_PREFER_FPS) as u32,
                 samples: [0; SAMPLE_COUNT],
                 current_frame: 0,
                 delta_frame: 0,
@@ -32,7 +38,7 @@ mod _FPSLimiter {
         }
 
         pub fn set_prefer_fps(&mut self, prefer_fps: f32) {
-            self.frame_time_prefer = (1000_000.0_f32 / prefer_fps) as u32;
+            self.frame_time_prefer = (1_000_000.0_f32 / prefer_fps) as u32;
         }
 
         pub fn tick_frame(&mut self) {
@@ -60,13 +66,13 @@ mod _FPSLimiter {
                 sum += val;
             });
 
-            1000_000.0_f32 / (sum as f32 / SAMPLE_COUNT_FLOAT)
+            1_000_000.0_f32 / (sum as f32 / SAMPLE_COUNT_FLOAT)
         }
 
         pub fn delta_time(&self) -> f32 {
-            self.delta_frame as f32 / 1000_000.0_f32 // time in second
+            self.delta_frame as f32 / 1_000_000.0_f32 // time in second
         }
     }
 }
 
-pub use _FPSLimiter::FPSLimiter;
+pub use _fps_limiter::FPSLimiter;
@@ -0,0 +1,121 @@
+mod _frame {
+    use crate::{
+        self as vk_utils,
+        command::FrameCommandPool,
+        framebuffer::{Fence, Semaphore},
+    };
+
+    use ash::vk;
+
+    // Everything one frame in flight records and submits with,
+    // only touched again once its fence has been waited on
+    pub struct FrameContext<T> {
+        command_pool: FrameCommandPool,
+        image_available: Semaphore,
+        render_finished: Semaphore,
+        in_flight: Fence,
+        // Scene resources written every frame (uniform buffers, descriptor sets)
+        data: T,
+    }
+
+    impl<T> FrameContext<T> {
+        pub fn new(
+            device: &ash::Device,
+            queue_family_index: u32,
+            data: T,
+        ) -> vk_utils::Result<Self> {
+            Ok(Self {
+                command_pool: FrameCommandPool::new(device, queue_family_index)?,
+                image_available: Semaphore::new(device)?,
+                render_finished: Semaphore::new(device)?,
+                // Signaled, so the first frame doesn't wait forever
+                in_flight: Fence::new(device, true)?,
+                data,
+            })
+        }
+
+        // Blocks until the previous submission of this frame is done
+        pub fn wait(&self, device: &ash::Device) -> vk_utils::Result<()> {
+            let fences = [self.in_flight.handle()];
+
+            unsafe {
+                device
+                    .wait_for_fences(&fences, true, u64::MAX)
+                    .map_err(vk_utils::Error::vulkan("failed to wait for fence!"))
+            }
+        }
+
+        // Right before submitting, after the last early return of the frame
+        pub fn reset(&self, device: &ash::Device) -> vk_utils::Result<()> {
+            let fences = [self.in_flight.handle()];
+
+            unsafe {
+                device
+                    .reset_fences(&fences)
+                    .map_err(vk_utils::Error::vulkan("failed to reset fence!"))
+            }
+        }
+
+        pub fn command_pool(&self) -> &FrameCommandPool {
+            &self.command_pool
+        }
+
+        pub fn image_available(&self) -> vk::Semaphore {
+            self.image_available.handle()
+        }
+
+        pub fn render_finished(&self) -> vk::Semaphore {
+            self.render_finished.handle()
+        }
+
+        pub fn in_flight(&self) -> vk::Fence {
+            self.in_flight.handle()
+        }
+
+        pub fn data(&self) -> &T {
+            &self.data
+        }
+
+        pub fn data_mut(&mut self) -> &mut T {
+            &mut self.data
+        }
+    }
+
+    // Which frame's fence each swap chain image was last submitted with.
+    // The swap chain can hand out images in any order, so with more images than
+    // frames in flight (or the other way around) an acquired image may still be rendered to
+    pub struct ImagesInFlight {
+        fences: Vec<vk::Fence>,
+    }
+
+    impl ImagesInFlight {
+        pub fn new(image_count: usize) -> Self {
+            Self {
+                fences: vec![vk::Fence::null(); image_count],
+            }
+        }
+
+        // Waits for whichever frame still renders to the image, then hands it to `fence`
+        pub fn acquire(
+            &mut self,
+            device: &ash::Device,
+            image_index: usize,
+            fence: vk::Fence,
+        ) -> vk_utils::Result<()> {
+            let previous_fence = self.fences[image_index];
+
+            if previous_fence != vk::Fence::null() && previous_fence != fence {
+                unsafe {
+                    device
+                        .wait_for_fences(&[previous_fence], true, u64::MAX)
+                        .map_err(vk_utils::Error::vulkan("failed to wait for fence!"))?;
+                }
+            }
+            self.fences[image_index] = fence;
+
+            Ok(())
+        }
+    }
+}
+
+pub use _frame::{FrameContext, ImagesInFlight};
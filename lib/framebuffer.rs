@@ -3,83 +3,121 @@ mod _framebuffer {
 
     use ash::vk;
 
-    pub fn create_framebuffers(
-        device: &ash::Device,
-        render_pass: vk::RenderPass,
-        image_views: &Vec<vk::ImageView>,
-        color_image_view: Option<vk::ImageView>,
-        depth_image_view: Option<vk::ImageView>,
-        swapchain_extent: &vk::Extent2D,
-    ) -> Vec<vk::Framebuffer> {
-        // Framebuffers
-        image_views
-            .iter()
-            .map(|&image_view| {
-                let mut attachments = vec![];
-
-                // Order matters here!
-                if color_image_view.is_some() {
-                    attachments.push(color_image_view.unwrap());
-                }
-                if depth_image_view.is_some() {
-                    attachments.push(depth_image_view.unwrap());
-                }
-                attachments.push(image_view);
-
-                let framebuffer_info = vk::FramebufferCreateInfo::builder()
-                    .render_pass(render_pass)
-                    .attachments(&attachments)
-                    .width(swapchain_extent.width)
-                    .height(swapchain_extent.height)
-                    .layers(1);
-                unsafe {
-                    device
-                        .create_framebuffer(&framebuffer_info, None)
-                        .expect("failed to create framebuffer!")
-                }
+    pub struct Framebuffer {
+        device: ash::Device,
+        framebuffer: vk::Framebuffer,
+    }
+
+    impl Framebuffer {
+        pub fn new(
+            device: &ash::Device,
+            render_pass: vk::RenderPass,
+            attachments: &[vk::ImageView],
+            extent: vk::Extent2D,
+        ) -> vk_utils::Result<Self> {
+            // Attachments in the same order as the render pass declares them
+            let framebuffer_info = vk::FramebufferCreateInfo::builder()
+                .render_pass(render_pass)
+                .attachments(attachments)
+                .width(extent.width)
+                .height(extent.height)
+                .layers(1);
+            let framebuffer = unsafe {
+                device
+                    .create_framebuffer(&framebuffer_info, None)
+                    .map_err(vk_utils::Error::vulkan("failed to create framebuffer!"))?
+            };
+
+            Ok(Self {
+                device: device.clone(),
+                framebuffer,
             })
-            .collect()
+        }
+
+        pub fn handle(&self) -> vk::Framebuffer {
+            self.framebuffer
+        }
     }
 
-    pub fn create_sync_objects(device: &ash::Device) -> vk_utils::SyncObjects {
-        // Creating the synchronization objects
-        let mut sync_objects = vk_utils::SyncObjects {
-            image_available_semaphores: Vec::new(),
-            render_finished_semaphores: Vec::new(),
-            in_flight_fences: Vec::new(),
-        };
+    impl Drop for Framebuffer {
+        fn drop(&mut self) {
+            unsafe {
+                self.device.destroy_framebuffer(self.framebuffer, None);
+            }
+        }
+    }
 
-        let semaphore_info = vk::SemaphoreCreateInfo::builder();
-        let fence_info = vk::FenceCreateInfo::builder().flags(vk::FenceCreateFlags::SIGNALED);
+    pub struct Semaphore {
+        device: ash::Device,
+        semaphore: vk::Semaphore,
+    }
 
-        for _ in 0..vk_utils::constants::MAX_FRAMES_IN_FLIGHT {
-            let image_available_semaphore = unsafe {
+    impl Semaphore {
+        pub fn new(device: &ash::Device) -> vk_utils::Result<Self> {
+            let semaphore_info = vk::SemaphoreCreateInfo::builder();
+            let semaphore = unsafe {
                 device
                     .create_semaphore(&semaphore_info, None)
-                    .expect("failed to create semaphore!")
+                    .map_err(vk_utils::Error::vulkan("failed to create semaphore!"))?
             };
-            let render_finished_semaphore = unsafe {
-                device
-                    .create_semaphore(&semaphore_info, None)
-                    .expect("failed to create semaphore!")
+
+            Ok(Self {
+                device: device.clone(),
+                semaphore,
+            })
+        }
+
+        pub fn handle(&self) -> vk::Semaphore {
+            self.semaphore
+        }
+    }
+
+    impl Drop for Semaphore {
+        fn drop(&mut self) {
+            unsafe {
+                self.device.destroy_semaphore(self.semaphore, None);
+            }
+        }
+    }
+
+    pub struct Fence {
+        device: ash::Device,
+        fence: vk::Fence,
+    }
+
+    impl Fence {
+        // Signaled fences don't block the first wait on them
+        pub fn new(device: &ash::Device, signaled: bool) -> vk_utils::Result<Self> {
+            let flags = if signaled {
+                vk::FenceCreateFlags::SIGNALED
+            } else {
+                vk::FenceCreateFlags::empty()
             };
-            let in_flight_fence = unsafe {
+            let fence_info = vk::FenceCreateInfo::builder().flags(flags);
+            let fence = unsafe {
                 device
                     .create_fence(&fence_info, None)
-                    .expect("failed to create fence!")
+                    .map_err(vk_utils::Error::vulkan("failed to create fence!"))?
             };
 
-            sync_objects
-                .image_available_semaphores
-                .push(image_available_semaphore);
-            sync_objects
-                .render_finished_semaphores
-                .push(render_finished_semaphore);
-            sync_objects.in_flight_fences.push(in_flight_fence);
+            Ok(Self {
+                device: device.clone(),
+                fence,
+            })
         }
 
-        sync_objects
+        pub fn handle(&self) -> vk::Fence {
+            self.fence
+        }
+    }
+
+    impl Drop for Fence {
+        fn drop(&mut self) {
+            unsafe {
+                self.device.destroy_fence(self.fence, None);
+            }
+        }
     }
 }
 
-pub use _framebuffer::{create_framebuffers, create_sync_objects};
+pub use _framebuffer::{Fence, Framebuffer, Semaphore};
@@ -2,98 +2,234 @@ mod _image {
     use crate as vk_utils;
     use ash::vk;
 
+    // Image bound to a range of an allocator block, both released when dropped
+    pub struct Image {
+        device: ash::Device,
+        image: vk::Image,
+        allocation: vk_utils::memory::Allocation,
+    }
+
+    impl Image {
+        pub fn handle(&self) -> vk::Image {
+            self.image
+        }
+
+        pub fn allocation(&self) -> &vk_utils::memory::Allocation {
+            &self.allocation
+        }
+    }
+
+    impl Drop for Image {
+        fn drop(&mut self) {
+            unsafe {
+                self.device.destroy_image(self.image, None);
+            }
+        }
+    }
+
+    pub struct ImageView {
+        device: ash::Device,
+        image_view: vk::ImageView,
+    }
+
+    impl ImageView {
+        pub fn new(device: &ash::Device, image_view: vk::ImageView) -> Self {
+            Self {
+                device: device.clone(),
+                image_view,
+            }
+        }
+
+        pub fn handle(&self) -> vk::ImageView {
+            self.image_view
+        }
+    }
+
+    impl Drop for ImageView {
+        fn drop(&mut self) {
+            unsafe {
+                self.device.destroy_image_view(self.image_view, None);
+            }
+        }
+    }
+
+    // 2D image with a single layer, `properties` picks the memory it gets bound to
+    #[derive(Clone, Copy)]
+    pub struct ImageCreateInfo {
+        pub extent: vk::Extent2D,
+        pub mip_levels: u32,
+        pub samples: vk::SampleCountFlags,
+        pub format: vk::Format,
+        pub tiling: vk::ImageTiling,
+        pub usage: vk::ImageUsageFlags,
+        pub properties: vk::MemoryPropertyFlags,
+    }
+
     pub fn create_image(
         device: &ash::Device,
-        width: u32,
-        height: u32,
-        mip_levels: u32,
-        num_samples: vk::SampleCountFlags,
-        format: vk::Format,
-        tiling: vk::ImageTiling,
-        usage: vk::ImageUsageFlags,
-        properties: vk::MemoryPropertyFlags,
-        device_memory_properties: &vk::PhysicalDeviceMemoryProperties,
-    ) -> (vk::Image, vk::DeviceMemory) {
+        allocator: &vk_utils::memory::Allocator,
+        create_info: &ImageCreateInfo,
+    ) -> vk_utils::Result<Image> {
+        let ImageCreateInfo {
+            extent,
+            mip_levels,
+            samples,
+            format,
+            tiling,
+            usage,
+            properties,
+        } = *create_info;
+
         // Texture image
         let image_info = vk::ImageCreateInfo::builder()
             .image_type(vk::ImageType::TYPE_2D)
             .extent(vk::Extent3D {
-                width,
-                height,
+                width: extent.width,
+                height: extent.height,
                 depth: 1,
             })
-            .mip_levels(1)
             .array_layers(1)
             .format(format)
             .tiling(tiling)
             .initial_layout(vk::ImageLayout::UNDEFINED)
             .usage(usage)
-            .samples(num_samples)
+            .samples(samples)
             .sharing_mode(vk::SharingMode::EXCLUSIVE)
             .mip_levels(mip_levels);
 
         let image = unsafe {
             device
                 .create_image(&image_info, None)
-                .expect("failed to create image!")
+                .map_err(vk_utils::Error::vulkan("failed to create image!"))?
         };
 
         let mem_requirements = unsafe { device.get_image_memory_requirements(image) };
+        let allocation = match allocator.allocate(
+            mem_requirements,
+            properties,
+            tiling == vk::ImageTiling::LINEAR,
+        ) {
+            Ok(allocation) => allocation,
+            Err(err) => {
+                unsafe {
+                    device.destroy_image(image, None);
+                }
 
-        let alloc_info = vk::MemoryAllocateInfo::builder()
-            .allocation_size(mem_requirements.size)
-            .memory_type_index(vk_utils::buffer::find_memory_type(
-                mem_requirements.memory_type_bits,
-                properties,
-                device_memory_properties,
-            ));
-
-        let image_memory = unsafe {
-            device
-                .allocate_memory(&alloc_info, None)
-                .expect("failed to allocate image memory!")
+                return Err(err);
+            }
+        };
+        let image = Image {
+            device: device.clone(),
+            image,
+            allocation,
         };
 
         unsafe {
             device
-                .bind_image_memory(image, image_memory, 0)
-                .expect("failed to bind image memory!");
+                .bind_image_memory(
+                    image.image,
+                    image.allocation.memory(),
+                    image.allocation.offset(),
+                )
+                .map_err(vk_utils::Error::vulkan("failed to bind image memory!"))?;
+        }
+
+        Ok(image)
+    }
+
+    // Stages and access that have to finish (or wait) around an image in `layout`
+    fn layout_access(layout: vk::ImageLayout) -> Option<(vk::PipelineStageFlags, vk::AccessFlags)> {
+        match layout {
+            vk::ImageLayout::UNDEFINED => Some((
+                vk::PipelineStageFlags::TOP_OF_PIPE,
+                vk::AccessFlags::empty(),
+            )),
+            vk::ImageLayout::TRANSFER_SRC_OPTIMAL => Some((
+                vk::PipelineStageFlags::TRANSFER,
+                vk::AccessFlags::TRANSFER_READ,
+            )),
+            vk::ImageLayout::TRANSFER_DST_OPTIMAL => Some((
+                vk::PipelineStageFlags::TRANSFER,
+                vk::AccessFlags::TRANSFER_WRITE,
+            )),
+            vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL => Some((
+                vk::PipelineStageFlags::FRAGMENT_SHADER,
+                vk::AccessFlags::SHADER_READ,
+            )),
+            vk::ImageLayout::COLOR_ATTACHMENT_OPTIMAL => Some((
+                vk::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT,
+                vk::AccessFlags::COLOR_ATTACHMENT_READ | vk::AccessFlags::COLOR_ATTACHMENT_WRITE,
+            )),
+            vk::ImageLayout::DEPTH_STENCIL_ATTACHMENT_OPTIMAL => Some((
+                vk::PipelineStageFlags::EARLY_FRAGMENT_TESTS
+                    | vk::PipelineStageFlags::LATE_FRAGMENT_TESTS,
+                vk::AccessFlags::DEPTH_STENCIL_ATTACHMENT_READ
+                    | vk::AccessFlags::DEPTH_STENCIL_ATTACHMENT_WRITE,
+            )),
+            vk::ImageLayout::GENERAL => Some((
+                vk::PipelineStageFlags::ALL_COMMANDS,
+                vk::AccessFlags::MEMORY_READ | vk::AccessFlags::MEMORY_WRITE,
+            )),
+            vk::ImageLayout::PRESENT_SRC_KHR => Some((
+                vk::PipelineStageFlags::BOTTOM_OF_PIPE,
+                vk::AccessFlags::empty(),
+            )),
+            _ => None,
         }
+    }
 
-        (image, image_memory)
+    // All mip levels of `image` go from `old_layout` to `new_layout`
+    #[derive(Clone, Copy)]
+    pub struct LayoutTransition {
+        pub image: vk::Image,
+        pub format: vk::Format,
+        pub old_layout: vk::ImageLayout,
+        pub new_layout: vk::ImageLayout,
+        pub mip_levels: u32,
     }
 
+    // One-off transition outside of any frame, frames use the render graph instead
     pub fn transition_image_layout(
         device: &ash::Device,
         command_pool: vk::CommandPool,
-        image: vk::Image,
-        format: vk::Format,
-        old_layout: vk::ImageLayout,
-        new_layout: vk::ImageLayout,
         graphics_queue: vk::Queue,
-        mip_levels: u32,
-    ) {
+        transition: &LayoutTransition,
+    ) -> vk_utils::Result<()> {
         use vk_utils::command::{begin_single_time_commands, end_single_time_commands};
 
-        // Layout transitions
-        let command_buffer = begin_single_time_commands(device, command_pool);
+        let LayoutTransition {
+            image,
+            format,
+            old_layout,
+            new_layout,
+            mip_levels,
+        } = *transition;
 
         // Transition barrier mask
-        let mode = if old_layout == vk::ImageLayout::UNDEFINED
-            && new_layout == vk::ImageLayout::TRANSFER_DST_OPTIMAL
-        {
-            0
-        } else if old_layout == vk::ImageLayout::TRANSFER_DST_OPTIMAL
-            && new_layout == vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL
-        {
-            1
-        } else if old_layout == vk::ImageLayout::UNDEFINED
-            && new_layout == vk::ImageLayout::DEPTH_STENCIL_ATTACHMENT_OPTIMAL
-        {
-            2
-        } else {
-            panic!("unsupported layout transition!");
-        };
+        let (source_stage, src_access_mask, destination_stage, dst_access_mask) =
+            match (layout_access(old_layout), layout_access(new_layout)) {
+                (
+                    Some((source_stage, src_access_mask)),
+                    Some((destination_stage, dst_access_mask)),
+                ) if new_layout != vk::ImageLayout::UNDEFINED => {
+                    // Reads don't need to be made available
+                    (
+                        source_stage,
+                        src_access_mask
+                            & (vk::AccessFlags::TRANSFER_WRITE
+                                | vk::AccessFlags::COLOR_ATTACHMENT_WRITE
+                                | vk::AccessFlags::DEPTH_STENCIL_ATTACHMENT_WRITE
+                                | vk::AccessFlags::MEMORY_WRITE),
+                        destination_stage,
+                        dst_access_mask,
+                    )
+                }
+                _ => {
+                    return Err(vk_utils::Error::UnsupportedLayoutTransition(
+                        old_layout, new_layout,
+                    ))
+                }
+            };
 
         let aspect_mask = if new_layout == vk::ImageLayout::DEPTH_STENCIL_ATTACHMENT_OPTIMAL {
             if vk_utils::swapchain::has_stencil_component(format) {
@@ -105,29 +241,6 @@ mod _image {
             vk::ImageAspectFlags::COLOR
         };
 
-        let src_access_mask = match mode {
-            1 => vk::AccessFlags::TRANSFER_WRITE,
-            _ => vk::AccessFlags::empty(),
-        };
-        let dst_access_mask = match mode {
-            1 => vk::AccessFlags::SHADER_READ,
-            2 => {
-                vk::AccessFlags::DEPTH_STENCIL_ATTACHMENT_READ
-                    | vk::AccessFlags::DEPTH_STENCIL_ATTACHMENT_WRITE
-            }
-            _ => vk::AccessFlags::TRANSFER_WRITE,
-        };
-
-        let source_stage = match mode {
-            1 => vk::PipelineStageFlags::TRANSFER,
-            _ => vk::PipelineStageFlags::TOP_OF_PIPE,
-        };
-        let destination_stage = match mode {
-            1 => vk::PipelineStageFlags::FRAGMENT_SHADER,
-            2 => vk::PipelineStageFlags::EARLY_FRAGMENT_TESTS,
-            _ => vk::PipelineStageFlags::TRANSFER,
-        };
-
         let image_subresource_range = vk::ImageSubresourceRange::builder()
             .aspect_mask(aspect_mask)
             .base_mip_level(0)
@@ -146,6 +259,9 @@ mod _image {
             .dst_access_mask(dst_access_mask)
             .build()];
 
+        // Layout transitions
+        let command_buffer = begin_single_time_commands(device, command_pool)?;
+
         unsafe {
             device.cmd_pipeline_barrier(
                 command_buffer,
@@ -158,7 +274,7 @@ mod _image {
             );
         }
 
-        end_single_time_commands(device, command_pool, command_buffer, graphics_queue);
+        end_single_time_commands(device, command_pool, command_buffer, graphics_queue)
     }
 
     pub fn copy_buffer_to_image(
@@ -169,11 +285,26 @@ mod _image {
         graphics_queue: vk::Queue,
         width: u32,
         height: u32,
-    ) {
+    ) -> vk_utils::Result<()> {
         use vk_utils::command::{begin_single_time_commands, end_single_time_commands};
 
         // Copying buffer to image
-        let command_buffer = begin_single_time_commands(device, command_pool);
+        let command_buffer = begin_single_time_commands(device, command_pool)?;
+
+        cmd_copy_buffer_to_image(device, command_buffer, buffer, image, width, height);
+
+        end_single_time_commands(device, command_pool, command_buffer, graphics_queue)
+    }
+
+    // Records the copy into mip level 0, the image has to be in TRANSFER_DST_OPTIMAL
+    pub fn cmd_copy_buffer_to_image(
+        device: &ash::Device,
+        command_buffer: vk::CommandBuffer,
+        buffer: vk::Buffer,
+        image: vk::Image,
+        width: u32,
+        height: u32,
+    ) {
         let regions = [vk::BufferImageCopy::builder()
             .buffer_offset(0)
             .buffer_row_length(0)
@@ -201,9 +332,54 @@ mod _image {
                 &regions,
             );
         }
+    }
+
+    pub fn copy_image_to_buffer(
+        device: &ash::Device,
+        command_pool: vk::CommandPool,
+        image: vk::Image,
+        buffer: vk::Buffer,
+        graphics_queue: vk::Queue,
+        width: u32,
+        height: u32,
+    ) -> vk_utils::Result<()> {
+        use vk_utils::command::{begin_single_time_commands, end_single_time_commands};
+
+        // Reading an image back to the host
+        let command_buffer = begin_single_time_commands(device, command_pool)?;
+        let regions = [vk::BufferImageCopy::builder()
+            .buffer_offset(0)
+            .buffer_row_length(0)
+            .buffer_image_height(0)
+            .image_subresource(vk::ImageSubresourceLayers {
+                aspect_mask: vk::ImageAspectFlags::COLOR,
+                mip_level: 0,
+                base_array_layer: 0,
+                layer_count: 1,
+            })
+            .image_offset(vk::Offset3D { x: 0, y: 0, z: 0 })
+            .image_extent(vk::Extent3D {
+                width,
+                height,
+                depth: 1,
+            })
+            .build()];
+
+        unsafe {
+            device.cmd_copy_image_to_buffer(
+                command_buffer,
+                image,
+                vk::ImageLayout::TRANSFER_SRC_OPTIMAL,
+                buffer,
+                &regions,
+            );
+        }
 
-        end_single_time_commands(device, command_pool, command_buffer, graphics_queue);
+        end_single_time_commands(device, command_pool, command_buffer, graphics_queue)
     }
 }
 
-pub use _image::{copy_buffer_to_image, create_image, transition_image_layout};
+pub use _image::{
+    cmd_copy_buffer_to_image, copy_buffer_to_image, copy_image_to_buffer, create_image,
+    transition_image_layout, Image, ImageCreateInfo, ImageView, LayoutTransition,
+};
@@ -0,0 +1,98 @@
+mod _instance {
+    use crate as vk_utils;
+    use crate::constants::{ENGINE_NAME, ENGINE_VERSION, VK_VALIDATION_LAYER_NAMES};
+    use crate::tools::debug as vk_debug;
+
+    use ash::{extensions::ext::DebugUtils, vk, Entry, Instance};
+    use raw_window_handle::RawDisplayHandle;
+    use std::ffi::CString;
+    use std::os::raw::c_char;
+
+    #[cfg(any(target_os = "macos", target_os = "ios"))]
+    use ash::vk::{KhrGetPhysicalDeviceProperties2Fn, KhrPortabilityEnumerationFn};
+
+    // Highest version the loader knows, capped at 1.3 (dynamic rendering)
+    pub fn instance_api_version(entry: &Entry) -> u32 {
+        match entry.try_enumerate_instance_version() {
+            Ok(Some(version)) => version.min(vk::API_VERSION_1_3),
+            _ => vk::API_VERSION_1_0,
+        }
+    }
+
+    pub fn create_instance(
+        entry: &Entry,
+        application_name: &str,
+        application_version: u32,
+        display_handle: Option<RawDisplayHandle>,
+    ) -> vk_utils::Result<Instance> {
+        if VK_VALIDATION_LAYER_NAMES.is_enable && !vk_debug::check_validation_layer_support(entry)?
+        {
+            return Err(vk_utils::Error::ValidationLayersUnavailable);
+        }
+
+        let app_name = CString::new(application_name).unwrap();
+        let engine_name = CString::new(ENGINE_NAME).unwrap();
+
+        // Surface extensions are only needed when rendering to a window
+        let mut extension_names: Vec<*const c_char> = match display_handle {
+            Some(display_handle) => ash_window::enumerate_required_extensions(display_handle)
+                .map_err(vk_utils::Error::vulkan(
+                    "failed to enumerate required surface extensions!",
+                ))?
+                .to_vec(),
+            None => Vec::new(),
+        };
+
+        extension_names.push(DebugUtils::name().as_ptr());
+
+        #[cfg(any(target_os = "macos", target_os = "ios"))]
+        {
+            extension_names.push(KhrPortabilityEnumerationFn::name().as_ptr());
+            extension_names.push(KhrGetPhysicalDeviceProperties2Fn::name().as_ptr());
+        }
+
+        let required_validation_layer_names: Vec<CString> = VK_VALIDATION_LAYER_NAMES
+            .required_validation_layers
+            .iter()
+            .map(|layer_name| CString::new(*layer_name).unwrap())
+            .collect();
+        let raw_layer_names: Vec<*const c_char> = required_validation_layer_names
+            .iter()
+            .map(|layer_name| layer_name.as_ptr())
+            .collect();
+
+        let app_info = vk::ApplicationInfo::builder()
+            .application_name(&app_name)
+            .application_version(application_version)
+            .engine_name(&engine_name)
+            .engine_version(ENGINE_VERSION)
+            .api_version(instance_api_version(entry));
+
+        let create_flags = if cfg!(any(target_os = "macos", target_os = "ios")) {
+            vk::InstanceCreateFlags::ENUMERATE_PORTABILITY_KHR
+        } else {
+            vk::InstanceCreateFlags::default()
+        };
+
+        let create_info = if VK_VALIDATION_LAYER_NAMES.is_enable {
+            vk::InstanceCreateInfo::builder()
+                .application_info(&app_info)
+                .enabled_layer_names(&raw_layer_names)
+                .enabled_extension_names(&extension_names)
+                .flags(create_flags)
+        } else {
+            vk::InstanceCreateInfo::builder()
+                .application_info(&app_info)
+                .enabled_extension_names(&extension_names)
+                .flags(create_flags)
+        };
+
+        unsafe {
+            entry
+                .create_instance(&create_info, None)
+                .map_err(vk_utils::Error::vulkan("failed to create instance!"))
+        }
+    }
+}
+
+pub use _instance::{create_instance, instance_api_version};
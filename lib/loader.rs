@@ -0,0 +1,237 @@
+mod _loader {
+    use crate as vk_utils;
+
+    use std::{
+        sync::{
+            atomic::{AtomicBool, Ordering},
+            mpsc, Arc, Mutex,
+        },
+        thread,
+    };
+
+    // Identifies one job of the loader, results are tagged with it
+    pub type AssetId = u64;
+
+    type Job<T> = Box<dyn FnOnce() -> vk_utils::Result<T> + Send>;
+
+    // Decodes assets (models, images) on worker threads.
+    // Only CPU data comes back: uploading is left to the render thread, which owns the queues
+    pub struct AssetLoader<T> {
+        jobs: Option<mpsc::Sender<(AssetId, Job<T>)>>,
+        results: mpsc::Receiver<(AssetId, vk_utils::Result<T>)>,
+        workers: Vec<thread::JoinHandle<()>>,
+        // Set when dropped, queued jobs are skipped from then on
+        is_cancelled: Arc<AtomicBool>,
+        next_id: AssetId,
+        pending: usize,
+    }
+
+    impl<T: Send + 'static> AssetLoader<T> {
+        pub fn new(worker_count: usize) -> vk_utils::Result<Self> {
+            let (job_sender, job_receiver) = mpsc::channel::<(AssetId, Job<T>)>();
+            let (result_sender, results) = mpsc::channel();
+            let job_receiver = Arc::new(Mutex::new(job_receiver));
+            let is_cancelled = Arc::new(AtomicBool::new(false));
+
+            let mut loader = Self {
+                jobs: Some(job_sender),
+                results,
+                workers: Vec::new(),
+                is_cancelled: is_cancelled.clone(),
+                next_id: 0,
+                pending: 0,
+            };
+
+            for index in 0..worker_count.max(1) {
+                let job_receiver = job_receiver.clone();
+                let result_sender = result_sender.clone();
+                let is_cancelled = is_cancelled.clone();
+
+                let worker = thread::Builder::new()
+                    .name(format!("asset loader {}", index))
+                    .spawn(move || loop {
+                        // The lock is only held while waiting for the next job
+                        let job = match job_receiver.lock() {
+                            Ok(job_receiver) => job_receiver.recv(),
+                            Err(_) => break,
+                        };
+                        let Ok((id, job)) = job else {
+                            break;
+                        };
+
+                        if is_cancelled.load(Ordering::Relaxed) {
+                            break;
+                        }
+                        // A panicking job still gets a result, so nobody waits for it forever
+                        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(job))
+                            .unwrap_or(Err(vk_utils::Error::AssetLoadPanicked));
+
+                        if result_sender.send((id, result)).is_err() {
+                            break;
+                        }
+                    })?;
+
+                loader.workers.push(worker);
+            }
+
+            Ok(loader)
+        }
+
+        // Queues `job` for the next free worker, returns right away
+        pub fn load<F>(&mut self, job: F) -> AssetId
+        where
+            F: FnOnce() -> vk_utils::Result<T> + Send + 'static,
+        {
+            let id = self.next_id;
+
+            self.next_id += 1;
+            if let Some(jobs) = self.jobs.as_ref() {
+                // Workers only go away along with the loader
+                if jobs.send((id, Box::new(job))).is_ok() {
+                    self.pending += 1;
+                }
+            }
+
+            id
+        }
+
+        // Results finished since the last call, in completion order. Never blocks
+        pub fn poll(&mut self) -> Vec<(AssetId, vk_utils::Result<T>)> {
+            let results = self.results.try_iter().collect::<Vec<_>>();
+
+            self.pending -= results.len();
+
+            results
+        }
+
+        // Blocks until every queued job is done
+        pub fn wait(&mut self) -> Vec<(AssetId, vk_utils::Result<T>)> {
+            let mut results = Vec::with_capacity(self.pending);
+
+            while self.pending > 0 {
+                match self.results.recv() {
+                    Ok(result) => {
+                        results.push(result);
+                        self.pending -= 1;
+                    }
+                    // Every worker is gone
+                    Err(_) => {
+                        self.pending = 0;
+                    }
+                }
+            }
+
+            results
+        }
+
+        // Jobs queued or running, whose results haven't been polled yet
+        pub fn pending(&self) -> usize {
+            self.pending
+        }
+    }
+
+    impl<T> Drop for AssetLoader<T> {
+        fn drop(&mut self) {
+            // Workers finish the job at hand, then see the closed channel
+            self.is_cancelled.store(true, Ordering::Relaxed);
+            self.jobs = None;
+
+            for worker in self.workers.drain(..) {
+                let _ = worker.join();
+            }
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use std::sync::atomic::AtomicUsize;
+        use std::time::Duration;
+
+        #[test]
+        fn results_are_tagged_with_their_job() {
+            let mut loader = AssetLoader::new(2).unwrap();
+            let ids = ["first", "second", "third"]
+                .map(|name| (loader.load(move || Ok(name.to_string())), name));
+
+            let mut results = loader.wait();
+            results.sort_by_key(|&(id, _)| id);
+
+            assert_eq!(results.len(), ids.len());
+            for ((id, result), (expected_id, name)) in results.into_iter().zip(ids) {
+                assert_eq!(id, expected_id);
+                assert_eq!(result.unwrap(), name);
+            }
+        }
+
+        #[test]
+        fn waiting_leaves_nothing_pending() {
+            let mut loader = AssetLoader::new(2).unwrap();
+
+            for value in 0..5 {
+                loader.load(move || Ok(value));
+            }
+            assert_eq!(loader.pending(), 5);
+
+            assert_eq!(loader.wait().len(), 5);
+            assert_eq!(loader.pending(), 0);
+            assert!(loader.poll().is_empty());
+            assert!(loader.wait().is_empty());
+        }
+
+        #[test]
+        fn panicking_jobs_still_get_a_result() {
+            let mut loader = AssetLoader::<u32>::new(1).unwrap();
+            let id = loader.load(|| panic!("broken asset"));
+            let after = loader.load(|| Ok(1));
+
+            let mut results = loader.wait();
+            results.sort_by_key(|&(id, _)| id);
+
+            assert!(matches!(
+                results[0],
+                (panicked, Err(vk_utils::Error::AssetLoadPanicked)) if panicked == id
+            ));
+            // The worker survives the panic
+            assert!(matches!(results[1], (next, Ok(1)) if next == after));
+            assert_eq!(loader.pending(), 0);
+        }
+
+        #[test]
+        fn dropping_skips_queued_jobs() {
+            let mut loader = AssetLoader::new(1).unwrap();
+            let (started_sender, started) = mpsc::channel();
+            let (release, released) = mpsc::channel::<()>();
+            let ran = Arc::new(AtomicUsize::new(0));
+
+            // Keeps the only worker busy until the loader is being dropped
+            loader.load(move || {
+                started_sender.send(()).unwrap();
+                let _ = released.recv();
+
+                Ok(())
+            });
+            started.recv().unwrap();
+
+            for _ in 0..4 {
+                let ran = ran.clone();
+
+                loader.load(move || {
+                    ran.fetch_add(1, Ordering::SeqCst);
+
+                    Ok(())
+                });
+            }
+
+            let dropping = thread::spawn(move || drop(loader));
+            // Drop cancels first and then joins, which waits for the running job
+            thread::sleep(Duration::from_millis(100));
+            release.send(()).unwrap();
+            dropping.join().unwrap();
+
+            assert_eq!(ran.load(Ordering::SeqCst), 0);
+        }
+    }
+}
+
+pub use _loader::{AssetId, AssetLoader};
@@ -0,0 +1,473 @@
+mod _memory {
+    use crate::{self as vk_utils, constants::memory::*};
+    use ash::vk;
+    use std::sync::{Arc, Mutex, MutexGuard};
+
+    #[derive(Clone, Copy, Debug, Default)]
+    pub struct MemoryStatistics {
+        pub block_count: usize,
+        pub allocation_count: usize,
+        pub allocated_bytes: vk::DeviceSize, // reserved from the driver
+        pub used_bytes: vk::DeviceSize,      // handed out to resources
+    }
+
+    struct MemoryBlock {
+        memory: vk::DeviceMemory,
+        size: vk::DeviceSize,
+        memory_type_index: u32,
+        linear: bool,
+        // Host visible blocks stay mapped for their whole lifetime
+        mapped: *mut u8,
+        // (offset, size), sorted by offset
+        free_ranges: Vec<(vk::DeviceSize, vk::DeviceSize)>,
+        allocation_count: usize,
+    }
+
+    impl MemoryBlock {
+        fn allocate(
+            &mut self,
+            size: vk::DeviceSize,
+            alignment: vk::DeviceSize,
+        ) -> Option<vk::DeviceSize> {
+            // First fit, the alignment padding stays free
+            let (index, offset) = self.free_ranges.iter().enumerate().find_map(
+                |(index, &(range_offset, range_size))| {
+                    let offset = align_up(range_offset, alignment);
+
+                    (offset + size <= range_offset + range_size).then_some((index, offset))
+                },
+            )?;
+            let (range_offset, range_size) = self.free_ranges.remove(index);
+            let range_end = range_offset + range_size;
+
+            if offset + size < range_end {
+                self.free_ranges
+                    .insert(index, (offset + size, range_end - offset - size));
+            }
+            if range_offset < offset {
+                self.free_ranges
+                    .insert(index, (range_offset, offset - range_offset));
+            }
+            self.allocation_count += 1;
+
+            Some(offset)
+        }
+
+        fn free(&mut self, offset: vk::DeviceSize, size: vk::DeviceSize) {
+            // Merging with the neighbouring free ranges
+            let index = self
+                .free_ranges
+                .partition_point(|&(range_offset, _)| range_offset < offset);
+            let (mut offset, mut size) = (offset, size);
+
+            if index < self.free_ranges.len() && offset + size == self.free_ranges[index].0 {
+                size += self.free_ranges.remove(index).1;
+            }
+            if index > 0 {
+                let (previous_offset, previous_size) = self.free_ranges[index - 1];
+
+                if previous_offset + previous_size == offset {
+                    self.free_ranges.remove(index - 1);
+                    offset = previous_offset;
+                    size += previous_size;
+
+                    self.free_ranges.insert(index - 1, (offset, size));
+                    self.allocation_count -= 1;
+
+                    return;
+                }
+            }
+
+            self.free_ranges.insert(index, (offset, size));
+            self.allocation_count -= 1;
+        }
+
+        fn used_bytes(&self) -> vk::DeviceSize {
+            self.size - self.free_ranges.iter().map(|&(_, size)| size).sum::<u64>()
+        }
+    }
+
+    struct AllocatorState {
+        device: ash::Device,
+        memory_properties: vk::PhysicalDeviceMemoryProperties,
+        buffer_image_granularity: vk::DeviceSize,
+        // Freed blocks leave a None behind so block ids stay valid
+        blocks: Vec<Option<MemoryBlock>>,
+    }
+
+    // Mapped pointers are only handed out together with the allocation owning the range
+    unsafe impl Send for AllocatorState {}
+
+    impl AllocatorState {
+        fn block_size(&self, memory_type_index: u32) -> vk::DeviceSize {
+            let heap_index =
+                self.memory_properties.memory_types[memory_type_index as usize].heap_index;
+            let heap_size = self.memory_properties.memory_heaps[heap_index as usize].size;
+
+            std::cmp::min(BLOCK_SIZE, heap_size / MIN_BLOCKS_PER_HEAP)
+        }
+
+        fn create_block(
+            &mut self,
+            memory_type_index: u32,
+            size: vk::DeviceSize,
+            linear: bool,
+        ) -> vk_utils::Result<usize> {
+            let alloc_info = vk::MemoryAllocateInfo::builder()
+                .allocation_size(size)
+                .memory_type_index(memory_type_index);
+            let memory = unsafe {
+                self.device
+                    .allocate_memory(&alloc_info, None)
+                    .map_err(vk_utils::Error::vulkan("failed to allocate memory block!"))?
+            };
+
+            let property_flags =
+                self.memory_properties.memory_types[memory_type_index as usize].property_flags;
+            let mapped = if property_flags.contains(vk::MemoryPropertyFlags::HOST_VISIBLE) {
+                let result = unsafe {
+                    self.device
+                        .map_memory(memory, 0, vk::WHOLE_SIZE, vk::MemoryMapFlags::empty())
+                };
+
+                match result {
+                    Ok(mapped) => mapped as *mut u8,
+                    Err(vk_result) => {
+                        unsafe {
+                            self.device.free_memory(memory, None);
+                        }
+
+                        return Err(vk_utils::Error::Vulkan("failed to map memory!", vk_result));
+                    }
+                }
+            } else {
+                std::ptr::null_mut()
+            };
+
+            let block = MemoryBlock {
+                memory,
+                size,
+                memory_type_index,
+                linear,
+                mapped,
+                free_ranges: vec![(0, size)],
+                allocation_count: 0,
+            };
+
+            match self.blocks.iter().position(|block| block.is_none()) {
+                Some(block_id) => {
+                    self.blocks[block_id] = Some(block);
+
+                    Ok(block_id)
+                }
+                None => {
+                    self.blocks.push(Some(block));
+
+                    Ok(self.blocks.len() - 1)
+                }
+            }
+        }
+
+        fn destroy_block(&mut self, block_id: usize) {
+            if let Some(block) = self.blocks[block_id].take() {
+                unsafe {
+                    // Freeing implicitly unmaps the memory
+                    self.device.free_memory(block.memory, None);
+                }
+            }
+        }
+    }
+
+    impl Drop for AllocatorState {
+        fn drop(&mut self) {
+            for block_id in 0..self.blocks.len() {
+                self.destroy_block(block_id);
+            }
+        }
+    }
+
+    // Carves buffers and images out of large memory blocks, one set per memory type
+    #[derive(Clone)]
+    pub struct Allocator {
+        state: Arc<Mutex<AllocatorState>>,
+    }
+
+    impl Allocator {
+        pub fn new(
+            instance: &ash::Instance,
+            device: &ash::Device,
+            physical_device: vk::PhysicalDevice,
+        ) -> Self {
+            let memory_properties =
+                unsafe { instance.get_physical_device_memory_properties(physical_device) };
+            let properties = unsafe { instance.get_physical_device_properties(physical_device) };
+
+            Self {
+                state: Arc::new(Mutex::new(AllocatorState {
+                    device: device.clone(),
+                    memory_properties,
+                    buffer_image_granularity: properties.limits.buffer_image_granularity,
+                    blocks: Vec::new(),
+                })),
+            }
+        }
+
+        fn lock(&self) -> MutexGuard<'_, AllocatorState> {
+            // Bookkeeping is updated before anything that can panic
+            self.state
+                .lock()
+                .unwrap_or_else(|poisoned| poisoned.into_inner())
+        }
+
+        pub fn memory_properties(&self) -> vk::PhysicalDeviceMemoryProperties {
+            self.lock().memory_properties
+        }
+
+        // `linear` is true for buffers and linearly tiled images
+        pub fn allocate(
+            &self,
+            requirements: vk::MemoryRequirements,
+            properties: vk::MemoryPropertyFlags,
+            linear: bool,
+        ) -> vk_utils::Result<Allocation> {
+            let mut state = self.lock();
+            let memory_type_index = vk_utils::buffer::find_memory_type(
+                requirements.memory_type_bits,
+                properties,
+                &state.memory_properties,
+            )?;
+            // Linear and optimal resources only share blocks when the device doesn't care
+            let linear = linear || state.buffer_image_granularity <= 1;
+            let block_size = state.block_size(memory_type_index);
+
+            let found = state
+                .blocks
+                .iter_mut()
+                .enumerate()
+                .filter_map(|(block_id, block)| block.as_mut().map(|block| (block_id, block)))
+                .filter(|(_, block)| {
+                    block.memory_type_index == memory_type_index && block.linear == linear
+                })
+                .find_map(|(block_id, block)| {
+                    block
+                        .allocate(requirements.size, requirements.alignment)
+                        .map(|offset| (block_id, offset, block.memory, block.mapped))
+                });
+
+            let (block_id, offset, memory, mapped) = match found {
+                Some(found) => found,
+                None => {
+                    // Oversized resources get a block of their own
+                    let size = std::cmp::max(block_size, requirements.size);
+                    let block_id = state.create_block(memory_type_index, size, linear)?;
+
+                    state.blocks[block_id]
+                        .as_mut()
+                        .and_then(|block| {
+                            block
+                                .allocate(requirements.size, requirements.alignment)
+                                .map(|offset| (block_id, offset, block.memory, block.mapped))
+                        })
+                        .ok_or(vk_utils::Error::NoSuitableMemoryType)?
+                }
+            };
+
+            Ok(Allocation {
+                allocator: self.clone(),
+                block_id,
+                memory,
+                offset,
+                size: requirements.size,
+                mapped: if mapped.is_null() {
+                    mapped
+                } else {
+                    unsafe { mapped.add(offset as usize) }
+                },
+            })
+        }
+
+        fn free(&self, block_id: usize, offset: vk::DeviceSize, size: vk::DeviceSize) {
+            let mut state = self.lock();
+            let block_size = state.block_size(
+                state.blocks[block_id]
+                    .as_ref()
+                    .map_or(0, |block| block.memory_type_index),
+            );
+
+            let is_dedicated_and_empty = match state.blocks[block_id].as_mut() {
+                Some(block) => {
+                    block.free(offset, size);
+
+                    block.allocation_count == 0 && block.size > block_size
+                }
+                None => false,
+            };
+
+            // Regular blocks are kept around for reuse, see free_empty_blocks
+            if is_dedicated_and_empty {
+                state.destroy_block(block_id);
+            }
+        }
+
+        // Gives empty blocks back to the driver
+        pub fn free_empty_blocks(&self) {
+            let mut state = self.lock();
+
+            for block_id in 0..state.blocks.len() {
+                let is_empty = state.blocks[block_id]
+                    .as_ref()
+                    .is_some_and(|block| block.allocation_count == 0);
+
+                if is_empty {
+                    state.destroy_block(block_id);
+                }
+            }
+        }
+
+        pub fn statistics(&self) -> MemoryStatistics {
+            self.lock().blocks.iter().flatten().fold(
+                MemoryStatistics::default(),
+                |statistics, block| MemoryStatistics {
+                    block_count: statistics.block_count + 1,
+                    allocation_count: statistics.allocation_count + block.allocation_count,
+                    allocated_bytes: statistics.allocated_bytes + block.size,
+                    used_bytes: statistics.used_bytes + block.used_bytes(),
+                },
+            )
+        }
+    }
+
+    // Range of a memory block, given back to the allocator when dropped
+    pub struct Allocation {
+        allocator: Allocator,
+        block_id: usize,
+        memory: vk::DeviceMemory,
+        offset: vk::DeviceSize,
+        size: vk::DeviceSize,
+        mapped: *mut u8,
+    }
+
+    // The mapped range belongs to this allocation alone
+    unsafe impl Send for Allocation {}
+    unsafe impl Sync for Allocation {}
+
+    impl Allocation {
+        pub fn memory(&self) -> vk::DeviceMemory {
+            self.memory
+        }
+
+        pub fn offset(&self) -> vk::DeviceSize {
+            self.offset
+        }
+
+        pub fn size(&self) -> vk::DeviceSize {
+            self.size
+        }
+
+        // None unless the memory is host visible
+        pub fn mapped_ptr(&self) -> Option<*mut u8> {
+            if self.mapped.is_null() {
+                None
+            } else {
+                Some(self.mapped)
+            }
+        }
+    }
+
+    impl Drop for Allocation {
+        fn drop(&mut self) {
+            self.allocator.free(self.block_id, self.offset, self.size);
+        }
+    }
+
+    fn align_up(value: vk::DeviceSize, alignment: vk::DeviceSize) -> vk::DeviceSize {
+        if alignment <= 1 {
+            value
+        } else {
+            value.div_ceil(alignment) * alignment
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        fn block(size: vk::DeviceSize) -> MemoryBlock {
+            MemoryBlock {
+                memory: vk::DeviceMemory::null(),
+                size,
+                memory_type_index: 0,
+                linear: true,
+                mapped: std::ptr::null_mut(),
+                free_ranges: vec![(0, size)],
+                allocation_count: 0,
+            }
+        }
+
+        #[test]
+        fn allocations_split_the_free_range() {
+            let mut block = block(1024);
+
+            assert_eq!(block.allocate(256, 1), Some(0));
+            assert_eq!(block.allocate(128, 1), Some(256));
+            assert_eq!(block.free_ranges, vec![(384, 640)]);
+            assert_eq!(block.allocation_count, 2);
+            assert_eq!(block.used_bytes(), 384);
+        }
+
+        #[test]
+        fn freed_ranges_coalesce_with_both_neighbours() {
+            let mut block = block(1024);
+            let first = block.allocate(256, 1).unwrap();
+            let second = block.allocate(256, 1).unwrap();
+            let third = block.allocate(256, 1).unwrap();
+
+            block.free(first, 256);
+            block.free(third, 256);
+            assert_eq!(block.free_ranges, vec![(0, 256), (512, 512)]);
+
+            block.free(second, 256);
+            assert_eq!(block.free_ranges, vec![(0, 1024)]);
+            assert_eq!(block.allocation_count, 0);
+            assert_eq!(block.used_bytes(), 0);
+        }
+
+        #[test]
+        fn alignment_padding_stays_free() {
+            let mut block = block(1024);
+
+            assert_eq!(block.allocate(10, 1), Some(0));
+            assert_eq!(block.allocate(64, 256), Some(256));
+            assert_eq!(block.free_ranges, vec![(10, 246), (320, 704)]);
+
+            // The padding is still handed out to resources that fit
+            assert_eq!(block.allocate(16, 16), Some(16));
+        }
+
+        #[test]
+        fn exhausted_blocks_refuse_allocations() {
+            let mut block = block(512);
+
+            assert_eq!(block.allocate(512, 1), Some(0));
+            assert!(block.free_ranges.is_empty());
+            assert_eq!(block.allocate(1, 1), None);
+
+            block.free(0, 512);
+            assert_eq!(block.allocate(256, 1), Some(0));
+            // Fits by size but not once aligned
+            assert_eq!(block.allocate(200, 512), None);
+        }
+
+        #[test]
+        fn align_up_rounds_to_the_next_multiple() {
+            assert_eq!(align_up(0, 256), 0);
+            assert_eq!(align_up(1, 256), 256);
+            assert_eq!(align_up(256, 256), 256);
+            assert_eq!(align_up(257, 256), 512);
+            assert_eq!(align_up(13, 0), 13);
+            assert_eq!(align_up(13, 1), 13);
+        }
+    }
+}
+
+pub use _memory::{Allocation, Allocator, MemoryStatistics};
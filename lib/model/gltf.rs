@@ -0,0 +1,613 @@
+use super::{Model, SubMesh};
+use crate::types::VertexWithTexture3D;
+use cgmath::{InnerSpace, Matrix, Matrix3, Matrix4, SquareMatrix};
+
+// One glTF mesh, each primitive becomes a submesh
+pub struct GltfMesh {
+    pub name: String,
+    pub vertices: Vec<VertexWithTexture3D>,
+    pub indices: Vec<u32>,
+    pub submeshes: Vec<SubMesh>,
+}
+
+pub struct SceneNode {
+    pub name: String,
+    pub local_transform: Matrix4<f32>,
+    // Local transform combined with all the parents
+    pub world_transform: Matrix4<f32>,
+    pub mesh: Option<usize>,
+    pub parent: Option<usize>,
+    pub children: Vec<usize>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AlphaMode {
+    Opaque,
+    Mask,
+    Blend,
+}
+
+// Metallic-roughness material, texture fields index GltfScene::textures
+pub struct PbrMaterial {
+    pub name: String,
+    pub base_color_factor: [f32; 4],
+    pub base_color_texture: Option<usize>,
+    pub metallic_factor: f32,
+    pub roughness_factor: f32,
+    pub metallic_roughness_texture: Option<usize>,
+    pub normal_texture: Option<usize>,
+    pub occlusion_texture: Option<usize>,
+    pub emissive_factor: [f32; 3],
+    pub emissive_texture: Option<usize>,
+    pub alpha_mode: AlphaMode,
+    pub alpha_cutoff: f32,
+    pub double_sided: bool,
+}
+
+// Decoded image, always RGBA8 with rows top to bottom
+pub struct TextureData {
+    pub name: String,
+    pub width: u32,
+    pub height: u32,
+    pub pixels: Vec<u8>,
+    // Color textures are sRGB encoded, data textures are linear
+    pub srgb: bool,
+}
+
+pub struct GltfScene {
+    pub meshes: Vec<GltfMesh>,
+    pub nodes: Vec<SceneNode>,
+    pub root_nodes: Vec<usize>,
+    pub materials: Vec<PbrMaterial>,
+    pub textures: Vec<TextureData>,
+}
+
+impl GltfScene {
+    // (mesh, world transform) for every node that draws something
+    pub fn mesh_instances(&self) -> impl Iterator<Item = (usize, Matrix4<f32>)> + '_ {
+        self.nodes
+            .iter()
+            .filter_map(|node| node.mesh.map(|mesh| (mesh, node.world_transform)))
+    }
+
+    // Flattens all mesh instances into a single model in world space,
+    // submesh material ids index GltfScene::materials
+    pub fn to_model(&self) -> Model {
+        let mut vertices = vec![];
+        let mut indices = vec![];
+        let mut submeshes = vec![];
+
+        for (mesh_index, transform) in self.mesh_instances() {
+            let mesh = &self.meshes[mesh_index];
+            let normal_matrix = normal_matrix(&transform);
+            let first_vertex = vertices.len() as u32;
+            let first_index = indices.len() as u32;
+
+            vertices.extend(mesh.vertices.iter().map(|vertex| {
+                let pos = transform * cgmath::Vector4::from(vertex.pos);
+                let mut normal = normal_matrix * cgmath::Vector3::from(vertex.normal);
+
+                if normal.magnitude2() > 0.0 {
+                    normal = normal.normalize();
+                }
+
+                VertexWithTexture3D {
+                    pos: pos.into(),
+                    normal: normal.into(),
+                    ..*vertex
+                }
+            }));
+            indices.extend(mesh.indices.iter().map(|index| first_vertex + index));
+            submeshes.extend(mesh.submeshes.iter().map(|submesh| SubMesh {
+                name: submesh.name.clone(),
+                first_index: first_index + submesh.first_index,
+                index_count: submesh.index_count,
+                material_id: submesh.material_id,
+            }));
+        }
+
+        Model {
+            vertices,
+            indices,
+            submeshes,
+            materials: vec![],
+        }
+    }
+}
+
+// Loads .gltf (with external or embedded buffers and images) and .glb files
+pub fn load_gltf(path: &std::path::Path) -> crate::Result<GltfScene> {
+    let (document, buffers, images) = ::gltf::import(path)?;
+
+    let meshes = document
+        .meshes()
+        .map(|mesh| load_mesh(&mesh, &buffers))
+        .collect::<crate::Result<Vec<_>>>()?;
+    let materials = document
+        .materials()
+        .map(|material| load_material(&material))
+        .collect();
+
+    // Only textures used for color are stored as sRGB
+    let mut srgb = vec![false; images.len()];
+
+    for material in document.materials() {
+        let pbr = material.pbr_metallic_roughness();
+        let color_textures = [pbr.base_color_texture(), material.emissive_texture()];
+
+        for info in color_textures.iter().flatten() {
+            srgb[info.texture().source().index()] = true;
+        }
+    }
+
+    let textures = document
+        .images()
+        .zip(images.iter())
+        .map(|(image, data)| {
+            Ok(TextureData {
+                name: image.name().unwrap_or_default().to_string(),
+                width: data.width,
+                height: data.height,
+                pixels: to_rgba8(data)?,
+                srgb: srgb[image.index()],
+            })
+        })
+        .collect::<crate::Result<Vec<_>>>()?;
+
+    let mut nodes = document
+        .nodes()
+        .map(|node| SceneNode {
+            name: node.name().unwrap_or_default().to_string(),
+            local_transform: Matrix4::from(node.transform().matrix()),
+            world_transform: Matrix4::identity(),
+            mesh: node.mesh().map(|mesh| mesh.index()),
+            parent: None,
+            children: node.children().map(|child| child.index()).collect(),
+        })
+        .collect::<Vec<_>>();
+
+    for index in 0..nodes.len() {
+        for child in nodes[index].children.clone() {
+            nodes[child].parent = Some(index);
+        }
+    }
+
+    // Files without a default scene get the first one, or every parentless node
+    let root_nodes: Vec<usize> = match document
+        .default_scene()
+        .or_else(|| document.scenes().next())
+    {
+        Some(scene) => scene.nodes().map(|node| node.index()).collect(),
+        None => (0..nodes.len())
+            .filter(|&index| nodes[index].parent.is_none())
+            .collect(),
+    };
+
+    // Parents are always resolved before their children
+    let mut stack = root_nodes
+        .iter()
+        .map(|&index| (index, Matrix4::identity()))
+        .collect::<Vec<_>>();
+
+    while let Some((index, parent_transform)) = stack.pop() {
+        let world_transform = parent_transform * nodes[index].local_transform;
+
+        nodes[index].world_transform = world_transform;
+        stack.extend(
+            nodes[index]
+                .children
+                .iter()
+                .map(|&child| (child, world_transform)),
+        );
+    }
+
+    Ok(GltfScene {
+        meshes,
+        nodes,
+        root_nodes,
+        materials,
+        textures,
+    })
+}
+
+fn load_mesh(mesh: &::gltf::Mesh, buffers: &[::gltf::buffer::Data]) -> crate::Result<GltfMesh> {
+    let mut vertices = vec![];
+    let mut indices = vec![];
+    let mut submeshes = vec![];
+
+    for primitive in mesh.primitives() {
+        if primitive.mode() != ::gltf::mesh::Mode::Triangles {
+            return Err(crate::Error::InvalidModel(
+                "Only triangle list primitives are supported!",
+            ));
+        }
+
+        let reader = primitive.reader(|buffer| Some(&buffers[buffer.index()]));
+        let positions = reader
+            .read_positions()
+            .ok_or(crate::Error::InvalidModel("Missing vertex positions!"))?
+            .collect::<Vec<_>>();
+        // Textures need coordinates, as for OBJ models
+        let tex_coords = reader
+            .read_tex_coords(0)
+            .ok_or(crate::Error::InvalidModel(
+                "Missing texture coordinate for the model!",
+            ))?
+            .into_f32()
+            .collect::<Vec<_>>();
+        // Normals and colors are optional in glTF
+        let normals = reader
+            .read_normals()
+            .map_or_else(Vec::new, |normals| normals.collect());
+        let colors = reader
+            .read_colors(0)
+            .map_or_else(Vec::new, |colors| colors.into_rgb_f32().collect());
+
+        let first_vertex = vertices.len() as u32;
+        let first_index = indices.len() as u32;
+
+        vertices.extend(positions.iter().enumerate().map(|(i, position)| {
+            VertexWithTexture3D::new(
+                [position[0], position[1], position[2], 1.0],
+                colors.get(i).copied().unwrap_or([1.0, 1.0, 1.0]),
+                tex_coords.get(i).copied().unwrap_or([0.0, 0.0]),
+            )
+            .with_normal(normals.get(i).copied().unwrap_or([0.0, 0.0, 0.0]))
+        }));
+
+        match reader.read_indices() {
+            Some(primitive_indices) => {
+                for index in primitive_indices.into_u32() {
+                    if index as usize >= positions.len() {
+                        return Err(crate::Error::InvalidModel("Vertex index out of range!"));
+                    }
+
+                    indices.push(first_vertex + index);
+                }
+            }
+            // Non-indexed primitives draw their vertices in order
+            None => indices.extend(first_vertex..first_vertex + positions.len() as u32),
+        }
+
+        submeshes.push(SubMesh {
+            name: mesh.name().unwrap_or_default().to_string(),
+            first_index,
+            index_count: indices.len() as u32 - first_index,
+            material_id: primitive.material().index(),
+        });
+    }
+
+    Ok(GltfMesh {
+        name: mesh.name().unwrap_or_default().to_string(),
+        vertices,
+        indices,
+        submeshes,
+    })
+}
+
+fn load_material(material: &::gltf::Material) -> PbrMaterial {
+    let pbr = material.pbr_metallic_roughness();
+    let image_index = |texture: ::gltf::Texture| texture.source().index();
+
+    PbrMaterial {
+        name: material.name().unwrap_or_default().to_string(),
+        base_color_factor: pbr.base_color_factor(),
+        base_color_texture: pbr
+            .base_color_texture()
+            .map(|info| image_index(info.texture())),
+        metallic_factor: pbr.metallic_factor(),
+        roughness_factor: pbr.roughness_factor(),
+        metallic_roughness_texture: pbr
+            .metallic_roughness_texture()
+            .map(|info| image_index(info.texture())),
+        normal_texture: material
+            .normal_texture()
+            .map(|info| image_index(info.texture())),
+        occlusion_texture: material
+            .occlusion_texture()
+            .map(|info| image_index(info.texture())),
+        emissive_factor: material.emissive_factor(),
+        emissive_texture: material
+            .emissive_texture()
+            .map(|info| image_index(info.texture())),
+        alpha_mode: match material.alpha_mode() {
+            ::gltf::material::AlphaMode::Opaque => AlphaMode::Opaque,
+            ::gltf::material::AlphaMode::Mask => AlphaMode::Mask,
+            ::gltf::material::AlphaMode::Blend => AlphaMode::Blend,
+        },
+        alpha_cutoff: material.alpha_cutoff().unwrap_or(0.5),
+        double_sided: material.double_sided(),
+    }
+}
+
+// Expands every glTF pixel format to 8 bits per channel RGBA
+fn to_rgba8(data: &::gltf::image::Data) -> crate::Result<Vec<u8>> {
+    use ::gltf::image::Format;
+
+    let pixel_count = (data.width * data.height) as usize;
+    // (channels, bytes per channel)
+    let (channels, channel_size) = match data.format {
+        Format::R8 => (1, 1),
+        Format::R8G8 => (2, 1),
+        Format::R8G8B8 => (3, 1),
+        Format::R8G8B8A8 => (4, 1),
+        Format::R16 => (1, 2),
+        Format::R16G16 => (2, 2),
+        Format::R16G16B16 => (3, 2),
+        Format::R16G16B16A16 => (4, 2),
+        Format::R32G32B32FLOAT => (3, 4),
+        Format::R32G32B32A32FLOAT => (4, 4),
+    };
+
+    if data.pixels.len() != pixel_count * channels * channel_size {
+        return Err(crate::Error::InvalidImage(
+            "glTF image data does not match its size!",
+        ));
+    }
+
+    let channel = |bytes: &[u8]| -> u8 {
+        match channel_size {
+            1 => bytes[0],
+            // Little endian, the high byte is enough
+            2 => bytes[1],
+            _ => {
+                let value = f32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
+
+                (value.clamp(0.0, 1.0) * 255.0).round() as u8
+            }
+        }
+    };
+
+    let mut pixels = Vec::with_capacity(pixel_count * 4);
+
+    for pixel in data.pixels.chunks_exact(channels * channel_size) {
+        let values = pixel
+            .chunks_exact(channel_size)
+            .map(channel)
+            .collect::<Vec<_>>();
+
+        pixels.extend_from_slice(&match values[..] {
+            // Single channel images are grayscale
+            [r] => [r, r, r, 255],
+            [r, g] => [r, g, 0, 255],
+            [r, g, b] => [r, g, b, 255],
+            [r, g, b, a] => [r, g, b, a],
+            _ => unreachable!(),
+        });
+    }
+
+    Ok(pixels)
+}
+
+// Inverse transpose keeps normals perpendicular under non-uniform scaling
+fn normal_matrix(transform: &Matrix4<f32>) -> Matrix3<f32> {
+    let upper_left = Matrix3::from_cols(
+        transform.x.truncate(),
+        transform.y.truncate(),
+        transform.z.truncate(),
+    );
+
+    upper_left
+        .invert()
+        .map_or(upper_left, |inverse| inverse.transpose())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Parent node moved along x, child scaled by 2 drawing a single triangle.
+    // Texture 1 samples image 0, so indices and sources differ
+    const DOCUMENT: &str = r#"{
+        "asset": { "version": "2.0" },
+        "scene": 0,
+        "scenes": [{ "nodes": [0] }],
+        "nodes": [
+            { "name": "parent", "translation": [1, 0, 0], "children": [1] },
+            { "name": "child", "scale": [2, 2, 2], "mesh": 0 }
+        ],
+        "meshes": [{
+            "name": "triangle",
+            "primitives": [{ "attributes": ATTRIBUTES, "indices": 2, "material": 0 }]
+        }],
+        "materials": [{
+            "name": "painted",
+            "pbrMetallicRoughness": {
+                "baseColorFactor": [0.5, 0.25, 1.0, 1.0],
+                "baseColorTexture": { "index": 1 },
+                "metallicFactor": 0.25,
+                "roughnessFactor": 0.75,
+                "metallicRoughnessTexture": { "index": 0 }
+            },
+            "alphaMode": "MASK",
+            "alphaCutoff": 0.25
+        }],
+        "textures": [{ "source": 1 }, { "source": 0 }],
+        "images": [{ "uri": "IMAGE" }, { "uri": "IMAGE" }],
+        "buffers": [BUFFER],
+        "bufferViews": [
+            { "buffer": 0, "byteOffset": 0, "byteLength": 36 },
+            { "buffer": 0, "byteOffset": 36, "byteLength": 24 },
+            { "buffer": 0, "byteOffset": 60, "byteLength": 6 }
+        ],
+        "accessors": [
+            { "bufferView": 0, "componentType": 5126, "count": 3, "type": "VEC3",
+              "min": [0, 0, 0], "max": [1, 1, 0] },
+            { "bufferView": 1, "componentType": 5126, "count": 3, "type": "VEC2" },
+            { "bufferView": 2, "componentType": 5123, "count": 3, "type": "SCALAR" }
+        ]
+    }"#;
+
+    fn buffer_data() -> Vec<u8> {
+        let positions = [0.0f32, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 1.0, 0.0];
+        let tex_coords = [0.0f32, 0.0, 1.0, 0.0, 0.0, 1.0];
+        let mut data = positions
+            .iter()
+            .chain(tex_coords.iter())
+            .flat_map(|value| value.to_le_bytes())
+            .collect::<Vec<_>>();
+
+        data.extend([0u16, 1, 2].iter().flat_map(|index| index.to_le_bytes()));
+        // Chunks and buffers are 4-byte aligned
+        data.resize(68, 0);
+
+        data
+    }
+
+    fn base64(data: &[u8]) -> String {
+        const ALPHABET: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+        data.chunks(3)
+            .flat_map(|chunk| {
+                let bytes = [
+                    chunk[0],
+                    *chunk.get(1).unwrap_or(&0),
+                    *chunk.get(2).unwrap_or(&0),
+                ];
+                let bits = u32::from_be_bytes([0, bytes[0], bytes[1], bytes[2]]);
+
+                // Missing input bytes are padded
+                (0..4).map(move |i| {
+                    if i <= chunk.len() {
+                        ALPHABET[(bits >> (18 - 6 * i) & 0x3f) as usize] as char
+                    } else {
+                        '='
+                    }
+                })
+            })
+            .collect()
+    }
+
+    fn png_uri() -> String {
+        let mut png = vec![];
+
+        image::DynamicImage::ImageRgba8(image::RgbaImage::new(1, 1))
+            .write_to(
+                &mut std::io::Cursor::new(&mut png),
+                image::ImageOutputFormat::Png,
+            )
+            .unwrap();
+
+        format!("data:image/png;base64,{}", base64(&png))
+    }
+
+    fn document(attributes: &str, buffer: &str) -> String {
+        DOCUMENT
+            .replace("ATTRIBUTES", attributes)
+            .replace("IMAGE", &png_uri())
+            .replace("BUFFER", buffer)
+    }
+
+    fn write_gltf(name: &str, attributes: &str) -> std::path::PathBuf {
+        let path = std::env::temp_dir().join(format!("vk_utils_gltf_{}.gltf", name));
+        let buffer = format!(
+            r#"{{ "byteLength": 68, "uri": "data:application/octet-stream;base64,{}" }}"#,
+            base64(&buffer_data())
+        );
+
+        std::fs::write(&path, document(attributes, &buffer)).unwrap();
+
+        path
+    }
+
+    // Same document with the buffer in the binary chunk
+    fn write_glb(name: &str) -> std::path::PathBuf {
+        let path = std::env::temp_dir().join(format!("vk_utils_gltf_{}.glb", name));
+        let mut json = document(
+            r#"{ "POSITION": 0, "TEXCOORD_0": 1 }"#,
+            r#"{ "byteLength": 68 }"#,
+        )
+        .into_bytes();
+        let bin = buffer_data();
+
+        json.resize(json.len().div_ceil(4) * 4, b' ');
+
+        let mut glb = vec![];
+        let total_length = 12 + 8 + json.len() + 8 + bin.len();
+
+        glb.extend(b"glTF");
+        glb.extend(2u32.to_le_bytes());
+        glb.extend((total_length as u32).to_le_bytes());
+        glb.extend((json.len() as u32).to_le_bytes());
+        glb.extend(b"JSON");
+        glb.extend(&json);
+        glb.extend((bin.len() as u32).to_le_bytes());
+        glb.extend(b"BIN\0");
+        glb.extend(&bin);
+
+        std::fs::write(&path, glb).unwrap();
+
+        path
+    }
+
+    fn load_triangle(name: &str) -> GltfScene {
+        load_gltf(&write_gltf(name, r#"{ "POSITION": 0, "TEXCOORD_0": 1 }"#)).unwrap()
+    }
+
+    #[test]
+    fn node_transforms_are_parent_times_child() {
+        let scene = load_triangle("transforms");
+        let expected = Matrix4::from_translation(cgmath::Vector3::new(1.0, 0.0, 0.0))
+            * Matrix4::from_scale(2.0);
+
+        assert_eq!(scene.root_nodes, vec![0]);
+        assert_eq!(scene.nodes[1].parent, Some(0));
+        assert_eq!(scene.nodes[1].world_transform, expected);
+        assert_eq!(
+            scene.mesh_instances().collect::<Vec<_>>(),
+            vec![(0, expected)]
+        );
+
+        // Scaled first, then moved: (1, 0, 0) ends up at (3, 0, 0)
+        let model = scene.to_model();
+        assert_eq!(model.vertices[1].pos, [3.0, 0.0, 0.0, 1.0]);
+        assert_eq!(model.vertices[2].pos, [1.0, 2.0, 0.0, 1.0]);
+    }
+
+    #[test]
+    fn pbr_materials_are_read() {
+        let scene = load_triangle("materials");
+        let material = &scene.materials[0];
+
+        assert_eq!(material.name, "painted");
+        assert_eq!(material.base_color_factor, [0.5, 0.25, 1.0, 1.0]);
+        assert_eq!(material.metallic_factor, 0.25);
+        assert_eq!(material.roughness_factor, 0.75);
+        assert_eq!(material.alpha_mode, AlphaMode::Mask);
+        assert_eq!(material.alpha_cutoff, 0.25);
+        assert!(!material.double_sided);
+        assert_eq!(material.normal_texture, None);
+
+        // Texture fields hold the image the texture samples
+        assert_eq!(material.base_color_texture, Some(0));
+        assert_eq!(material.metallic_roughness_texture, Some(1));
+        assert_eq!(scene.textures.len(), 2);
+        assert!(scene.textures[0].srgb);
+        assert!(!scene.textures[1].srgb);
+        assert_eq!(scene.textures[0].pixels.len(), 4);
+
+        assert_eq!(scene.meshes[0].submeshes[0].material_id, Some(0));
+    }
+
+    #[test]
+    fn binary_and_text_files_give_the_same_mesh() {
+        let text = load_triangle("text");
+        let binary = load_gltf(&write_glb("binary")).unwrap();
+        let (text, binary) = (&text.meshes[0], &binary.meshes[0]);
+
+        assert!(text.vertices == binary.vertices);
+        assert_eq!(text.indices, binary.indices);
+        assert_eq!(text.indices, vec![0, 1, 2]);
+        assert_eq!(text.vertices[2].tex_coord, [0.0, 1.0]);
+    }
+
+    #[test]
+    fn primitives_without_texture_coordinates_are_rejected() {
+        let path = write_gltf("untextured", r#"{ "POSITION": 0 }"#);
+
+        assert!(matches!(
+            load_gltf(&path),
+            Err(crate::Error::InvalidModel(_))
+        ));
+    }
+}
@@ -0,0 +1,180 @@
+mod gltf;
+
+pub use self::gltf::{
+    load_gltf, AlphaMode, GltfMesh, GltfScene, PbrMaterial, SceneNode, TextureData,
+};
+
+// Index range of one OBJ object or glTF primitive, drawn with a single material
+#[derive(Clone)]
+pub struct SubMesh {
+    pub name: String,
+    pub first_index: u32,
+    pub index_count: u32,
+    pub material_id: Option<usize>,
+}
+
+pub struct Model {
+    pub vertices: Vec<crate::types::VertexWithTexture3D>,
+    pub indices: Vec<u32>,
+    pub submeshes: Vec<SubMesh>,
+    pub materials: Vec<tobj::Material>,
+}
+
+pub fn load_model(model_path: &std::path::Path) -> crate::Result<Model> {
+    use std::collections::HashMap;
+
+    // Positions, normals and texture coordinates keep their own indices
+    let load_options = tobj::LoadOptions {
+        triangulate: true,
+        ..Default::default()
+    };
+    let (models, materials) = tobj::load_obj(model_path, &load_options)?;
+    let mut vertices = vec![];
+    let mut indices = vec![];
+    let mut submeshes = vec![];
+    // Identical vertices (also across meshes) are stored once
+    let mut unique_vertices = HashMap::new();
+
+    for model in models.iter() {
+        let mesh = &model.mesh;
+
+        if mesh.texcoords.is_empty() {
+            return Err(crate::Error::InvalidModel(
+                "Missing texture coordinate for the model!",
+            ));
+        }
+
+        let first_index = indices.len() as u32;
+
+        for (i, &position_index) in mesh.indices.iter().enumerate() {
+            let position_index = position_index as usize;
+            let texcoord_index = mesh
+                .texcoord_indices
+                .get(i)
+                .map_or(position_index, |&index| index as usize);
+            let normal_index = mesh
+                .normal_indices
+                .get(i)
+                .map_or(position_index, |&index| index as usize);
+
+            let position = mesh
+                .positions
+                .get(position_index * 3..position_index * 3 + 3)
+                .ok_or(crate::Error::InvalidModel("Vertex index out of range!"))?;
+            let texcoord = mesh
+                .texcoords
+                .get(texcoord_index * 2..texcoord_index * 2 + 2)
+                .ok_or(crate::Error::InvalidModel(
+                    "Texture coordinate index out of range!",
+                ))?;
+            // Vertex colors and normals are optional in OBJ files
+            let color = mesh
+                .vertex_color
+                .get(position_index * 3..position_index * 3 + 3)
+                .map_or([1.0, 1.0, 1.0], |color| [color[0], color[1], color[2]]);
+            let normal = mesh
+                .normals
+                .get(normal_index * 3..normal_index * 3 + 3)
+                .map_or([0.0, 0.0, 0.0], |normal| [normal[0], normal[1], normal[2]]);
+
+            let vertex = crate::types::VertexWithTexture3D::new(
+                [position[0], position[1], position[2], 1.0],
+                color,
+                [texcoord[0], texcoord[1]],
+            )
+            .with_normal(normal);
+
+            let index = *unique_vertices.entry(vertex).or_insert_with(|| {
+                vertices.push(vertex);
+
+                (vertices.len() - 1) as u32
+            });
+
+            indices.push(index);
+        }
+
+        submeshes.push(SubMesh {
+            name: model.name.clone(),
+            first_index,
+            index_count: indices.len() as u32 - first_index,
+            material_id: mesh.material_id,
+        });
+    }
+
+    Ok(Model {
+        vertices,
+        indices,
+        submeshes,
+        // Missing or broken MTL files only lose the material info
+        materials: materials.unwrap_or_default(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write_obj(name: &str, source: &str) -> std::path::PathBuf {
+        let path = std::env::temp_dir().join(format!("vk_utils_model_{}.obj", name));
+
+        std::fs::write(&path, source).unwrap();
+
+        path
+    }
+
+    #[test]
+    fn shared_corners_are_stored_once() {
+        // Two triangles of a quad, sharing the diagonal
+        let path = write_obj(
+            "quad",
+            "v 0 0 0\nv 1 0 0\nv 1 1 0\nv 0 1 0\n\
+             vt 0 0\nvt 1 0\nvt 1 1\nvt 0 1\n\
+             f 1/1 2/2 3/3\nf 1/1 3/3 4/4\n",
+        );
+        let model = load_model(&path).unwrap();
+
+        assert_eq!(model.vertices.len(), 4);
+        assert_eq!(model.indices, vec![0, 1, 2, 0, 2, 3]);
+        assert_eq!(model.submeshes.len(), 1);
+        assert_eq!(model.submeshes[0].index_count, 6);
+    }
+
+    #[test]
+    fn corners_with_other_attributes_are_kept_apart() {
+        // Same position, different texture coordinates (a UV seam)
+        let path = write_obj(
+            "seam",
+            "v 0 0 0\nv 1 0 0\nv 1 1 0\n\
+             vt 0 0\nvt 1 0\nvt 1 1\nvt 0.5 0.5\n\
+             f 1/1 2/2 3/3\nf 1/4 2/2 3/3\n",
+        );
+        let model = load_model(&path).unwrap();
+
+        assert_eq!(model.vertices.len(), 4);
+        assert_eq!(model.indices, vec![0, 1, 2, 3, 1, 2]);
+    }
+
+    #[test]
+    fn negative_zero_is_the_same_corner() {
+        let path = write_obj(
+            "negative_zero",
+            "v 0 0 0\nv -0 0 -0\nv 1 0 0\nv 1 1 0\n\
+             vt 0 0\nvt 1 0\nvt 1 1\n\
+             f 1/1 3/2 4/3\nf 2/1 3/2 4/3\n",
+        );
+        let model = load_model(&path).unwrap();
+
+        assert_eq!(model.vertices.len(), 3);
+        assert_eq!(model.indices, vec![0, 1, 2, 0, 1, 2]);
+    }
+
+    #[test]
+    fn models_without_texture_coordinates_are_rejected() {
+        let path = write_obj("untextured", "v 0 0 0\nv 1 0 0\nv 1 1 0\nf 1 2 3\n");
+
+        assert!(matches!(
+            load_model(&path),
+            Err(crate::Error::InvalidModel(_))
+        ));
+    }
+}
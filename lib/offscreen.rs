@@ -0,0 +1,112 @@
+mod _offscreen {
+    use crate as vk_utils;
+    use ash::vk;
+
+    pub fn create_offscreen_target(
+        device: &ash::Device,
+        allocator: &vk_utils::memory::Allocator,
+        extent: vk::Extent2D,
+        format: vk::Format,
+    ) -> vk_utils::Result<vk_utils::VkOffscreenInfo> {
+        // Render target standing in for the swap chain images
+        let image = vk_utils::image::create_image(
+            device,
+            allocator,
+            &vk_utils::image::ImageCreateInfo {
+                extent,
+                mip_levels: 1,
+                samples: vk::SampleCountFlags::TYPE_1,
+                format,
+                tiling: vk::ImageTiling::OPTIMAL,
+                usage: vk::ImageUsageFlags::COLOR_ATTACHMENT | vk::ImageUsageFlags::TRANSFER_SRC,
+                properties: vk::MemoryPropertyFlags::DEVICE_LOCAL,
+            },
+        )?;
+        let image_view = vk_utils::swapchain::create_image_view(
+            device,
+            image.handle(),
+            format,
+            vk::ImageAspectFlags::COLOR,
+            1,
+        )?;
+
+        Ok(vk_utils::VkOffscreenInfo {
+            image,
+            image_view,
+            format,
+            extent,
+        })
+    }
+
+    pub fn read_offscreen_image(
+        device: &ash::Device,
+        command_pool: vk::CommandPool,
+        graphics_queue: vk::Queue,
+        offscreen_info: &vk_utils::VkOffscreenInfo,
+        allocator: &vk_utils::memory::Allocator,
+    ) -> vk_utils::Result<Vec<u8>> {
+        // Pixels are returned as tightly packed RGBA8
+        let vk::Extent2D { width, height } = offscreen_info.extent;
+        let image_size = (width * height * 4) as vk::DeviceSize;
+
+        let staging_buffer = vk_utils::buffer::create_buffer(
+            device,
+            allocator,
+            image_size,
+            vk::BufferUsageFlags::TRANSFER_DST,
+            vk::MemoryPropertyFlags::HOST_VISIBLE | vk::MemoryPropertyFlags::HOST_COHERENT,
+        )?;
+
+        vk_utils::image::copy_image_to_buffer(
+            device,
+            command_pool,
+            offscreen_info.image.handle(),
+            staging_buffer.handle(),
+            graphics_queue,
+            width,
+            height,
+        )?;
+
+        let mut pixels = vec![0_u8; image_size as usize];
+
+        staging_buffer.read(&mut pixels)?;
+
+        if offscreen_info.format == vk::Format::B8G8R8A8_SRGB
+            || offscreen_info.format == vk::Format::B8G8R8A8_UNORM
+        {
+            for pixel in pixels.chunks_exact_mut(4) {
+                pixel.swap(0, 2);
+            }
+        }
+
+        Ok(pixels)
+    }
+
+    pub fn save_offscreen_image(
+        device: &ash::Device,
+        command_pool: vk::CommandPool,
+        graphics_queue: vk::Queue,
+        offscreen_info: &vk_utils::VkOffscreenInfo,
+        allocator: &vk_utils::memory::Allocator,
+        image_path: &std::path::Path,
+    ) -> vk_utils::Result<()> {
+        let pixels = read_offscreen_image(
+            device,
+            command_pool,
+            graphics_queue,
+            offscreen_info,
+            allocator,
+        )?;
+
+        image::save_buffer(
+            image_path,
+            &pixels,
+            offscreen_info.extent.width,
+            offscreen_info.extent.height,
+            image::ColorType::Rgba8,
+        )
+        .map_err(vk_utils::Error::from)
+    }
+}
+
+pub use _offscreen::{create_offscreen_target, read_offscreen_image, save_offscreen_image};
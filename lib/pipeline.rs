@@ -1,7 +1,191 @@
 mod _pipeline {
+    use crate::{
+        self as vk_utils,
+        attributes::{InstanceData, Vertex},
+        reflection::ShaderReflection,
+    };
     use ash::vk;
+    use std::path::{Path, PathBuf};
 
-    pub fn create_descriptor_set_layout(device: &ash::Device) -> vk::DescriptorSetLayout {
+    // Graphics pipeline along with its layout
+    pub struct Pipeline {
+        device: ash::Device,
+        pipeline: vk::Pipeline,
+        layout: vk::PipelineLayout,
+        // Declared by the layout, needed to pick the stages when pushing
+        push_constant_ranges: Vec<vk::PushConstantRange>,
+    }
+
+    impl Pipeline {
+        pub fn new(
+            device: &ash::Device,
+            pipeline: vk::Pipeline,
+            layout: vk::PipelineLayout,
+            push_constant_ranges: &[vk::PushConstantRange],
+        ) -> Self {
+            Self {
+                device: device.clone(),
+                pipeline,
+                layout,
+                push_constant_ranges: push_constant_ranges.to_vec(),
+            }
+        }
+
+        // Writes `data` at `offset` of the push constant block, for every stage
+        // whose range covers it (e.g. a types::DrawPushConstants per draw call)
+        pub fn push_constants<T: Copy>(
+            &self,
+            command_buffer: vk::CommandBuffer,
+            offset: u32,
+            data: &T,
+        ) -> vk_utils::Result<()> {
+            let size = std::mem::size_of::<T>() as u32;
+            let stage_flags = self
+                .push_constant_ranges
+                .iter()
+                .filter(|range| range.offset < offset + size && offset < range.offset + range.size)
+                .fold(vk::ShaderStageFlags::empty(), |stage_flags, range| {
+                    stage_flags | range.stage_flags
+                });
+
+            if stage_flags.is_empty() {
+                return Err(vk_utils::Error::ShaderInterfaceMismatch(format!(
+                    "no push constant range covers bytes {} to {}",
+                    offset,
+                    offset + size
+                )));
+            }
+
+            unsafe {
+                let bytes =
+                    std::slice::from_raw_parts(data as *const T as *const u8, size as usize);
+
+                self.device.cmd_push_constants(
+                    command_buffer,
+                    self.layout,
+                    stage_flags,
+                    offset,
+                    bytes,
+                );
+            }
+
+            Ok(())
+        }
+
+        pub fn handle(&self) -> vk::Pipeline {
+            self.pipeline
+        }
+
+        pub fn layout(&self) -> vk::PipelineLayout {
+            self.layout
+        }
+    }
+
+    impl Drop for Pipeline {
+        fn drop(&mut self) {
+            unsafe {
+                self.device.destroy_pipeline(self.pipeline, None);
+                self.device.destroy_pipeline_layout(self.layout, None);
+            }
+        }
+    }
+
+    // Pipeline cache kept on disk between runs
+    pub struct PipelineCache {
+        device: ash::Device,
+        cache: vk::PipelineCache,
+        path: PathBuf,
+    }
+
+    impl PipelineCache {
+        // Cache files written by another device or driver are ignored
+        pub fn new(
+            instance: &ash::Instance,
+            device: &ash::Device,
+            physical_device: vk::PhysicalDevice,
+            path: &Path,
+        ) -> vk_utils::Result<Self> {
+            let properties = unsafe { instance.get_physical_device_properties(physical_device) };
+            let initial_data = std::fs::read(path)
+                .ok()
+                .filter(|data| is_compatible_cache(data, &properties))
+                .unwrap_or_default();
+
+            let create_info = vk::PipelineCacheCreateInfo::builder().initial_data(&initial_data);
+            let cache = unsafe {
+                device
+                    .create_pipeline_cache(&create_info, None)
+                    .map_err(vk_utils::Error::vulkan("failed to create pipeline cache!"))?
+            };
+
+            Ok(Self {
+                device: device.clone(),
+                cache,
+                path: path.to_path_buf(),
+            })
+        }
+
+        pub fn handle(&self) -> vk::PipelineCache {
+            self.cache
+        }
+
+        pub fn save(&self) -> vk_utils::Result<()> {
+            let data =
+                unsafe {
+                    self.device.get_pipeline_cache_data(self.cache).map_err(
+                        vk_utils::Error::vulkan("failed to get pipeline cache data!"),
+                    )?
+                };
+
+            if let Some(parent) = self.path.parent() {
+                std::fs::create_dir_all(parent)?;
+            }
+
+            // Writing next to it first, so a crash never leaves half a cache behind
+            let temporary_path = self.path.with_extension("tmp");
+
+            std::fs::write(&temporary_path, data)?;
+            std::fs::rename(&temporary_path, &self.path)?;
+
+            Ok(())
+        }
+    }
+
+    impl Drop for PipelineCache {
+        fn drop(&mut self) {
+            unsafe {
+                self.device.destroy_pipeline_cache(self.cache, None);
+            }
+        }
+    }
+
+    // Header: length, version, vendor id, device id and cache UUID
+    fn is_compatible_cache(data: &[u8], properties: &vk::PhysicalDeviceProperties) -> bool {
+        const HEADER_SIZE: usize = 16 + vk::UUID_SIZE;
+
+        if data.len() < HEADER_SIZE {
+            return false;
+        }
+
+        let word = |index: usize| {
+            u32::from_le_bytes([
+                data[index * 4],
+                data[index * 4 + 1],
+                data[index * 4 + 2],
+                data[index * 4 + 3],
+            ])
+        };
+
+        word(0) as usize >= HEADER_SIZE
+            && word(1) == vk::PipelineCacheHeaderVersion::ONE.as_raw() as u32
+            && word(2) == properties.vendor_id
+            && word(3) == properties.device_id
+            && data[16..HEADER_SIZE] == properties.pipeline_cache_uuid
+    }
+
+    pub fn create_descriptor_set_layout(
+        device: &ash::Device,
+    ) -> vk_utils::Result<vk::DescriptorSetLayout> {
         // Descriptor set layout
         let ubo_layout_bindings = [vk::DescriptorSetLayoutBinding::builder()
             .binding(0)
@@ -15,26 +199,501 @@ mod _pipeline {
         let descriptor_set_layout = unsafe {
             device
                 .create_descriptor_set_layout(&layout_info, None)
-                .expect("failed to create descriptor set layout!")
+                .map_err(vk_utils::Error::vulkan(
+                    "failed to create descriptor set layout!",
+                ))?
         };
 
-        descriptor_set_layout
+        Ok(descriptor_set_layout)
     }
 
-    pub fn create_shader_module(device: &ash::Device, code: &Vec<u8>) -> vk::ShaderModule {
-        // FIXED: Major bug when creating shader module!
-        let create_info = vk::ShaderModuleCreateInfo {
-            code_size: code.len(),
-            p_code: code.as_ptr() as *const u32,
-            ..Default::default()
-        };
+    pub fn create_shader_module(
+        device: &ash::Device,
+        code: &[u32],
+    ) -> vk_utils::Result<vk::ShaderModule> {
+        let create_info = vk::ShaderModuleCreateInfo::builder().code(code);
 
         unsafe {
             device
                 .create_shader_module(&create_info, None)
-                .expect("failed to create shader module!")
+                .map_err(vk_utils::Error::vulkan("failed to create shader module!"))
+        }
+    }
+
+    #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+    pub enum BlendMode {
+        Opaque,
+        // Classic transparency (src * alpha + dst * (1 - alpha))
+        Alpha,
+        // Color already multiplied by alpha
+        PremultipliedAlpha,
+        Additive,
+    }
+
+    impl BlendMode {
+        fn attachment_state(self) -> vk::PipelineColorBlendAttachmentState {
+            let (src_color, dst_color) = match self {
+                Self::Opaque => (vk::BlendFactor::ONE, vk::BlendFactor::ZERO),
+                Self::Alpha => (
+                    vk::BlendFactor::SRC_ALPHA,
+                    vk::BlendFactor::ONE_MINUS_SRC_ALPHA,
+                ),
+                Self::PremultipliedAlpha => {
+                    (vk::BlendFactor::ONE, vk::BlendFactor::ONE_MINUS_SRC_ALPHA)
+                }
+                Self::Additive => (vk::BlendFactor::SRC_ALPHA, vk::BlendFactor::ONE),
+            };
+
+            vk::PipelineColorBlendAttachmentState::builder()
+                .color_write_mask(vk::ColorComponentFlags::RGBA)
+                .blend_enable(self != Self::Opaque)
+                .src_color_blend_factor(src_color)
+                .dst_color_blend_factor(dst_color)
+                .color_blend_op(vk::BlendOp::ADD)
+                .src_alpha_blend_factor(vk::BlendFactor::ONE)
+                .dst_alpha_blend_factor(vk::BlendFactor::ONE_MINUS_SRC_ALPHA)
+                .alpha_blend_op(vk::BlendOp::ADD)
+                .build()
+        }
+    }
+
+    // Graphics pipeline description, the defaults match the tutorial's pipeline
+    // (triangle list, back-face culling, LESS depth test, no blending, no MSAA)
+    #[derive(Clone)]
+    pub struct PipelineBuilder {
+        // GLSL sources, the stage comes from the file extension
+        shader_sources: Vec<PathBuf>,
+        topology: vk::PrimitiveTopology,
+        polygon_mode: vk::PolygonMode,
+        line_width: f32,
+        cull_mode: vk::CullModeFlags,
+        front_face: vk::FrontFace,
+        depth_test: bool,
+        depth_write: bool,
+        depth_compare_op: vk::CompareOp,
+        // (front, back), None disables the stencil test
+        stencil: Option<(vk::StencilOpState, vk::StencilOpState)>,
+        blend_mode: BlendMode,
+        msaa_samples: vk::SampleCountFlags,
+        descriptor_set_layouts: Vec<vk::DescriptorSetLayout>,
+        // Reflected from the shaders unless given explicitly
+        push_constant_ranges: Vec<vk::PushConstantRange>,
+        pipeline_cache: vk::PipelineCache,
+        // Only used when built without a render pass (dynamic rendering)
+        color_formats: Vec<vk::Format>,
+        depth_format: vk::Format,
+    }
+
+    impl Default for PipelineBuilder {
+        fn default() -> Self {
+            Self {
+                shader_sources: vec![],
+                topology: vk::PrimitiveTopology::TRIANGLE_LIST,
+                polygon_mode: vk::PolygonMode::FILL,
+                line_width: 1.0,
+                cull_mode: vk::CullModeFlags::BACK,
+                front_face: vk::FrontFace::COUNTER_CLOCKWISE,
+                depth_test: true,
+                depth_write: true,
+                depth_compare_op: vk::CompareOp::LESS,
+                stencil: None,
+                blend_mode: BlendMode::Opaque,
+                msaa_samples: vk::SampleCountFlags::TYPE_1,
+                descriptor_set_layouts: vec![],
+                push_constant_ranges: vec![],
+                pipeline_cache: vk::PipelineCache::null(),
+                color_formats: vec![],
+                depth_format: vk::Format::UNDEFINED,
+            }
+        }
+    }
+
+    impl PipelineBuilder {
+        pub fn new() -> Self {
+            Self::default()
+        }
+
+        pub fn shader<P: AsRef<Path>>(mut self, source_path: P) -> Self {
+            self.shader_sources.push(source_path.as_ref().to_path_buf());
+            self
+        }
+
+        pub fn topology(mut self, topology: vk::PrimitiveTopology) -> Self {
+            self.topology = topology;
+            self
+        }
+
+        // LINE and POINT need the fillModeNonSolid device feature
+        pub fn polygon_mode(mut self, polygon_mode: vk::PolygonMode) -> Self {
+            self.polygon_mode = polygon_mode;
+            self
+        }
+
+        // Anything but 1.0 needs the wideLines device feature
+        pub fn line_width(mut self, line_width: f32) -> Self {
+            self.line_width = line_width;
+            self
+        }
+
+        pub fn cull_mode(mut self, cull_mode: vk::CullModeFlags) -> Self {
+            self.cull_mode = cull_mode;
+            self
+        }
+
+        pub fn front_face(mut self, front_face: vk::FrontFace) -> Self {
+            self.front_face = front_face;
+            self
+        }
+
+        pub fn depth_test(mut self, test: bool, write: bool, compare_op: vk::CompareOp) -> Self {
+            self.depth_test = test;
+            self.depth_write = write;
+            self.depth_compare_op = compare_op;
+            self
+        }
+
+        pub fn stencil_test(mut self, front: vk::StencilOpState, back: vk::StencilOpState) -> Self {
+            self.stencil = Some((front, back));
+            self
+        }
+
+        pub fn blend_mode(mut self, blend_mode: BlendMode) -> Self {
+            self.blend_mode = blend_mode;
+            self
+        }
+
+        pub fn msaa_samples(mut self, msaa_samples: vk::SampleCountFlags) -> Self {
+            self.msaa_samples = msaa_samples;
+            self
+        }
+
+        // Not owned by the pipeline, they have to outlive it
+        pub fn descriptor_set_layouts(mut self, layouts: &[vk::DescriptorSetLayout]) -> Self {
+            self.descriptor_set_layouts = layouts.to_vec();
+            self
+        }
+
+        pub fn push_constant_range(
+            mut self,
+            stage_flags: vk::ShaderStageFlags,
+            offset: u32,
+            size: u32,
+        ) -> Self {
+            self.push_constant_ranges.push(vk::PushConstantRange {
+                stage_flags,
+                offset,
+                size,
+            });
+            self
+        }
+
+        // See PipelineCache, null disables caching
+        pub fn pipeline_cache(mut self, pipeline_cache: vk::PipelineCache) -> Self {
+            self.pipeline_cache = pipeline_cache;
+            self
+        }
+
+        // Formats of the attachments drawn to, needed when no render pass is given
+        pub fn attachment_formats(
+            mut self,
+            color_formats: &[vk::Format],
+            depth_format: vk::Format,
+        ) -> Self {
+            self.color_formats = color_formats.to_vec();
+            self.depth_format = depth_format;
+            self
+        }
+
+        // A null render pass builds the pipeline for dynamic rendering
+        pub fn build<V: Vertex>(
+            &self,
+            device: &ash::Device,
+            render_pass: vk::RenderPass,
+        ) -> vk_utils::Result<Pipeline> {
+            self.build_instanced::<V, ()>(device, render_pass)
+        }
+
+        // Vertices from binding 0 and per-instance data `I` from binding 1
+        pub fn build_instanced<V: Vertex, I: InstanceData>(
+            &self,
+            device: &ash::Device,
+            render_pass: vk::RenderPass,
+        ) -> vk_utils::Result<Pipeline> {
+            let shader_codes = self
+                .shader_sources
+                .iter()
+                .map(|source_path| vk_utils::shader::load_shader(source_path))
+                .collect::<vk_utils::Result<Vec<_>>>()?;
+
+            // Shader interface, checked against the vertex type
+            let reflections = shader_codes
+                .iter()
+                .map(|code| ShaderReflection::new(code))
+                .collect::<vk_utils::Result<Vec<_>>>()?;
+
+            if let Some(vertex_stage) = reflections
+                .iter()
+                .find(|reflection| reflection.stage == vk::ShaderStageFlags::VERTEX)
+            {
+                vertex_stage.validate_vertex_input::<V, I>()?;
+            }
+
+            // Explicit ranges replace the ones the shaders declare
+            let push_constant_ranges = if self.push_constant_ranges.is_empty() {
+                vk_utils::reflection::merge_push_constant_ranges(&reflections)
+            } else {
+                self.push_constant_ranges.clone()
+            };
+            let pipeline_layout = vk_utils::reflection::create_pipeline_layout(
+                device,
+                &self.descriptor_set_layouts,
+                &push_constant_ranges,
+            )?;
+
+            let mut shader_modules = vec![];
+
+            for code in shader_codes.iter() {
+                match create_shader_module(device, code) {
+                    Ok(shader_module) => shader_modules.push(shader_module),
+                    Err(err) => {
+                        unsafe {
+                            for &shader_module in shader_modules.iter() {
+                                device.destroy_shader_module(shader_module, None);
+                            }
+                            device.destroy_pipeline_layout(pipeline_layout, None);
+                        }
+
+                        return Err(err);
+                    }
+                }
+            }
+
+            let result = self.create_pipeline::<V, I>(
+                device,
+                render_pass,
+                pipeline_layout,
+                &reflections,
+                &shader_modules,
+            );
+
+            unsafe {
+                for &shader_module in shader_modules.iter() {
+                    device.destroy_shader_module(shader_module, None);
+                }
+            }
+
+            match result {
+                Ok(pipeline) => Ok(Pipeline::new(
+                    device,
+                    pipeline,
+                    pipeline_layout,
+                    &push_constant_ranges,
+                )),
+                Err(result) => {
+                    unsafe {
+                        device.destroy_pipeline_layout(pipeline_layout, None);
+                    }
+
+                    Err(vk_utils::Error::Vulkan(
+                        "failed to create graphics pipeline!",
+                        result,
+                    ))
+                }
+            }
+        }
+
+        fn create_pipeline<V: Vertex, I: InstanceData>(
+            &self,
+            device: &ash::Device,
+            render_pass: vk::RenderPass,
+            pipeline_layout: vk::PipelineLayout,
+            reflections: &[ShaderReflection],
+            shader_modules: &[vk::ShaderModule],
+        ) -> Result<vk::Pipeline, vk::Result> {
+            let main_function_name = std::ffi::CString::new("main").unwrap();
+
+            let shader_stages = reflections
+                .iter()
+                .zip(shader_modules.iter())
+                .map(|(reflection, &shader_module)| {
+                    vk::PipelineShaderStageCreateInfo::builder()
+                        .stage(reflection.stage)
+                        .module(shader_module)
+                        .name(&main_function_name)
+                        .build()
+                })
+                .collect::<Vec<_>>();
+
+            // Viewport and scissor are set while recording
+            let dynamic_states = [vk::DynamicState::VIEWPORT, vk::DynamicState::SCISSOR];
+            let dynamic_state =
+                vk::PipelineDynamicStateCreateInfo::builder().dynamic_states(&dynamic_states);
+
+            // Vertex input
+            let mut binding_descriptions = V::get_binding_description();
+            binding_descriptions.extend(I::get_binding_description());
+            let attribute_descriptions =
+                vk_utils::attributes::input_attribute_descriptions::<V, I>();
+            let vertex_input_info = vk::PipelineVertexInputStateCreateInfo::builder()
+                .vertex_binding_descriptions(&binding_descriptions)
+                .vertex_attribute_descriptions(&attribute_descriptions);
+
+            // Input assembly
+            let input_assembly = vk::PipelineInputAssemblyStateCreateInfo::builder()
+                .topology(self.topology)
+                .primitive_restart_enable(false);
+
+            let viewport_state = vk::PipelineViewportStateCreateInfo::builder()
+                .viewport_count(1)
+                .scissor_count(1);
+
+            // Rasterizer
+            let rasterizer = vk::PipelineRasterizationStateCreateInfo::builder()
+                .depth_clamp_enable(false)
+                .rasterizer_discard_enable(false)
+                .polygon_mode(self.polygon_mode)
+                .line_width(self.line_width)
+                .cull_mode(self.cull_mode)
+                .front_face(self.front_face)
+                .depth_bias_enable(false);
+
+            // Multisampling
+            let multisampling = vk::PipelineMultisampleStateCreateInfo::builder()
+                .sample_shading_enable(false)
+                .rasterization_samples(self.msaa_samples)
+                .min_sample_shading(0.2)
+                .alpha_to_coverage_enable(false)
+                .alpha_to_one_enable(false);
+
+            // Depth and stencil state
+            let (stencil_front, stencil_back) = self.stencil.unwrap_or_default();
+            let depth_stencil = vk::PipelineDepthStencilStateCreateInfo::builder()
+                .depth_test_enable(self.depth_test)
+                .depth_write_enable(self.depth_write)
+                .depth_compare_op(self.depth_compare_op)
+                .depth_bounds_test_enable(false)
+                .min_depth_bounds(0.0)
+                .max_depth_bounds(1.0)
+                .stencil_test_enable(self.stencil.is_some())
+                .front(stencil_front)
+                .back(stencil_back);
+
+            // Color blending
+            let color_blend_attachments = [self.blend_mode.attachment_state()];
+            let color_blending = vk::PipelineColorBlendStateCreateInfo::builder()
+                .logic_op_enable(false)
+                .logic_op(vk::LogicOp::COPY)
+                .attachments(&color_blend_attachments)
+                .blend_constants([0.0, 0.0, 0.0, 0.0]);
+
+            let stencil_format = if vk_utils::swapchain::has_stencil_component(self.depth_format) {
+                self.depth_format
+            } else {
+                vk::Format::UNDEFINED
+            };
+            let mut rendering_info = vk::PipelineRenderingCreateInfo::builder()
+                .color_attachment_formats(&self.color_formats)
+                .depth_attachment_format(self.depth_format)
+                .stencil_attachment_format(stencil_format);
+
+            let mut pipeline_info = vk::GraphicsPipelineCreateInfo::builder()
+                .stages(&shader_stages)
+                .vertex_input_state(&vertex_input_info)
+                .input_assembly_state(&input_assembly)
+                .viewport_state(&viewport_state)
+                .rasterization_state(&rasterizer)
+                .multisample_state(&multisampling)
+                .depth_stencil_state(&depth_stencil)
+                .color_blend_state(&color_blending)
+                .dynamic_state(&dynamic_state)
+                .layout(pipeline_layout)
+                .render_pass(render_pass)
+                .subpass(0);
+            if render_pass == vk::RenderPass::null() {
+                pipeline_info = pipeline_info.push_next(&mut rendering_info);
+            }
+            let pipeline_infos = [pipeline_info.build()];
+
+            unsafe {
+                device
+                    .create_graphics_pipelines(self.pipeline_cache, &pipeline_infos, None)
+                    .map(|pipelines| pipelines[0])
+                    .map_err(|(_, result)| result)
+            }
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        fn properties() -> vk::PhysicalDeviceProperties {
+            vk::PhysicalDeviceProperties {
+                vendor_id: 0x10de,
+                device_id: 0x2484,
+                pipeline_cache_uuid: [7; vk::UUID_SIZE],
+                ..Default::default()
+            }
+        }
+
+        // Header as the driver writes it, followed by some cache data
+        fn cache_data(vendor_id: u32, device_id: u32, uuid: [u8; vk::UUID_SIZE]) -> Vec<u8> {
+            let header_size = 16 + vk::UUID_SIZE as u32;
+            let version = vk::PipelineCacheHeaderVersion::ONE.as_raw() as u32;
+
+            [header_size, version, vendor_id, device_id]
+                .iter()
+                .flat_map(|word| word.to_le_bytes())
+                .chain(uuid)
+                .chain([0xab; 64])
+                .collect()
+        }
+
+        #[test]
+        fn caches_of_the_same_device_are_compatible() {
+            let data = cache_data(0x10de, 0x2484, [7; vk::UUID_SIZE]);
+
+            assert!(is_compatible_cache(&data, &properties()));
+        }
+
+        #[test]
+        fn caches_of_other_devices_are_ignored() {
+            let wrong_vendor = cache_data(0x1002, 0x2484, [7; vk::UUID_SIZE]);
+            let wrong_device = cache_data(0x10de, 0x2204, [7; vk::UUID_SIZE]);
+            let mut uuid = [7; vk::UUID_SIZE];
+            uuid[vk::UUID_SIZE - 1] = 8;
+            let wrong_uuid = cache_data(0x10de, 0x2484, uuid);
+
+            assert!(!is_compatible_cache(&wrong_vendor, &properties()));
+            assert!(!is_compatible_cache(&wrong_device, &properties()));
+            assert!(!is_compatible_cache(&wrong_uuid, &properties()));
+        }
+
+        #[test]
+        fn malformed_headers_are_ignored() {
+            let data = cache_data(0x10de, 0x2484, [7; vk::UUID_SIZE]);
+
+            // Truncated before the end of the UUID
+            assert!(!is_compatible_cache(
+                &data[..16 + vk::UUID_SIZE - 1],
+                &properties()
+            ));
+            assert!(!is_compatible_cache(&data[..8], &properties()));
+            assert!(!is_compatible_cache(&[], &properties()));
+
+            // Header length too short, unknown header version
+            let mut short_header = data.clone();
+            short_header[0] = 16;
+            let mut unknown_version = data;
+            unknown_version[4] = 2;
+
+            assert!(!is_compatible_cache(&short_header, &properties()));
+            assert!(!is_compatible_cache(&unknown_version, &properties()));
         }
     }
 }
 
-pub use _pipeline::{create_descriptor_set_layout, create_shader_module};
+pub use _pipeline::{
+    create_descriptor_set_layout, create_shader_module, BlendMode, Pipeline, PipelineBuilder,
+    PipelineCache,
+};
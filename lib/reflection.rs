@@ -0,0 +1,898 @@
+mod _reflection {
+    use crate::{
+        self as vk_utils,
+        attributes::{InstanceData, Vertex},
+    };
+    use ash::vk;
+    use std::collections::HashMap;
+
+    const SPIRV_MAGIC: u32 = 0x0723_0203;
+
+    // Opcodes
+    const OP_NAME: u32 = 5;
+    const OP_ENTRY_POINT: u32 = 15;
+    const OP_TYPE_INT: u32 = 21;
+    const OP_TYPE_FLOAT: u32 = 22;
+    const OP_TYPE_VECTOR: u32 = 23;
+    const OP_TYPE_MATRIX: u32 = 24;
+    const OP_TYPE_IMAGE: u32 = 25;
+    const OP_TYPE_SAMPLER: u32 = 26;
+    const OP_TYPE_SAMPLED_IMAGE: u32 = 27;
+    const OP_TYPE_ARRAY: u32 = 28;
+    const OP_TYPE_RUNTIME_ARRAY: u32 = 29;
+    const OP_TYPE_STRUCT: u32 = 30;
+    const OP_TYPE_POINTER: u32 = 32;
+    const OP_CONSTANT: u32 = 43;
+    const OP_VARIABLE: u32 = 59;
+    const OP_DECORATE: u32 = 71;
+    const OP_MEMBER_DECORATE: u32 = 72;
+
+    // Decorations
+    const DECORATION_BUFFER_BLOCK: u32 = 3;
+    const DECORATION_ARRAY_STRIDE: u32 = 6;
+    const DECORATION_MATRIX_STRIDE: u32 = 7;
+    const DECORATION_BUILT_IN: u32 = 11;
+    const DECORATION_LOCATION: u32 = 30;
+    const DECORATION_BINDING: u32 = 33;
+    const DECORATION_DESCRIPTOR_SET: u32 = 34;
+    const DECORATION_OFFSET: u32 = 35;
+
+    // Storage classes
+    const STORAGE_UNIFORM_CONSTANT: u32 = 0;
+    const STORAGE_INPUT: u32 = 1;
+    const STORAGE_UNIFORM: u32 = 2;
+    const STORAGE_PUSH_CONSTANT: u32 = 9;
+    const STORAGE_STORAGE_BUFFER: u32 = 12;
+
+    // Image dimensions
+    const DIM_BUFFER: u32 = 5;
+    const DIM_SUBPASS_DATA: u32 = 6;
+
+    #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+    enum ScalarKind {
+        Float,
+        Int,
+        Uint,
+    }
+
+    enum Type {
+        Scalar(ScalarKind, u32), // (kind, width in bits)
+        Vector(u32, u32),        // (component type, count)
+        Matrix(u32, u32),        // (column type, count)
+        Image { dim: u32, sampled: u32 },
+        Sampler,
+        SampledImage(u32),
+        Array(u32, u32), // (element type, length constant)
+        RuntimeArray,
+        Struct(Vec<u32>),
+        Pointer(u32), // pointee type, the storage class is on the variable
+    }
+
+    pub struct DescriptorBinding {
+        pub name: String,
+        pub set: u32,
+        pub binding: u32,
+        pub descriptor_type: vk::DescriptorType,
+        pub count: u32,
+        pub stage_flags: vk::ShaderStageFlags,
+    }
+
+    pub struct VertexInput {
+        pub name: String,
+        pub location: u32,
+        pub format: vk::Format,
+    }
+
+    // Interface of a single shader stage, read from its SPIR-V
+    pub struct ShaderReflection {
+        pub stage: vk::ShaderStageFlags,
+        pub descriptor_bindings: Vec<DescriptorBinding>,
+        pub push_constant_ranges: Vec<vk::PushConstantRange>,
+        // Only filled for vertex shaders, sorted by location
+        pub vertex_inputs: Vec<VertexInput>,
+    }
+
+    // Everything the module declares, indexed by result id
+    #[derive(Default)]
+    struct Module {
+        stage: Option<vk::ShaderStageFlags>,
+        names: HashMap<u32, String>,
+        types: HashMap<u32, Type>,
+        constants: HashMap<u32, u32>,
+        decorations: HashMap<(u32, u32), u32>,
+        member_decorations: HashMap<(u32, u32, u32), u32>,
+        // (id, pointer type, storage class)
+        variables: Vec<(u32, u32, u32)>,
+    }
+
+    impl Module {
+        fn parse(code: &[u32]) -> vk_utils::Result<Self> {
+            if code.len() < 5 || code[0] != SPIRV_MAGIC {
+                return Err(vk_utils::Error::InvalidSpirv("missing SPIR-V header!"));
+            }
+
+            let mut module = Self::default();
+            let mut words = &code[5..];
+
+            while !words.is_empty() {
+                let word_count = (words[0] >> 16) as usize;
+                let opcode = words[0] & 0xffff;
+
+                if word_count == 0 || word_count > words.len() {
+                    return Err(vk_utils::Error::InvalidSpirv("truncated instruction!"));
+                }
+
+                module.instruction(opcode, &words[1..word_count])?;
+                words = &words[word_count..];
+            }
+
+            Ok(module)
+        }
+
+        fn instruction(&mut self, opcode: u32, operands: &[u32]) -> vk_utils::Result<()> {
+            let operand = |index: usize| {
+                operands
+                    .get(index)
+                    .copied()
+                    .ok_or(vk_utils::Error::InvalidSpirv(
+                        "missing instruction operand!",
+                    ))
+            };
+
+            match opcode {
+                OP_NAME => {
+                    self.names
+                        .insert(operand(0)?, literal_string(&operands[1..]));
+                }
+                // Only the first entry point is reflected
+                OP_ENTRY_POINT if self.stage.is_none() => {
+                    self.stage = Some(match operand(0)? {
+                        0 => vk::ShaderStageFlags::VERTEX,
+                        1 => vk::ShaderStageFlags::TESSELLATION_CONTROL,
+                        2 => vk::ShaderStageFlags::TESSELLATION_EVALUATION,
+                        3 => vk::ShaderStageFlags::GEOMETRY,
+                        4 => vk::ShaderStageFlags::FRAGMENT,
+                        5 => vk::ShaderStageFlags::COMPUTE,
+                        _ => return Err(vk_utils::Error::InvalidSpirv("unknown shader stage!")),
+                    });
+                }
+                OP_TYPE_INT => {
+                    let kind = if operand(2)? == 1 {
+                        ScalarKind::Int
+                    } else {
+                        ScalarKind::Uint
+                    };
+
+                    self.types
+                        .insert(operand(0)?, Type::Scalar(kind, operand(1)?));
+                }
+                OP_TYPE_FLOAT => {
+                    self.types
+                        .insert(operand(0)?, Type::Scalar(ScalarKind::Float, operand(1)?));
+                }
+                OP_TYPE_VECTOR => {
+                    self.types
+                        .insert(operand(0)?, Type::Vector(operand(1)?, operand(2)?));
+                }
+                OP_TYPE_MATRIX => {
+                    self.types
+                        .insert(operand(0)?, Type::Matrix(operand(1)?, operand(2)?));
+                }
+                OP_TYPE_IMAGE => {
+                    self.types.insert(
+                        operand(0)?,
+                        Type::Image {
+                            dim: operand(2)?,
+                            sampled: operand(6)?,
+                        },
+                    );
+                }
+                OP_TYPE_SAMPLER => {
+                    self.types.insert(operand(0)?, Type::Sampler);
+                }
+                OP_TYPE_SAMPLED_IMAGE => {
+                    self.types
+                        .insert(operand(0)?, Type::SampledImage(operand(1)?));
+                }
+                OP_TYPE_ARRAY => {
+                    self.types
+                        .insert(operand(0)?, Type::Array(operand(1)?, operand(2)?));
+                }
+                OP_TYPE_RUNTIME_ARRAY => {
+                    self.types.insert(operand(0)?, Type::RuntimeArray);
+                }
+                OP_TYPE_STRUCT => {
+                    self.types
+                        .insert(operand(0)?, Type::Struct(operands[1..].to_vec()));
+                }
+                OP_TYPE_POINTER => {
+                    self.types.insert(operand(0)?, Type::Pointer(operand(2)?));
+                }
+                // Only the low word matters for array lengths
+                OP_CONSTANT => {
+                    self.constants.insert(operand(1)?, operand(2)?);
+                }
+                OP_VARIABLE => {
+                    self.variables.push((operand(1)?, operand(0)?, operand(2)?));
+                }
+                OP_DECORATE => {
+                    let value = operands.get(2).copied().unwrap_or(0);
+
+                    self.decorations.insert((operand(0)?, operand(1)?), value);
+                }
+                OP_MEMBER_DECORATE => {
+                    let value = operands.get(3).copied().unwrap_or(0);
+
+                    self.member_decorations
+                        .insert((operand(0)?, operand(1)?, operand(2)?), value);
+                }
+                _ => {}
+            }
+
+            Ok(())
+        }
+
+        fn get_type(&self, id: u32) -> vk_utils::Result<&Type> {
+            self.types.get(&id).ok_or(vk_utils::Error::InvalidSpirv(
+                "reference to an unknown type!",
+            ))
+        }
+
+        fn decoration(&self, id: u32, decoration: u32) -> Option<u32> {
+            self.decorations.get(&(id, decoration)).copied()
+        }
+
+        fn name(&self, id: u32) -> String {
+            self.names.get(&id).cloned().unwrap_or_default()
+        }
+
+        fn array_length(&self, length_id: u32) -> vk_utils::Result<u32> {
+            self.constants
+                .get(&length_id)
+                .copied()
+                .ok_or(vk_utils::Error::InvalidSpirv(
+                    "array length is not a constant!",
+                ))
+        }
+
+        // (descriptor type, array size) of a resource variable
+        fn descriptor_type(
+            &self,
+            type_id: u32,
+            storage_class: u32,
+        ) -> vk_utils::Result<(vk::DescriptorType, u32)> {
+            let (type_id, count) = match self.get_type(type_id)? {
+                Type::Array(element, length) => (*element, self.array_length(*length)?),
+                Type::RuntimeArray => {
+                    return Err(vk_utils::Error::InvalidSpirv(
+                        "unsized descriptor arrays are not supported!",
+                    ))
+                }
+                _ => (type_id, 1),
+            };
+
+            let descriptor_type = match (storage_class, self.get_type(type_id)?) {
+                (STORAGE_UNIFORM, Type::Struct(_))
+                    if self.decoration(type_id, DECORATION_BUFFER_BLOCK).is_some() =>
+                {
+                    vk::DescriptorType::STORAGE_BUFFER
+                }
+                (STORAGE_UNIFORM, Type::Struct(_)) => vk::DescriptorType::UNIFORM_BUFFER,
+                (STORAGE_STORAGE_BUFFER, _) => vk::DescriptorType::STORAGE_BUFFER,
+                (_, Type::Sampler) => vk::DescriptorType::SAMPLER,
+                (_, Type::SampledImage(image)) => match self.get_type(*image)? {
+                    Type::Image {
+                        dim: DIM_BUFFER, ..
+                    } => vk::DescriptorType::UNIFORM_TEXEL_BUFFER,
+                    _ => vk::DescriptorType::COMBINED_IMAGE_SAMPLER,
+                },
+                (_, Type::Image { dim, sampled }) => match (*dim, *sampled) {
+                    (DIM_SUBPASS_DATA, _) => vk::DescriptorType::INPUT_ATTACHMENT,
+                    (DIM_BUFFER, 2) => vk::DescriptorType::STORAGE_TEXEL_BUFFER,
+                    (DIM_BUFFER, _) => vk::DescriptorType::UNIFORM_TEXEL_BUFFER,
+                    (_, 2) => vk::DescriptorType::STORAGE_IMAGE,
+                    _ => vk::DescriptorType::SAMPLED_IMAGE,
+                },
+                _ => {
+                    return Err(vk_utils::Error::InvalidSpirv(
+                        "unsupported descriptor type!",
+                    ))
+                }
+            };
+
+            Ok((descriptor_type, count))
+        }
+
+        // Bytes covered by a type in a buffer block (std140/std430 offsets are explicit)
+        fn type_size(&self, type_id: u32, matrix_stride: Option<u32>) -> vk_utils::Result<u32> {
+            Ok(match self.get_type(type_id)? {
+                Type::Scalar(_, width) => width / 8,
+                Type::Vector(component, count) => self.type_size(*component, None)? * count,
+                Type::Matrix(column, count) => match matrix_stride {
+                    Some(stride) => stride * count,
+                    None => self.type_size(*column, None)? * count,
+                },
+                Type::Array(element, length) => {
+                    let length = self.array_length(*length)?;
+
+                    match self.decoration(type_id, DECORATION_ARRAY_STRIDE) {
+                        Some(stride) => stride * length,
+                        None => self.type_size(*element, matrix_stride)? * length,
+                    }
+                }
+                Type::Struct(members) => {
+                    let mut size = 0;
+
+                    for (index, &member) in members.iter().enumerate() {
+                        let member_decoration = |decoration| {
+                            self.member_decorations
+                                .get(&(type_id, index as u32, decoration))
+                                .copied()
+                        };
+                        let offset = member_decoration(DECORATION_OFFSET).unwrap_or(size);
+                        let member_size =
+                            self.type_size(member, member_decoration(DECORATION_MATRIX_STRIDE))?;
+
+                        size = std::cmp::max(size, offset + member_size);
+                    }
+
+                    size
+                }
+                _ => {
+                    return Err(vk_utils::Error::InvalidSpirv(
+                        "type has no size in a buffer!",
+                    ))
+                }
+            })
+        }
+
+        // First member offset and end of a push constant block
+        fn push_constant_range(&self, type_id: u32) -> vk_utils::Result<(u32, u32)> {
+            let offset = match self.get_type(type_id)? {
+                Type::Struct(members) => (0..members.len() as u32)
+                    .filter_map(|index| {
+                        self.member_decorations
+                            .get(&(type_id, index, DECORATION_OFFSET))
+                            .copied()
+                    })
+                    .min()
+                    .unwrap_or(0),
+                _ => 0,
+            };
+
+            Ok((offset, self.type_size(type_id, None)?))
+        }
+
+        fn vertex_format(&self, type_id: u32) -> vk_utils::Result<vk::Format> {
+            let (component, count) = match self.get_type(type_id)? {
+                Type::Vector(component, count) => (*component, *count),
+                Type::Scalar(..) => (type_id, 1),
+                _ => {
+                    return Err(vk_utils::Error::InvalidSpirv(
+                        "only scalar and vector vertex inputs are supported!",
+                    ))
+                }
+            };
+
+            let format = match (self.get_type(component)?, count) {
+                (Type::Scalar(ScalarKind::Float, 32), 1) => vk::Format::R32_SFLOAT,
+                (Type::Scalar(ScalarKind::Float, 32), 2) => vk::Format::R32G32_SFLOAT,
+                (Type::Scalar(ScalarKind::Float, 32), 3) => vk::Format::R32G32B32_SFLOAT,
+                (Type::Scalar(ScalarKind::Float, 32), 4) => vk::Format::R32G32B32A32_SFLOAT,
+                (Type::Scalar(ScalarKind::Int, 32), 1) => vk::Format::R32_SINT,
+                (Type::Scalar(ScalarKind::Int, 32), 2) => vk::Format::R32G32_SINT,
+                (Type::Scalar(ScalarKind::Int, 32), 3) => vk::Format::R32G32B32_SINT,
+                (Type::Scalar(ScalarKind::Int, 32), 4) => vk::Format::R32G32B32A32_SINT,
+                (Type::Scalar(ScalarKind::Uint, 32), 1) => vk::Format::R32_UINT,
+                (Type::Scalar(ScalarKind::Uint, 32), 2) => vk::Format::R32G32_UINT,
+                (Type::Scalar(ScalarKind::Uint, 32), 3) => vk::Format::R32G32B32_UINT,
+                (Type::Scalar(ScalarKind::Uint, 32), 4) => vk::Format::R32G32B32A32_UINT,
+                _ => {
+                    return Err(vk_utils::Error::InvalidSpirv(
+                        "unsupported vertex input type!",
+                    ))
+                }
+            };
+
+            Ok(format)
+        }
+    }
+
+    impl ShaderReflection {
+        pub fn new(code: &[u32]) -> vk_utils::Result<Self> {
+            let module = Module::parse(code)?;
+            let stage = module
+                .stage
+                .ok_or(vk_utils::Error::InvalidSpirv("missing entry point!"))?;
+
+            let mut reflection = Self {
+                stage,
+                descriptor_bindings: vec![],
+                push_constant_ranges: vec![],
+                vertex_inputs: vec![],
+            };
+
+            for &(id, pointer_type, storage_class) in module.variables.iter() {
+                let type_id = match module.get_type(pointer_type)? {
+                    Type::Pointer(type_id) => *type_id,
+                    _ => return Err(vk_utils::Error::InvalidSpirv("variable is not a pointer!")),
+                };
+
+                match storage_class {
+                    STORAGE_UNIFORM_CONSTANT | STORAGE_UNIFORM | STORAGE_STORAGE_BUFFER => {
+                        let (descriptor_type, count) =
+                            module.descriptor_type(type_id, storage_class)?;
+
+                        reflection.descriptor_bindings.push(DescriptorBinding {
+                            name: module.name(id),
+                            set: module
+                                .decoration(id, DECORATION_DESCRIPTOR_SET)
+                                .unwrap_or(0),
+                            binding: module.decoration(id, DECORATION_BINDING).unwrap_or(0),
+                            descriptor_type,
+                            count,
+                            stage_flags: stage,
+                        });
+                    }
+                    STORAGE_PUSH_CONSTANT => {
+                        let (offset, end) = module.push_constant_range(type_id)?;
+
+                        reflection.push_constant_ranges.push(vk::PushConstantRange {
+                            stage_flags: stage,
+                            offset,
+                            size: end - offset,
+                        });
+                    }
+                    // Built-ins such as gl_VertexIndex aren't fed by vertex buffers
+                    STORAGE_INPUT
+                        if stage == vk::ShaderStageFlags::VERTEX
+                            && module.decoration(id, DECORATION_BUILT_IN).is_none() =>
+                    {
+                        let location = module.decoration(id, DECORATION_LOCATION).ok_or(
+                            vk_utils::Error::InvalidSpirv("vertex input has no location!"),
+                        )?;
+
+                        reflection.vertex_inputs.push(VertexInput {
+                            name: module.name(id),
+                            location,
+                            format: module.vertex_format(type_id)?,
+                        });
+                    }
+                    _ => {}
+                }
+            }
+
+            reflection
+                .descriptor_bindings
+                .sort_by_key(|binding| (binding.set, binding.binding));
+            reflection
+                .vertex_inputs
+                .sort_by_key(|vertex_input| vertex_input.location);
+
+            Ok(reflection)
+        }
+
+        // SPIR-V can't tell dynamic buffers apart, so the application marks the bindings
+        // it binds with dynamic offsets (see RingBuffer)
+        pub fn use_dynamic_offsets(&mut self, set: u32, binding: u32) {
+            for descriptor_binding in
+                self.descriptor_bindings
+                    .iter_mut()
+                    .filter(|descriptor_binding| {
+                        (descriptor_binding.set, descriptor_binding.binding) == (set, binding)
+                    })
+            {
+                descriptor_binding.descriptor_type = match descriptor_binding.descriptor_type {
+                    vk::DescriptorType::UNIFORM_BUFFER => {
+                        vk::DescriptorType::UNIFORM_BUFFER_DYNAMIC
+                    }
+                    vk::DescriptorType::STORAGE_BUFFER => {
+                        vk::DescriptorType::STORAGE_BUFFER_DYNAMIC
+                    }
+                    descriptor_type => descriptor_type,
+                };
+            }
+        }
+
+        // Every shader input needs an attribute of the same numeric type,
+        // missing trailing components are filled in by the device
+        pub fn validate_vertex_input<V: Vertex, I: InstanceData>(&self) -> vk_utils::Result<()> {
+            let attributes = vk_utils::attributes::input_attribute_descriptions::<V, I>();
+            let provider = match I::get_binding_description().is_empty() {
+                true => std::any::type_name::<V>().to_string(),
+                false => format!(
+                    "{} + {}",
+                    std::any::type_name::<V>(),
+                    std::any::type_name::<I>()
+                ),
+            };
+
+            for input in self.vertex_inputs.iter() {
+                let attribute = attributes
+                    .iter()
+                    .find(|attribute| attribute.location == input.location)
+                    .ok_or_else(|| {
+                        vk_utils::Error::ShaderInterfaceMismatch(format!(
+                            "vertex input '{}' (location {}, {:?}) has no matching attribute in {}",
+                            input.name, input.location, input.format, provider
+                        ))
+                    })?;
+
+                let compatible = match (
+                    format_components(attribute.format),
+                    format_components(input.format),
+                ) {
+                    (Some((attribute_kind, _)), Some((input_kind, _))) => {
+                        attribute_kind == input_kind
+                    }
+                    _ => attribute.format == input.format,
+                };
+
+                if !compatible {
+                    return Err(vk_utils::Error::ShaderInterfaceMismatch(format!(
+                        "vertex input '{}' (location {}) expects {:?}, but {} provides {:?}",
+                        input.name, input.location, input.format, provider, attribute.format
+                    )));
+                }
+            }
+
+            Ok(())
+        }
+    }
+
+    fn literal_string(words: &[u32]) -> String {
+        let bytes = words
+            .iter()
+            .flat_map(|word| word.to_le_bytes())
+            .take_while(|&byte| byte != 0)
+            .collect::<Vec<_>>();
+
+        String::from_utf8_lossy(&bytes).into_owned()
+    }
+
+    // (numeric type, component count) of the vertex formats shaders can declare
+    fn format_components(format: vk::Format) -> Option<(ScalarKind, u32)> {
+        match format {
+            vk::Format::R32_SFLOAT => Some((ScalarKind::Float, 1)),
+            vk::Format::R32G32_SFLOAT => Some((ScalarKind::Float, 2)),
+            vk::Format::R32G32B32_SFLOAT => Some((ScalarKind::Float, 3)),
+            vk::Format::R32G32B32A32_SFLOAT => Some((ScalarKind::Float, 4)),
+            vk::Format::R32_SINT => Some((ScalarKind::Int, 1)),
+            vk::Format::R32G32_SINT => Some((ScalarKind::Int, 2)),
+            vk::Format::R32G32B32_SINT => Some((ScalarKind::Int, 3)),
+            vk::Format::R32G32B32A32_SINT => Some((ScalarKind::Int, 4)),
+            vk::Format::R32_UINT => Some((ScalarKind::Uint, 1)),
+            vk::Format::R32G32_UINT => Some((ScalarKind::Uint, 2)),
+            vk::Format::R32G32B32_UINT => Some((ScalarKind::Uint, 3)),
+            vk::Format::R32G32B32A32_UINT => Some((ScalarKind::Uint, 4)),
+            _ => None,
+        }
+    }
+
+    // Combines the bindings of all stages, sorted by (set, binding)
+    pub fn merge_descriptor_bindings(
+        reflections: &[ShaderReflection],
+    ) -> vk_utils::Result<Vec<DescriptorBinding>> {
+        let mut merged: Vec<DescriptorBinding> = vec![];
+
+        for binding in reflections
+            .iter()
+            .flat_map(|reflection| reflection.descriptor_bindings.iter())
+        {
+            match merged
+                .iter_mut()
+                .find(|merged| (merged.set, merged.binding) == (binding.set, binding.binding))
+            {
+                Some(merged) => {
+                    if (merged.descriptor_type, merged.count)
+                        != (binding.descriptor_type, binding.count)
+                    {
+                        return Err(vk_utils::Error::ShaderInterfaceMismatch(format!(
+                            "set {} binding {} is {:?}[{}] in one stage and {:?}[{}] in another",
+                            binding.set,
+                            binding.binding,
+                            merged.descriptor_type,
+                            merged.count,
+                            binding.descriptor_type,
+                            binding.count
+                        )));
+                    }
+
+                    merged.stage_flags |= binding.stage_flags;
+                }
+                None => merged.push(DescriptorBinding {
+                    name: binding.name.clone(),
+                    ..*binding
+                }),
+            }
+        }
+
+        merged.sort_by_key(|binding| (binding.set, binding.binding));
+
+        Ok(merged)
+    }
+
+    // One layout per set index, unused sets in between get empty layouts
+    pub fn create_descriptor_set_layouts(
+        device: &ash::Device,
+        reflections: &[ShaderReflection],
+    ) -> vk_utils::Result<Vec<vk::DescriptorSetLayout>> {
+        let bindings = merge_descriptor_bindings(reflections)?;
+        let set_count = bindings
+            .iter()
+            .map(|binding| binding.set + 1)
+            .max()
+            .unwrap_or(0);
+        let mut set_layouts = vec![];
+
+        for set in 0..set_count {
+            let layout_bindings = bindings
+                .iter()
+                .filter(|binding| binding.set == set)
+                .map(|binding| {
+                    vk::DescriptorSetLayoutBinding::builder()
+                        .binding(binding.binding)
+                        .descriptor_type(binding.descriptor_type)
+                        .descriptor_count(binding.count)
+                        .stage_flags(binding.stage_flags)
+                        .build()
+                })
+                .collect::<Vec<_>>();
+            let layout_info =
+                vk::DescriptorSetLayoutCreateInfo::builder().bindings(&layout_bindings);
+
+            let result = unsafe { device.create_descriptor_set_layout(&layout_info, None) };
+
+            match result {
+                Ok(set_layout) => set_layouts.push(set_layout),
+                Err(vk_result) => {
+                    for &set_layout in set_layouts.iter() {
+                        unsafe {
+                            device.destroy_descriptor_set_layout(set_layout, None);
+                        }
+                    }
+
+                    return Err(vk_utils::Error::Vulkan(
+                        "failed to create descriptor set layout!",
+                        vk_result,
+                    ));
+                }
+            }
+        }
+
+        Ok(set_layouts)
+    }
+
+    // Push constant ranges of identical extent are shared between stages
+    pub fn merge_push_constant_ranges(
+        reflections: &[ShaderReflection],
+    ) -> Vec<vk::PushConstantRange> {
+        let mut push_constant_ranges: Vec<vk::PushConstantRange> = vec![];
+
+        for range in reflections
+            .iter()
+            .flat_map(|reflection| reflection.push_constant_ranges.iter())
+        {
+            match push_constant_ranges
+                .iter_mut()
+                .find(|merged| (merged.offset, merged.size) == (range.offset, range.size))
+            {
+                Some(merged) => merged.stage_flags |= range.stage_flags,
+                None => push_constant_ranges.push(*range),
+            }
+        }
+
+        push_constant_ranges
+    }
+
+    pub fn create_pipeline_layout(
+        device: &ash::Device,
+        set_layouts: &[vk::DescriptorSetLayout],
+        push_constant_ranges: &[vk::PushConstantRange],
+    ) -> vk_utils::Result<vk::PipelineLayout> {
+        let pipeline_layout_info = vk::PipelineLayoutCreateInfo::builder()
+            .set_layouts(set_layouts)
+            .push_constant_ranges(push_constant_ranges);
+
+        unsafe {
+            device
+                .create_pipeline_layout(&pipeline_layout_info, None)
+                .map_err(vk_utils::Error::vulkan("failed to create pipeline layout!"))
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use crate::{
+            constants::shader::*,
+            types::{InstanceTransform, Vertex2D, VertexWithTexture3D},
+        };
+
+        // Straight from the sources, without touching the SPIR-V cache
+        fn reflect(path: &str) -> ShaderReflection {
+            let stage = match path.rsplit('.').next() {
+                Some("vert") => naga::ShaderStage::Vertex,
+                Some("frag") => naga::ShaderStage::Fragment,
+                _ => naga::ShaderStage::Compute,
+            };
+            let source = std::fs::read_to_string(path).unwrap();
+            let code = vk_utils::shader::compile_glsl(&source, stage, path).unwrap();
+
+            ShaderReflection::new(&code).unwrap()
+        }
+
+        fn reflect_source(source: &str, stage: naga::ShaderStage) -> ShaderReflection {
+            let code = vk_utils::shader::compile_glsl(source, stage, "inline").unwrap();
+
+            ShaderReflection::new(&code).unwrap()
+        }
+
+        fn bindings(reflection: &[DescriptorBinding]) -> Vec<(u32, u32, vk::DescriptorType)> {
+            reflection
+                .iter()
+                .map(|binding| (binding.set, binding.binding, binding.descriptor_type))
+                .collect()
+        }
+
+        fn locations(reflection: &ShaderReflection) -> Vec<(u32, vk::Format)> {
+            reflection
+                .vertex_inputs
+                .iter()
+                .map(|vertex_input| (vertex_input.location, vertex_input.format))
+                .collect()
+        }
+
+        #[test]
+        fn textured_vertex_shader() {
+            let reflection = reflect(HELLO_TRIANGLE_VERT);
+
+            assert_eq!(reflection.stage, vk::ShaderStageFlags::VERTEX);
+            assert_eq!(
+                bindings(&reflection.descriptor_bindings),
+                vec![(0, 0, vk::DescriptorType::UNIFORM_BUFFER)]
+            );
+            assert_eq!(reflection.descriptor_bindings[0].count, 1);
+            // mat4 model + uint materialIndex
+            assert_eq!(
+                reflection
+                    .push_constant_ranges
+                    .iter()
+                    .map(|range| (range.stage_flags, range.offset, range.size))
+                    .collect::<Vec<_>>(),
+                vec![(vk::ShaderStageFlags::VERTEX, 0, 68)]
+            );
+            assert_eq!(
+                locations(&reflection),
+                vec![
+                    (0, vk::Format::R32G32B32A32_SFLOAT),
+                    (1, vk::Format::R32G32B32_SFLOAT),
+                    (2, vk::Format::R32G32_SFLOAT),
+                    (4, vk::Format::R32G32B32A32_SFLOAT),
+                    (5, vk::Format::R32G32B32A32_SFLOAT),
+                    (6, vk::Format::R32G32B32A32_SFLOAT),
+                    (7, vk::Format::R32G32B32A32_SFLOAT),
+                    (8, vk::Format::R32G32B32A32_SFLOAT),
+                ]
+            );
+            assert!(reflection
+                .validate_vertex_input::<VertexWithTexture3D, InstanceTransform>()
+                .is_ok());
+        }
+
+        #[test]
+        fn textured_fragment_shader() {
+            let reflection = reflect(HELLO_TRIANGLE_FRAG);
+
+            assert_eq!(reflection.stage, vk::ShaderStageFlags::FRAGMENT);
+            assert_eq!(
+                bindings(&reflection.descriptor_bindings),
+                vec![
+                    (0, 1, vk::DescriptorType::SAMPLED_IMAGE),
+                    (0, 2, vk::DescriptorType::SAMPLER),
+                ]
+            );
+            assert!(reflection.push_constant_ranges.is_empty());
+            assert!(reflection.vertex_inputs.is_empty());
+        }
+
+        #[test]
+        fn vertex_color_shader() {
+            let reflection = reflect(VERTEX_COLOR_VERT);
+
+            assert_eq!(
+                locations(&reflection)[..2],
+                [
+                    (0, vk::Format::R32G32_SFLOAT),
+                    (1, vk::Format::R32G32B32_SFLOAT)
+                ]
+            );
+            assert_eq!(reflection.vertex_inputs.len(), 7);
+            assert!(reflection
+                .validate_vertex_input::<Vertex2D, InstanceTransform>()
+                .is_ok());
+        }
+
+        #[test]
+        fn missing_attributes_are_a_mismatch() {
+            let reflection = reflect(HELLO_TRIANGLE_VERT);
+
+            // No instance attributes
+            assert!(matches!(
+                reflection.validate_vertex_input::<VertexWithTexture3D, ()>(),
+                Err(vk_utils::Error::ShaderInterfaceMismatch(_))
+            ));
+            // Instance attributes shifted to locations 2..
+            assert!(matches!(
+                reflection.validate_vertex_input::<Vertex2D, InstanceTransform>(),
+                Err(vk_utils::Error::ShaderInterfaceMismatch(_))
+            ));
+        }
+
+        #[test]
+        fn stages_merge_into_one_layout() {
+            let mut reflections = [reflect(HELLO_TRIANGLE_VERT), reflect(HELLO_TRIANGLE_FRAG)];
+            reflections[0].use_dynamic_offsets(0, 0);
+
+            let merged = merge_descriptor_bindings(&reflections).unwrap();
+
+            assert_eq!(
+                bindings(&merged),
+                vec![
+                    (0, 0, vk::DescriptorType::UNIFORM_BUFFER_DYNAMIC),
+                    (0, 1, vk::DescriptorType::SAMPLED_IMAGE),
+                    (0, 2, vk::DescriptorType::SAMPLER),
+                ]
+            );
+            assert_eq!(merged[0].stage_flags, vk::ShaderStageFlags::VERTEX);
+            assert_eq!(merged[1].stage_flags, vk::ShaderStageFlags::FRAGMENT);
+            assert_eq!(merge_push_constant_ranges(&reflections).len(), 1);
+        }
+
+        #[test]
+        fn conflicting_bindings_fail_to_merge() {
+            let reflections = [
+                reflect(HELLO_TRIANGLE_FRAG),
+                reflect_source(
+                    "#version 450\n\
+                     layout(binding = 1) uniform Data { vec4 value; } data;\n\
+                     layout(location = 0) out vec4 outColor;\n\
+                     void main() { outColor = data.value; }\n",
+                    naga::ShaderStage::Fragment,
+                ),
+            ];
+
+            assert!(matches!(
+                merge_descriptor_bindings(&reflections),
+                Err(vk_utils::Error::ShaderInterfaceMismatch(_))
+            ));
+        }
+
+        #[test]
+        fn compute_shader_storage_buffers() {
+            let reflection = reflect_source(
+                "#version 450\n\
+                 layout(local_size_x = 64) in;\n\
+                 layout(set = 1, binding = 3) buffer Particles { vec4 positions[]; } particles;\n\
+                 layout(push_constant) uniform Step { float deltaTime; } step;\n\
+                 void main() {\n\
+                     particles.positions[gl_GlobalInvocationID.x].x += step.deltaTime;\n\
+                 }\n",
+                naga::ShaderStage::Compute,
+            );
+
+            assert_eq!(reflection.stage, vk::ShaderStageFlags::COMPUTE);
+            assert_eq!(
+                bindings(&reflection.descriptor_bindings),
+                vec![(1, 3, vk::DescriptorType::STORAGE_BUFFER)]
+            );
+            assert_eq!(reflection.push_constant_ranges[0].size, 4);
+            assert!(reflection.vertex_inputs.is_empty());
+        }
+    }
+}
+
+pub use _reflection::{
+    create_descriptor_set_layouts, create_pipeline_layout, merge_descriptor_bindings,
+    merge_push_constant_ranges, DescriptorBinding, ShaderReflection, VertexInput,
+};
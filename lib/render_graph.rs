@@ -0,0 +1,1456 @@
+mod _render_graph {
+    use crate::{
+        self as vk_utils,
+        framebuffer::Framebuffer,
+        image::{Image, ImageView},
+    };
+    use ash::vk;
+
+    const WRITE_ACCESS: vk::AccessFlags = vk::AccessFlags::from_raw(
+        vk::AccessFlags::COLOR_ATTACHMENT_WRITE.as_raw()
+            | vk::AccessFlags::DEPTH_STENCIL_ATTACHMENT_WRITE.as_raw()
+            | vk::AccessFlags::SHADER_WRITE.as_raw()
+            | vk::AccessFlags::TRANSFER_WRITE.as_raw(),
+    );
+
+    // Handles only mean something to the builder (and graph) that handed them out
+    #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+    pub struct ImageHandle(usize);
+
+    #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+    pub struct BufferHandle(usize);
+
+    #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+    pub struct PassHandle(usize);
+
+    #[derive(Clone, Copy, Debug)]
+    pub struct ImageDesc {
+        pub format: vk::Format,
+        pub extent: vk::Extent2D,
+        pub samples: vk::SampleCountFlags,
+    }
+
+    // What happens to an attachment's previous contents when a pass begins
+    #[derive(Clone, Copy)]
+    pub enum AttachmentLoad {
+        Clear(vk::ClearValue),
+        Load,
+        DontCare,
+    }
+
+    // Image uses other than being rendered to
+    #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+    pub enum ImageUsage {
+        Sampled,
+        StorageRead,
+        StorageWrite,
+        TransferSrc,
+        TransferDst,
+    }
+
+    #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+    pub enum BufferUsage {
+        Vertex,
+        Index,
+        Indirect,
+        Uniform,
+        StorageRead,
+        StorageWrite,
+        TransferSrc,
+        TransferDst,
+    }
+
+    #[derive(Clone, Copy)]
+    enum ImageAccess {
+        Color,
+        Depth,
+        Resolve,
+        Other(ImageUsage),
+    }
+
+    // Layout, stages and access a use of an image needs
+    fn image_access(
+        access: ImageAccess,
+        shader_stages: vk::PipelineStageFlags,
+    ) -> (vk::ImageLayout, vk::PipelineStageFlags, vk::AccessFlags) {
+        match access {
+            ImageAccess::Color => (
+                vk::ImageLayout::COLOR_ATTACHMENT_OPTIMAL,
+                vk::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT,
+                vk::AccessFlags::COLOR_ATTACHMENT_READ | vk::AccessFlags::COLOR_ATTACHMENT_WRITE,
+            ),
+            ImageAccess::Depth => (
+                vk::ImageLayout::DEPTH_STENCIL_ATTACHMENT_OPTIMAL,
+                vk::PipelineStageFlags::EARLY_FRAGMENT_TESTS
+                    | vk::PipelineStageFlags::LATE_FRAGMENT_TESTS,
+                vk::AccessFlags::DEPTH_STENCIL_ATTACHMENT_READ
+                    | vk::AccessFlags::DEPTH_STENCIL_ATTACHMENT_WRITE,
+            ),
+            ImageAccess::Resolve => (
+                vk::ImageLayout::COLOR_ATTACHMENT_OPTIMAL,
+                vk::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT,
+                vk::AccessFlags::COLOR_ATTACHMENT_WRITE,
+            ),
+            ImageAccess::Other(ImageUsage::Sampled) => (
+                vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL,
+                shader_stages,
+                vk::AccessFlags::SHADER_READ,
+            ),
+            ImageAccess::Other(ImageUsage::StorageRead) => (
+                vk::ImageLayout::GENERAL,
+                shader_stages,
+                vk::AccessFlags::SHADER_READ,
+            ),
+            ImageAccess::Other(ImageUsage::StorageWrite) => (
+                vk::ImageLayout::GENERAL,
+                shader_stages,
+                vk::AccessFlags::SHADER_READ | vk::AccessFlags::SHADER_WRITE,
+            ),
+            ImageAccess::Other(ImageUsage::TransferSrc) => (
+                vk::ImageLayout::TRANSFER_SRC_OPTIMAL,
+                vk::PipelineStageFlags::TRANSFER,
+                vk::AccessFlags::TRANSFER_READ,
+            ),
+            ImageAccess::Other(ImageUsage::TransferDst) => (
+                vk::ImageLayout::TRANSFER_DST_OPTIMAL,
+                vk::PipelineStageFlags::TRANSFER,
+                vk::AccessFlags::TRANSFER_WRITE,
+            ),
+        }
+    }
+
+    fn image_usage_flags(access: ImageAccess) -> vk::ImageUsageFlags {
+        match access {
+            ImageAccess::Color | ImageAccess::Resolve => vk::ImageUsageFlags::COLOR_ATTACHMENT,
+            ImageAccess::Depth => vk::ImageUsageFlags::DEPTH_STENCIL_ATTACHMENT,
+            ImageAccess::Other(ImageUsage::Sampled) => vk::ImageUsageFlags::SAMPLED,
+            ImageAccess::Other(ImageUsage::StorageRead | ImageUsage::StorageWrite) => {
+                vk::ImageUsageFlags::STORAGE
+            }
+            ImageAccess::Other(ImageUsage::TransferSrc) => vk::ImageUsageFlags::TRANSFER_SRC,
+            ImageAccess::Other(ImageUsage::TransferDst) => vk::ImageUsageFlags::TRANSFER_DST,
+        }
+    }
+
+    fn buffer_access(
+        usage: BufferUsage,
+        shader_stages: vk::PipelineStageFlags,
+    ) -> (vk::PipelineStageFlags, vk::AccessFlags) {
+        match usage {
+            BufferUsage::Vertex => (
+                vk::PipelineStageFlags::VERTEX_INPUT,
+                vk::AccessFlags::VERTEX_ATTRIBUTE_READ,
+            ),
+            BufferUsage::Index => (
+                vk::PipelineStageFlags::VERTEX_INPUT,
+                vk::AccessFlags::INDEX_READ,
+            ),
+            BufferUsage::Indirect => (
+                vk::PipelineStageFlags::DRAW_INDIRECT,
+                vk::AccessFlags::INDIRECT_COMMAND_READ,
+            ),
+            BufferUsage::Uniform => (shader_stages, vk::AccessFlags::UNIFORM_READ),
+            BufferUsage::StorageRead => (shader_stages, vk::AccessFlags::SHADER_READ),
+            BufferUsage::StorageWrite => (
+                shader_stages,
+                vk::AccessFlags::SHADER_READ | vk::AccessFlags::SHADER_WRITE,
+            ),
+            BufferUsage::TransferSrc => (
+                vk::PipelineStageFlags::TRANSFER,
+                vk::AccessFlags::TRANSFER_READ,
+            ),
+            BufferUsage::TransferDst => (
+                vk::PipelineStageFlags::TRANSFER,
+                vk::AccessFlags::TRANSFER_WRITE,
+            ),
+        }
+    }
+
+    fn aspect_mask(format: vk::Format) -> vk::ImageAspectFlags {
+        match format {
+            vk::Format::D16_UNORM | vk::Format::X8_D24_UNORM_PACK32 | vk::Format::D32_SFLOAT => {
+                vk::ImageAspectFlags::DEPTH
+            }
+            vk::Format::S8_UINT => vk::ImageAspectFlags::STENCIL,
+            vk::Format::D16_UNORM_S8_UINT
+            | vk::Format::D24_UNORM_S8_UINT
+            | vk::Format::D32_SFLOAT_S8_UINT => {
+                vk::ImageAspectFlags::DEPTH | vk::ImageAspectFlags::STENCIL
+            }
+            _ => vk::ImageAspectFlags::COLOR,
+        }
+    }
+
+    // A pass and everything it reads and writes, declared up front
+    #[derive(Clone)]
+    pub struct Pass {
+        name: String,
+        shader_stages: vk::PipelineStageFlags,
+        color_attachments: Vec<(ImageHandle, AttachmentLoad)>,
+        depth_attachment: Option<(ImageHandle, AttachmentLoad)>,
+        resolve_attachments: Vec<ImageHandle>,
+        images: Vec<(ImageHandle, ImageUsage)>,
+        buffers: Vec<(BufferHandle, BufferUsage)>,
+    }
+
+    impl Pass {
+        pub fn new(name: &str) -> Self {
+            Self {
+                name: name.to_string(),
+                shader_stages: vk::PipelineStageFlags::FRAGMENT_SHADER,
+                color_attachments: Vec::new(),
+                depth_attachment: None,
+                resolve_attachments: Vec::new(),
+                images: Vec::new(),
+                buffers: Vec::new(),
+            }
+        }
+
+        // Stages that sampled, storage and uniform resources are accessed from
+        pub fn shader_stages(mut self, stages: vk::PipelineStageFlags) -> Self {
+            self.shader_stages = stages;
+            self
+        }
+
+        pub fn color_attachment(mut self, image: ImageHandle, load: AttachmentLoad) -> Self {
+            self.color_attachments.push((image, load));
+            self
+        }
+
+        pub fn depth_attachment(mut self, image: ImageHandle, load: AttachmentLoad) -> Self {
+            self.depth_attachment = Some((image, load));
+            self
+        }
+
+        // Multisampled color attachments are resolved in declaration order
+        pub fn resolve_attachment(mut self, image: ImageHandle) -> Self {
+            self.resolve_attachments.push(image);
+            self
+        }
+
+        pub fn image(mut self, image: ImageHandle, usage: ImageUsage) -> Self {
+            self.images.push((image, usage));
+            self
+        }
+
+        pub fn buffer(mut self, buffer: BufferHandle, usage: BufferUsage) -> Self {
+            self.buffers.push((buffer, usage));
+            self
+        }
+
+        fn has_attachments(&self) -> bool {
+            !self.color_attachments.is_empty() || self.depth_attachment.is_some()
+        }
+
+        // Attachments in render pass order: colors, depth, resolves
+        fn attachments(&self) -> Vec<(ImageHandle, ImageAccess, AttachmentLoad)> {
+            self.color_attachments
+                .iter()
+                .map(|&(image, load)| (image, ImageAccess::Color, load))
+                .chain(
+                    self.depth_attachment
+                        .iter()
+                        .map(|&(image, load)| (image, ImageAccess::Depth, load)),
+                )
+                .chain(
+                    self.resolve_attachments
+                        .iter()
+                        .map(|&image| (image, ImageAccess::Resolve, AttachmentLoad::DontCare)),
+                )
+                .collect()
+        }
+
+        fn image_accesses(&self) -> Vec<(ImageHandle, ImageAccess)> {
+            self.attachments()
+                .into_iter()
+                .map(|(image, access, _)| (image, access))
+                .chain(
+                    self.images
+                        .iter()
+                        .map(|&(image, usage)| (image, ImageAccess::Other(usage))),
+                )
+                .collect()
+        }
+    }
+
+    struct ImageDecl {
+        name: String,
+        desc: ImageDesc,
+        // Empty for transient images, which the graph allocates itself
+        images: Vec<vk::Image>,
+        views: Vec<vk::ImageView>,
+        initial_layout: vk::ImageLayout,
+        final_layout: Option<vk::ImageLayout>,
+    }
+
+    #[derive(Default)]
+    pub struct RenderGraphBuilder {
+        images: Vec<ImageDecl>,
+        buffers: Vec<vk::Buffer>,
+        passes: Vec<Pass>,
+        dynamic_rendering: bool,
+    }
+
+    impl RenderGraphBuilder {
+        pub fn new() -> Self {
+            Self::default()
+        }
+
+        // Begin/end rendering instead of render pass and framebuffer objects,
+        // the device has to be created with the dynamicRendering feature
+        pub fn dynamic_rendering(&mut self, enabled: bool) {
+            self.dynamic_rendering = enabled;
+        }
+
+        // Image owned elsewhere; with one view per swap chain image the view
+        // used is picked by the image index passed to `RenderGraph::record`
+        pub fn import_image(
+            &mut self,
+            name: &str,
+            desc: ImageDesc,
+            images: &[vk::Image],
+            views: &[vk::ImageView],
+            initial_layout: vk::ImageLayout,
+            final_layout: vk::ImageLayout,
+        ) -> ImageHandle {
+            self.images.push(ImageDecl {
+                name: name.to_string(),
+                desc,
+                images: images.to_vec(),
+                views: views.to_vec(),
+                initial_layout,
+                final_layout: Some(final_layout),
+            });
+
+            ImageHandle(self.images.len() - 1)
+        }
+
+        // Image that only lives inside the graph, allocated when it is built
+        pub fn create_image(&mut self, name: &str, desc: ImageDesc) -> ImageHandle {
+            self.images.push(ImageDecl {
+                name: name.to_string(),
+                desc,
+                images: Vec::new(),
+                views: Vec::new(),
+                initial_layout: vk::ImageLayout::UNDEFINED,
+                final_layout: None,
+            });
+
+            ImageHandle(self.images.len() - 1)
+        }
+
+        pub fn import_buffer(&mut self, buffer: vk::Buffer) -> BufferHandle {
+            self.buffers.push(buffer);
+
+            BufferHandle(self.buffers.len() - 1)
+        }
+
+        // Passes run in the order they are added
+        pub fn add_pass(&mut self, pass: Pass) -> PassHandle {
+            self.passes.push(pass);
+
+            PassHandle(self.passes.len() - 1)
+        }
+
+        fn validate(&self) -> vk_utils::Result<()> {
+            let invalid = |message: String| Err(vk_utils::Error::InvalidRenderGraph(message));
+
+            for pass in self.passes.iter() {
+                let accesses = pass.image_accesses();
+
+                // Handles handed out by another builder
+                if accesses
+                    .iter()
+                    .any(|&(image, _)| image.0 >= self.images.len())
+                    || pass
+                        .buffers
+                        .iter()
+                        .any(|&(buffer, _)| buffer.0 >= self.buffers.len())
+                {
+                    return invalid(format!(
+                        "pass \"{}\" uses a resource not declared in this graph",
+                        pass.name
+                    ));
+                }
+
+                // A write can't be ordered against another use within the same pass
+                let writes = |access: ImageAccess| {
+                    image_access(access, pass.shader_stages)
+                        .2
+                        .intersects(WRITE_ACCESS)
+                };
+                for (position, &(image, access)) in accesses.iter().enumerate() {
+                    if accesses[..position].iter().any(|&(other, other_access)| {
+                        other == image && (writes(access) || writes(other_access))
+                    }) {
+                        return invalid(format!(
+                            "image \"{}\" is written and used again in pass \"{}\"",
+                            self.images[image.0].name, pass.name
+                        ));
+                    }
+                }
+
+                if !pass.resolve_attachments.is_empty()
+                    && pass.resolve_attachments.len() != pass.color_attachments.len()
+                {
+                    return invalid(format!(
+                        "pass \"{}\" has {} color but {} resolve attachments",
+                        pass.name,
+                        pass.color_attachments.len(),
+                        pass.resolve_attachments.len()
+                    ));
+                }
+
+                let attachments = pass.attachments();
+                if let Some(&(first, _, _)) = attachments.first() {
+                    let extent = self.images[first.0].desc.extent;
+
+                    for &(image, _, _) in attachments.iter() {
+                        if self.images[image.0].desc.extent != extent {
+                            return invalid(format!(
+                                "attachment \"{}\" of pass \"{}\" differs in size",
+                                self.images[image.0].name, pass.name
+                            ));
+                        }
+                    }
+                }
+            }
+
+            for image in self.images.iter() {
+                if image.final_layout.is_some()
+                    && (image.views.is_empty() || image.images.len() != image.views.len())
+                {
+                    return invalid(format!(
+                        "imported image \"{}\" needs one view per image",
+                        image.name
+                    ));
+                }
+            }
+
+            Ok(())
+        }
+
+        // Usage a transient image is created with, the union of all its uses.
+        // None for imported images and images no pass uses
+        fn transient_usage(&self, index: usize) -> Option<vk::ImageUsageFlags> {
+            if self.images[index].final_layout.is_some() {
+                return None;
+            }
+
+            let accesses = self
+                .passes
+                .iter()
+                .flat_map(|pass| pass.image_accesses())
+                .filter(|&(image, _)| image.0 == index)
+                .map(|(_, access)| access)
+                .collect::<Vec<_>>();
+            if accesses.is_empty() {
+                return None;
+            }
+
+            let mut usage = accesses
+                .iter()
+                .fold(vk::ImageUsageFlags::empty(), |usage, &access| {
+                    usage | image_usage_flags(access)
+                });
+            // Never leaves tile memory on hardware that cares
+            if accesses.iter().all(|access| {
+                matches!(
+                    access,
+                    ImageAccess::Color | ImageAccess::Depth | ImageAccess::Resolve
+                )
+            }) {
+                usage |= vk::ImageUsageFlags::TRANSIENT_ATTACHMENT;
+            }
+
+            Some(usage)
+        }
+
+        // Walks the passes in order and derives barriers, load/store ops and render pass
+        // descriptions without touching the device
+        fn plan(&self) -> GraphPlan {
+            let mut plan = GraphPlan {
+                transient_usages: (0..self.images.len())
+                    .map(|index| self.transient_usage(index))
+                    .collect(),
+                passes: Vec::new(),
+                final_barriers: Barriers::default(),
+            };
+
+            let mut image_states = self
+                .images
+                .iter()
+                .map(|decl| ResourceState::new(decl.initial_layout))
+                .collect::<Vec<_>>();
+            let mut buffer_states = self
+                .buffers
+                .iter()
+                .map(|_| ResourceState::new(vk::ImageLayout::UNDEFINED))
+                .collect::<Vec<_>>();
+            let last_uses = (0..self.images.len())
+                .map(|index| {
+                    self.passes.iter().rposition(|pass| {
+                        pass.image_accesses()
+                            .iter()
+                            .any(|&(image, _)| image.0 == index)
+                    })
+                })
+                .collect::<Vec<_>>();
+
+            for (pass_index, pass) in self.passes.iter().enumerate() {
+                let mut barriers = Barriers::default();
+
+                for &(image, usage) in pass.images.iter() {
+                    let (layout, stage, access) =
+                        image_access(ImageAccess::Other(usage), pass.shader_stages);
+
+                    if let Some((src_stage, src_access)) =
+                        image_states[image.0].access(layout, stage, access)
+                    {
+                        barriers.src_stage |= src_stage;
+                        barriers.dst_stage |= stage;
+                        barriers.images.push(ImageBarrier {
+                            image,
+                            old_layout: image_states[image.0].layout,
+                            new_layout: layout,
+                            src_access,
+                            dst_access: access,
+                        });
+                    }
+                    image_states[image.0].layout = layout;
+                }
+
+                for &(buffer, usage) in pass.buffers.iter() {
+                    let (stage, access) = buffer_access(usage, pass.shader_stages);
+
+                    if let Some((src_stage, src_access)) =
+                        buffer_states[buffer.0].access(vk::ImageLayout::UNDEFINED, stage, access)
+                    {
+                        barriers.src_stage |= src_stage;
+                        barriers.dst_stage |= stage;
+                        barriers.buffers.push(BufferBarrier {
+                            buffer,
+                            src_access,
+                            dst_access: access,
+                        });
+                    }
+                }
+
+                let mut compiled = CompiledPass {
+                    barriers,
+                    render_pass: vk::RenderPass::null(),
+                    framebuffers: Vec::new(),
+                    attachments: Vec::new(),
+                    color_count: pass.color_attachments.len(),
+                    has_depth: pass.depth_attachment.is_some(),
+                    clear_values: Vec::new(),
+                    extent: vk::Extent2D::default(),
+                };
+
+                if !pass.has_attachments() {
+                    plan.passes.push((compiled, None));
+                    continue;
+                }
+
+                let mut descriptions = vec![];
+                let mut dependency = vk::SubpassDependency::builder()
+                    .src_subpass(vk::SUBPASS_EXTERNAL)
+                    .dst_subpass(0)
+                    .build();
+
+                for (image, access, load) in pass.attachments() {
+                    let decl = &self.images[image.0];
+                    let state = &mut image_states[image.0];
+                    let (layout, stage, access_flags) = image_access(access, pass.shader_stages);
+
+                    let (src_stage, src_access) = state.attachment_access(stage, access_flags);
+                    let (load_op, initial_layout) = match load {
+                        AttachmentLoad::Clear(_) => {
+                            (vk::AttachmentLoadOp::CLEAR, vk::ImageLayout::UNDEFINED)
+                        }
+                        AttachmentLoad::Load => (vk::AttachmentLoadOp::LOAD, state.layout),
+                        AttachmentLoad::DontCare => {
+                            (vk::AttachmentLoadOp::DONT_CARE, vk::ImageLayout::UNDEFINED)
+                        }
+                    };
+                    // Contents only need to survive when someone looks at them later
+                    let store_op =
+                        if decl.final_layout.is_some() || last_uses[image.0] != Some(pass_index) {
+                            vk::AttachmentStoreOp::STORE
+                        } else {
+                            vk::AttachmentStoreOp::DONT_CARE
+                        };
+                    compiled.clear_values.push(match load {
+                        AttachmentLoad::Clear(clear_value) => clear_value,
+                        _ => vk::ClearValue::default(),
+                    });
+                    compiled.attachments.push(CompiledAttachment {
+                        image,
+                        layout,
+                        load_op,
+                        store_op,
+                    });
+
+                    if self.dynamic_rendering {
+                        // No render pass to do the transition, so it's a barrier
+                        compiled.barriers.src_stage |= src_stage;
+                        compiled.barriers.dst_stage |= stage;
+                        compiled.barriers.images.push(ImageBarrier {
+                            image,
+                            old_layout: initial_layout,
+                            new_layout: layout,
+                            src_access,
+                            dst_access: access_flags,
+                        });
+                        state.layout = layout;
+
+                        continue;
+                    }
+
+                    // Transitions happen in the render pass, only the dependency is needed
+                    dependency.src_stage_mask |= src_stage;
+                    dependency.src_access_mask |= src_access;
+                    dependency.dst_stage_mask |= stage;
+                    dependency.dst_access_mask |= access_flags;
+
+                    let final_layout = match decl.final_layout {
+                        Some(final_layout)
+                            if last_uses[image.0] == Some(pass_index)
+                                && final_layout != vk::ImageLayout::UNDEFINED =>
+                        {
+                            final_layout
+                        }
+                        _ => layout,
+                    };
+                    let has_stencil =
+                        aspect_mask(decl.desc.format).contains(vk::ImageAspectFlags::STENCIL);
+
+                    descriptions.push(
+                        vk::AttachmentDescription::builder()
+                            .format(decl.desc.format)
+                            .samples(decl.desc.samples)
+                            .load_op(load_op)
+                            .store_op(store_op)
+                            .stencil_load_op(if has_stencil {
+                                load_op
+                            } else {
+                                vk::AttachmentLoadOp::DONT_CARE
+                            })
+                            .stencil_store_op(if has_stencil {
+                                store_op
+                            } else {
+                                vk::AttachmentStoreOp::DONT_CARE
+                            })
+                            .initial_layout(initial_layout)
+                            .final_layout(final_layout)
+                            .build(),
+                    );
+
+                    state.layout = final_layout;
+                }
+
+                compiled.extent = self.images[compiled.attachments[0].image.0].desc.extent;
+
+                let render_pass = (!self.dynamic_rendering).then_some(RenderPassDesc {
+                    descriptions,
+                    dependency,
+                });
+                plan.passes.push((compiled, render_pass));
+            }
+
+            // Imported images are handed back in the layout they were promised in
+            for (index, decl) in self.images.iter().enumerate() {
+                let state = &image_states[index];
+
+                match decl.final_layout {
+                    Some(final_layout)
+                        if final_layout != vk::ImageLayout::UNDEFINED
+                            && final_layout != state.layout
+                            && last_uses[index].is_some() =>
+                    {
+                        plan.final_barriers.src_stage |= state.write_stage | state.read_stages;
+                        plan.final_barriers.dst_stage |= vk::PipelineStageFlags::BOTTOM_OF_PIPE;
+                        plan.final_barriers.images.push(ImageBarrier {
+                            image: ImageHandle(index),
+                            old_layout: state.layout,
+                            new_layout: final_layout,
+                            src_access: state.write_access,
+                            dst_access: vk::AccessFlags::empty(),
+                        });
+                    }
+                    _ => {}
+                }
+            }
+
+            plan
+        }
+
+        // Allocates transient images and creates the render passes and framebuffers
+        // the plan calls for
+        pub fn build(
+            self,
+            device: &ash::Device,
+            allocator: &vk_utils::memory::Allocator,
+        ) -> vk_utils::Result<RenderGraph> {
+            self.validate()?;
+
+            let plan = self.plan();
+            let mut graph = RenderGraph {
+                device: device.clone(),
+                images: Vec::new(),
+                buffers: self.buffers.clone(),
+                passes: Vec::new(),
+                final_barriers: plan.final_barriers,
+                transient_images: Vec::new(),
+            };
+
+            for (decl, usage) in self.images.iter().zip(plan.transient_usages) {
+                let mut resource = ImageResource {
+                    format: decl.desc.format,
+                    images: decl.images.clone(),
+                    views: decl.views.clone(),
+                };
+
+                if let Some(usage) = usage {
+                    let image = vk_utils::image::create_image(
+                        device,
+                        allocator,
+                        &vk_utils::image::ImageCreateInfo {
+                            extent: decl.desc.extent,
+                            mip_levels: 1,
+                            samples: decl.desc.samples,
+                            format: decl.desc.format,
+                            tiling: vk::ImageTiling::OPTIMAL,
+                            usage,
+                            properties: vk::MemoryPropertyFlags::DEVICE_LOCAL,
+                        },
+                    )?;
+                    let image_view = vk_utils::swapchain::create_image_view(
+                        device,
+                        image.handle(),
+                        decl.desc.format,
+                        aspect_mask(decl.desc.format),
+                        1,
+                    )?;
+
+                    resource.images = vec![image.handle()];
+                    resource.views = vec![image_view.handle()];
+                    graph.transient_images.push((image, image_view));
+                }
+
+                graph.images.push(resource);
+            }
+
+            for (pass_index, (mut compiled, render_pass)) in plan.passes.into_iter().enumerate() {
+                let Some(render_pass) = render_pass else {
+                    graph.passes.push(compiled);
+                    continue;
+                };
+                let pass = &self.passes[pass_index];
+                let attachments = pass.attachments();
+
+                let reference = |index: usize, access: ImageAccess| {
+                    vk::AttachmentReference::builder()
+                        .attachment(index as u32)
+                        .layout(image_access(access, pass.shader_stages).0)
+                        .build()
+                };
+                let color_count = pass.color_attachments.len();
+                let color_refs = (0..color_count)
+                    .map(|index| reference(index, ImageAccess::Color))
+                    .collect::<Vec<_>>();
+                let depth_ref = reference(color_count, ImageAccess::Depth);
+                let resolve_offset = color_count + pass.depth_attachment.iter().count();
+                let resolve_refs = (0..pass.resolve_attachments.len())
+                    .map(|index| reference(resolve_offset + index, ImageAccess::Resolve))
+                    .collect::<Vec<_>>();
+
+                let mut subpass = vk::SubpassDescription::builder()
+                    .pipeline_bind_point(vk::PipelineBindPoint::GRAPHICS)
+                    .color_attachments(&color_refs);
+                if pass.depth_attachment.is_some() {
+                    subpass = subpass.depth_stencil_attachment(&depth_ref);
+                }
+                if !resolve_refs.is_empty() {
+                    subpass = subpass.resolve_attachments(&resolve_refs);
+                }
+
+                let subpasses = [subpass.build()];
+                let dependencies = [render_pass.dependency];
+                let render_pass_info = vk::RenderPassCreateInfo::builder()
+                    .attachments(&render_pass.descriptions)
+                    .subpasses(&subpasses)
+                    .dependencies(&dependencies);
+
+                compiled.render_pass = unsafe {
+                    device
+                        .create_render_pass(&render_pass_info, None)
+                        .map_err(vk_utils::Error::vulkan("failed to create render pass!"))?
+                };
+
+                // One framebuffer per swap chain image the pass renders to
+                let framebuffer_count = attachments
+                    .iter()
+                    .map(|&(image, _, _)| graph.images[image.0].views.len())
+                    .max()
+                    .unwrap_or(1);
+                // Pushed before creating the framebuffers so Drop cleans up on errors
+                let render_pass = compiled.render_pass;
+                let extent = compiled.extent;
+                graph.passes.push(compiled);
+
+                for framebuffer_index in 0..framebuffer_count {
+                    let views = attachments
+                        .iter()
+                        .map(|&(image, _, _)| {
+                            let views = &graph.images[image.0].views;
+                            views[framebuffer_index % views.len()]
+                        })
+                        .collect::<Vec<_>>();
+                    let framebuffer = Framebuffer::new(device, render_pass, &views, extent)?;
+
+                    graph.passes[pass_index].framebuffers.push(framebuffer);
+                }
+            }
+
+            Ok(graph)
+        }
+    }
+
+    // What happened to a resource so far while walking the passes
+    struct ResourceState {
+        layout: vk::ImageLayout,
+        write_stage: vk::PipelineStageFlags,
+        write_access: vk::AccessFlags,
+        // Stages that read since the last write and already see it
+        read_stages: vk::PipelineStageFlags,
+    }
+
+    impl ResourceState {
+        fn new(layout: vk::ImageLayout) -> Self {
+            Self {
+                layout,
+                write_stage: vk::PipelineStageFlags::empty(),
+                write_access: vk::AccessFlags::empty(),
+                read_stages: vk::PipelineStageFlags::empty(),
+            }
+        }
+
+        // Source stage and access of the barrier an access needs, if any
+        fn access(
+            &mut self,
+            layout: vk::ImageLayout,
+            stage: vk::PipelineStageFlags,
+            access: vk::AccessFlags,
+        ) -> Option<(vk::PipelineStageFlags, vk::AccessFlags)> {
+            let writes = access.intersects(WRITE_ACCESS);
+            let transitions = layout != self.layout;
+
+            let barrier = if writes || transitions {
+                // Everything before has to be done, a layout transition is a write too
+                Some((self.write_stage | self.read_stages, self.write_access))
+            } else if !self.write_access.is_empty() && !self.read_stages.contains(stage) {
+                Some((self.write_stage, self.write_access))
+            } else {
+                None
+            };
+
+            if writes {
+                self.write_stage = stage;
+                self.write_access = access & WRITE_ACCESS;
+                self.read_stages = vk::PipelineStageFlags::empty();
+            } else if transitions {
+                self.write_stage = stage;
+                self.write_access = vk::AccessFlags::empty();
+                self.read_stages = stage;
+            } else {
+                self.read_stages |= stage;
+            }
+
+            barrier.map(|(src_stage, src_access)| {
+                if src_stage.is_empty() {
+                    (vk::PipelineStageFlags::TOP_OF_PIPE, src_access)
+                } else {
+                    (src_stage, src_access)
+                }
+            })
+        }
+
+        // Source half of a render pass dependency for an attachment write
+        fn attachment_access(
+            &mut self,
+            stage: vk::PipelineStageFlags,
+            access: vk::AccessFlags,
+        ) -> (vk::PipelineStageFlags, vk::AccessFlags) {
+            let src = if self.write_stage.is_empty() && self.read_stages.is_empty() {
+                // First use this frame still has to wait for the last frame's writes
+                // (and for the swap chain image to be acquired)
+                (stage, access & WRITE_ACCESS)
+            } else {
+                (self.write_stage | self.read_stages, self.write_access)
+            };
+
+            self.write_stage = stage;
+            self.write_access = access & WRITE_ACCESS;
+            self.read_stages = vk::PipelineStageFlags::empty();
+
+            src
+        }
+    }
+
+    struct ImageBarrier {
+        image: ImageHandle,
+        old_layout: vk::ImageLayout,
+        new_layout: vk::ImageLayout,
+        src_access: vk::AccessFlags,
+        dst_access: vk::AccessFlags,
+    }
+
+    struct BufferBarrier {
+        buffer: BufferHandle,
+        src_access: vk::AccessFlags,
+        dst_access: vk::AccessFlags,
+    }
+
+    // Recorded as a single vkCmdPipelineBarrier
+    #[derive(Default)]
+    struct Barriers {
+        src_stage: vk::PipelineStageFlags,
+        dst_stage: vk::PipelineStageFlags,
+        images: Vec<ImageBarrier>,
+        buffers: Vec<BufferBarrier>,
+    }
+
+    struct ImageResource {
+        format: vk::Format,
+        images: Vec<vk::Image>,
+        views: Vec<vk::ImageView>,
+    }
+
+    struct CompiledAttachment {
+        image: ImageHandle,
+        layout: vk::ImageLayout,
+        load_op: vk::AttachmentLoadOp,
+        store_op: vk::AttachmentStoreOp,
+    }
+
+    struct CompiledPass {
+        barriers: Barriers,
+        // Null for passes without attachments and with dynamic rendering
+        render_pass: vk::RenderPass,
+        framebuffers: Vec<Framebuffer>,
+        // In render pass order, empty for passes that don't render
+        attachments: Vec<CompiledAttachment>,
+        color_count: usize,
+        has_depth: bool,
+        clear_values: Vec<vk::ClearValue>,
+        extent: vk::Extent2D,
+    }
+
+    // Attachments and dependency of the single subpass render pass a pass becomes
+    struct RenderPassDesc {
+        descriptions: Vec<vk::AttachmentDescription>,
+        dependency: vk::SubpassDependency,
+    }
+
+    // Everything `build` derives before creating Vulkan objects
+    struct GraphPlan {
+        // Indexed like the declared images
+        transient_usages: Vec<Option<vk::ImageUsageFlags>>,
+        // Render pass to create, None without attachments or with dynamic rendering
+        passes: Vec<(CompiledPass, Option<RenderPassDesc>)>,
+        final_barriers: Barriers,
+    }
+
+    pub struct RenderGraph {
+        device: ash::Device,
+        images: Vec<ImageResource>,
+        buffers: Vec<vk::Buffer>,
+        passes: Vec<CompiledPass>,
+        final_barriers: Barriers,
+        transient_images: Vec<(Image, ImageView)>,
+    }
+
+    impl RenderGraph {
+        pub fn render_pass(&self, pass: PassHandle) -> vk::RenderPass {
+            self.passes[pass.0].render_pass
+        }
+
+        pub fn extent(&self, pass: PassHandle) -> vk::Extent2D {
+            self.passes[pass.0].extent
+        }
+
+        // Only applies to attachments declared with AttachmentLoad::Clear
+        pub fn set_clear_value(
+            &mut self,
+            pass: PassHandle,
+            image: ImageHandle,
+            clear_value: vk::ClearValue,
+        ) {
+            let pass = &mut self.passes[pass.0];
+
+            for (attachment, value) in pass.attachments.iter().zip(pass.clear_values.iter_mut()) {
+                if attachment.image == image && attachment.load_op == vk::AttachmentLoadOp::CLEAR {
+                    *value = clear_value;
+                }
+            }
+        }
+
+        // Records every pass in order, `record_pass` fills in the draws (or dispatches)
+        // and is called inside the render pass with viewport and scissor set
+        pub fn record<F>(
+            &self,
+            command_buffer: vk::CommandBuffer,
+            image_index: usize,
+            mut record_pass: F,
+        ) -> vk_utils::Result<()>
+        where
+            F: FnMut(PassHandle, vk::CommandBuffer) -> vk_utils::Result<()>,
+        {
+            for (pass_index, pass) in self.passes.iter().enumerate() {
+                self.record_barriers(command_buffer, image_index, &pass.barriers);
+
+                if pass.attachments.is_empty() {
+                    record_pass(PassHandle(pass_index), command_buffer)?;
+                    continue;
+                }
+
+                let viewports = [vk::Viewport::builder()
+                    .x(0.0)
+                    .y(0.0)
+                    .width(pass.extent.width as f32)
+                    .height(pass.extent.height as f32)
+                    .min_depth(0.0)
+                    .max_depth(1.0)
+                    .build()];
+                let scissors = [vk::Rect2D::builder()
+                    .offset(vk::Offset2D { x: 0, y: 0 })
+                    .extent(pass.extent)
+                    .build()];
+
+                if pass.render_pass != vk::RenderPass::null() {
+                    self.begin_render_pass(command_buffer, image_index, pass);
+                } else {
+                    self.begin_rendering(command_buffer, image_index, pass);
+                }
+
+                unsafe {
+                    self.device.cmd_set_viewport(command_buffer, 0, &viewports);
+                    self.device.cmd_set_scissor(command_buffer, 0, &scissors);
+                }
+
+                record_pass(PassHandle(pass_index), command_buffer)?;
+
+                unsafe {
+                    if pass.render_pass != vk::RenderPass::null() {
+                        self.device.cmd_end_render_pass(command_buffer);
+                    } else {
+                        self.device.cmd_end_rendering(command_buffer);
+                    }
+                }
+            }
+
+            self.record_barriers(command_buffer, image_index, &self.final_barriers);
+
+            Ok(())
+        }
+
+        fn begin_render_pass(
+            &self,
+            command_buffer: vk::CommandBuffer,
+            image_index: usize,
+            pass: &CompiledPass,
+        ) {
+            let render_pass_info = vk::RenderPassBeginInfo::builder()
+                .render_pass(pass.render_pass)
+                .framebuffer(pass.framebuffers[image_index % pass.framebuffers.len()].handle())
+                .render_area(vk::Rect2D {
+                    offset: vk::Offset2D { x: 0, y: 0 },
+                    extent: pass.extent,
+                })
+                .clear_values(&pass.clear_values);
+
+            unsafe {
+                self.device.cmd_begin_render_pass(
+                    command_buffer,
+                    &render_pass_info,
+                    vk::SubpassContents::INLINE,
+                );
+            }
+        }
+
+        fn begin_rendering(
+            &self,
+            command_buffer: vk::CommandBuffer,
+            image_index: usize,
+            pass: &CompiledPass,
+        ) {
+            let image_view = |image: ImageHandle| {
+                let views = &self.images[image.0].views;
+                views[image_index % views.len()]
+            };
+            let attachment_info = |index: usize| {
+                let attachment = &pass.attachments[index];
+
+                vk::RenderingAttachmentInfo::builder()
+                    .image_view(image_view(attachment.image))
+                    .image_layout(attachment.layout)
+                    .load_op(attachment.load_op)
+                    .store_op(attachment.store_op)
+                    .clear_value(pass.clear_values[index])
+            };
+
+            let depth_count = pass.has_depth as usize;
+            let resolve_offset = pass.color_count + depth_count;
+            let color_attachments = (0..pass.color_count)
+                .map(|index| {
+                    let info = attachment_info(index);
+
+                    // Multisampled colors are resolved at the end of rendering
+                    match pass.attachments.get(resolve_offset + index) {
+                        Some(resolve) => info
+                            .resolve_mode(vk::ResolveModeFlags::AVERAGE)
+                            .resolve_image_view(image_view(resolve.image))
+                            .resolve_image_layout(resolve.layout)
+                            .build(),
+                        None => info.build(),
+                    }
+                })
+                .collect::<Vec<_>>();
+            // Depth follows the colors
+            let depth_attachment = pass
+                .has_depth
+                .then(|| attachment_info(pass.color_count).build());
+
+            let mut rendering_info = vk::RenderingInfo::builder()
+                .render_area(vk::Rect2D {
+                    offset: vk::Offset2D { x: 0, y: 0 },
+                    extent: pass.extent,
+                })
+                .layer_count(1)
+                .color_attachments(&color_attachments);
+            if let Some(depth_attachment) = depth_attachment.as_ref() {
+                rendering_info = rendering_info.depth_attachment(depth_attachment);
+
+                let format = self.images[pass.attachments[pass.color_count].image.0].format;
+                if aspect_mask(format).contains(vk::ImageAspectFlags::STENCIL) {
+                    rendering_info = rendering_info.stencil_attachment(depth_attachment);
+                }
+            }
+
+            unsafe {
+                self.device
+                    .cmd_begin_rendering(command_buffer, &rendering_info);
+            }
+        }
+
+        fn record_barriers(
+            &self,
+            command_buffer: vk::CommandBuffer,
+            image_index: usize,
+            barriers: &Barriers,
+        ) {
+            if barriers.images.is_empty() && barriers.buffers.is_empty() {
+                return;
+            }
+
+            let image_barriers = barriers
+                .images
+                .iter()
+                .map(|barrier| {
+                    let resource = &self.images[barrier.image.0];
+
+                    vk::ImageMemoryBarrier::builder()
+                        .old_layout(barrier.old_layout)
+                        .new_layout(barrier.new_layout)
+                        .src_queue_family_index(vk::QUEUE_FAMILY_IGNORED)
+                        .dst_queue_family_index(vk::QUEUE_FAMILY_IGNORED)
+                        .image(resource.images[image_index % resource.images.len()])
+                        .subresource_range(vk::ImageSubresourceRange {
+                            aspect_mask: aspect_mask(resource.format),
+                            base_mip_level: 0,
+                            level_count: vk::REMAINING_MIP_LEVELS,
+                            base_array_layer: 0,
+                            layer_count: vk::REMAINING_ARRAY_LAYERS,
+                        })
+                        .src_access_mask(barrier.src_access)
+                        .dst_access_mask(barrier.dst_access)
+                        .build()
+                })
+                .collect::<Vec<_>>();
+            let buffer_barriers = barriers
+                .buffers
+                .iter()
+                .map(|barrier| {
+                    vk::BufferMemoryBarrier::builder()
+                        .src_queue_family_index(vk::QUEUE_FAMILY_IGNORED)
+                        .dst_queue_family_index(vk::QUEUE_FAMILY_IGNORED)
+                        .buffer(self.buffers[barrier.buffer.0])
+                        .offset(0)
+                        .size(vk::WHOLE_SIZE)
+                        .src_access_mask(barrier.src_access)
+                        .dst_access_mask(barrier.dst_access)
+                        .build()
+                })
+                .collect::<Vec<_>>();
+
+            unsafe {
+                self.device.cmd_pipeline_barrier(
+                    command_buffer,
+                    barriers.src_stage,
+                    barriers.dst_stage,
+                    vk::DependencyFlags::empty(),
+                    &[],
+                    &buffer_barriers,
+                    &image_barriers,
+                );
+            }
+        }
+    }
+
+    impl Drop for RenderGraph {
+        fn drop(&mut self) {
+            for pass in self.passes.iter_mut() {
+                // Framebuffers go before the render pass they were made for
+                pass.framebuffers.clear();
+
+                if pass.render_pass != vk::RenderPass::null() {
+                    unsafe {
+                        self.device.destroy_render_pass(pass.render_pass, None);
+                    }
+                }
+            }
+
+            // Views go before their images
+            for (image, image_view) in self.transient_images.drain(..) {
+                drop(image_view);
+                drop(image);
+            }
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        const CLEAR: AttachmentLoad = AttachmentLoad::Clear(vk::ClearValue {
+            color: vk::ClearColorValue {
+                float32: [0.0, 0.0, 0.0, 1.0],
+            },
+        });
+
+        fn desc(format: vk::Format, samples: vk::SampleCountFlags) -> ImageDesc {
+            ImageDesc {
+                format,
+                extent: vk::Extent2D {
+                    width: 800,
+                    height: 600,
+                },
+                samples,
+            }
+        }
+
+        // Stands in for a swap chain image, nothing here looks at the handles
+        fn import_target(graph: &mut RenderGraphBuilder) -> ImageHandle {
+            graph.import_image(
+                "target",
+                desc(vk::Format::B8G8R8A8_SRGB, vk::SampleCountFlags::TYPE_1),
+                &[vk::Image::null()],
+                &[vk::ImageView::null()],
+                vk::ImageLayout::UNDEFINED,
+                vk::ImageLayout::PRESENT_SRC_KHR,
+            )
+        }
+
+        fn is_rejected(graph: &RenderGraphBuilder) -> bool {
+            matches!(
+                graph.validate(),
+                Err(vk_utils::Error::InvalidRenderGraph(_))
+            )
+        }
+
+        #[test]
+        fn sampling_a_rendered_image_waits_for_the_color_writes() {
+            for dynamic_rendering in [false, true] {
+                let mut graph = RenderGraphBuilder::new();
+                graph.dynamic_rendering(dynamic_rendering);
+                let target = import_target(&mut graph);
+                let offscreen = graph.create_image(
+                    "offscreen",
+                    desc(vk::Format::R8G8B8A8_UNORM, vk::SampleCountFlags::TYPE_1),
+                );
+                graph.add_pass(Pass::new("offscreen").color_attachment(offscreen, CLEAR));
+                graph.add_pass(
+                    Pass::new("composite")
+                        .color_attachment(target, AttachmentLoad::DontCare)
+                        .image(offscreen, ImageUsage::Sampled),
+                );
+                assert!(graph.validate().is_ok());
+
+                let plan = graph.plan();
+                let barriers = &plan.passes[1].0.barriers;
+                let barrier = &barriers.images[0];
+
+                assert_eq!(
+                    barriers.src_stage,
+                    vk::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT
+                );
+                assert!(barriers
+                    .dst_stage
+                    .contains(vk::PipelineStageFlags::FRAGMENT_SHADER));
+                assert_eq!(barrier.image, offscreen);
+                assert_eq!(
+                    barrier.old_layout,
+                    vk::ImageLayout::COLOR_ATTACHMENT_OPTIMAL
+                );
+                assert_eq!(
+                    barrier.new_layout,
+                    vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL
+                );
+                assert_eq!(barrier.src_access, vk::AccessFlags::COLOR_ATTACHMENT_WRITE);
+                assert_eq!(barrier.dst_access, vk::AccessFlags::SHADER_READ);
+
+                // Read later, so it's stored and can't live in tile memory only
+                assert_eq!(
+                    plan.passes[0].0.attachments[0].store_op,
+                    vk::AttachmentStoreOp::STORE
+                );
+                assert_eq!(
+                    plan.transient_usages[offscreen.0],
+                    Some(vk::ImageUsageFlags::COLOR_ATTACHMENT | vk::ImageUsageFlags::SAMPLED)
+                );
+                assert_eq!(plan.transient_usages[target.0], None);
+            }
+        }
+
+        #[test]
+        fn multisampled_attachments_are_transient() {
+            let samples = vk::SampleCountFlags::TYPE_4;
+            let mut graph = RenderGraphBuilder::new();
+            let target = import_target(&mut graph);
+            let color = graph.create_image("color", desc(vk::Format::B8G8R8A8_SRGB, samples));
+            let depth = graph.create_image("depth", desc(vk::Format::D32_SFLOAT, samples));
+            graph.add_pass(
+                Pass::new("scene")
+                    .color_attachment(color, CLEAR)
+                    .depth_attachment(depth, CLEAR)
+                    .resolve_attachment(target),
+            );
+            assert!(graph.validate().is_ok());
+
+            let plan = graph.plan();
+            let (pass, render_pass) = &plan.passes[0];
+            let store_ops = pass
+                .attachments
+                .iter()
+                .map(|attachment| attachment.store_op)
+                .collect::<Vec<_>>();
+
+            assert_eq!(
+                store_ops,
+                vec![
+                    vk::AttachmentStoreOp::DONT_CARE,
+                    vk::AttachmentStoreOp::DONT_CARE,
+                    vk::AttachmentStoreOp::STORE
+                ]
+            );
+            assert_eq!(
+                plan.transient_usages,
+                vec![
+                    None,
+                    Some(
+                        vk::ImageUsageFlags::COLOR_ATTACHMENT
+                            | vk::ImageUsageFlags::TRANSIENT_ATTACHMENT
+                    ),
+                    Some(
+                        vk::ImageUsageFlags::DEPTH_STENCIL_ATTACHMENT
+                            | vk::ImageUsageFlags::TRANSIENT_ATTACHMENT
+                    ),
+                ]
+            );
+
+            // The render pass hands the resolved image over for presenting
+            let descriptions = &render_pass.as_ref().unwrap().descriptions;
+            assert_eq!(descriptions[0].samples, samples);
+            assert_eq!(
+                descriptions[2].final_layout,
+                vk::ImageLayout::PRESENT_SRC_KHR
+            );
+            assert!(plan.final_barriers.images.is_empty());
+        }
+
+        #[test]
+        fn undeclared_resources_are_rejected() {
+            let mut other = RenderGraphBuilder::new();
+            other.create_image(
+                "first",
+                desc(vk::Format::R8G8B8A8_UNORM, vk::SampleCountFlags::TYPE_1),
+            );
+            let foreign = other.create_image(
+                "second",
+                desc(vk::Format::R8G8B8A8_UNORM, vk::SampleCountFlags::TYPE_1),
+            );
+
+            let mut graph = RenderGraphBuilder::new();
+            let target = import_target(&mut graph);
+            graph.add_pass(
+                Pass::new("composite")
+                    .color_attachment(target, CLEAR)
+                    .image(foreign, ImageUsage::Sampled),
+            );
+            assert!(is_rejected(&graph));
+
+            let mut graph = RenderGraphBuilder::new();
+            let foreign_buffer = other.import_buffer(vk::Buffer::null());
+            graph.add_pass(Pass::new("compute").buffer(foreign_buffer, BufferUsage::StorageWrite));
+            assert!(is_rejected(&graph));
+        }
+
+        #[test]
+        fn images_written_twice_in_a_pass_are_rejected() {
+            let graph_with_pass = |pass: fn(ImageHandle, ImageHandle) -> Pass| {
+                let mut graph = RenderGraphBuilder::new();
+                let target = import_target(&mut graph);
+                let image = graph.create_image(
+                    "image",
+                    desc(vk::Format::R8G8B8A8_UNORM, vk::SampleCountFlags::TYPE_1),
+                );
+                graph.add_pass(pass(target, image));
+
+                graph
+            };
+
+            assert!(is_rejected(&graph_with_pass(|_, image| {
+                Pass::new("both")
+                    .color_attachment(image, CLEAR)
+                    .image(image, ImageUsage::StorageWrite)
+            })));
+            assert!(is_rejected(&graph_with_pass(|target, _| {
+                Pass::new("twice")
+                    .color_attachment(target, CLEAR)
+                    .color_attachment(target, CLEAR)
+            })));
+            assert!(is_rejected(&graph_with_pass(|target, image| {
+                Pass::new("read while written")
+                    .color_attachment(target, CLEAR)
+                    .image(image, ImageUsage::Sampled)
+                    .image(image, ImageUsage::StorageWrite)
+            })));
+
+            // Reading the same image twice is fine
+            assert!(graph_with_pass(|target, image| {
+                Pass::new("reads")
+                    .color_attachment(target, CLEAR)
+                    .image(image, ImageUsage::Sampled)
+                    .image(image, ImageUsage::Sampled)
+            })
+            .validate()
+            .is_ok());
+        }
+    }
+}
+
+pub use _render_graph::{
+    AttachmentLoad, BufferHandle, BufferUsage, ImageDesc, ImageHandle, ImageUsage, Pass,
+    PassHandle, RenderGraph, RenderGraphBuilder,
+};
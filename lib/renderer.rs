@@ -0,0 +1,1036 @@
+mod _renderer {
+    use crate::{
+        self as vk_utils,
+        constants::VK_VALIDATION_LAYER_NAMES,
+        frame::{FrameContext, ImagesInFlight},
+        image::ImageView,
+        render_graph::{
+            AttachmentLoad, ImageDesc, ImageHandle, Pass, PassHandle, RenderGraph,
+            RenderGraphBuilder,
+        },
+        tools::debug as vk_debug,
+        QueueFamilyIndices, VkOffscreenInfo, VkSurfaceInfo, VkSwapChainInfo,
+    };
+
+    use ash::{extensions::ext::DebugUtils, vk, Device, Entry, Instance};
+    use std::mem::ManuallyDrop;
+    use winit::window::Window;
+
+    // Everything a scene needs to create its resources and pipeline
+    pub struct RenderContext<'a> {
+        pub instance: &'a Instance,
+        pub device: &'a Device,
+        pub physical_device: vk::PhysicalDevice,
+        pub physical_device_memory_properties: &'a vk::PhysicalDeviceMemoryProperties,
+        pub allocator: &'a vk_utils::memory::Allocator,
+        pub command_pool: vk::CommandPool,
+        pub graphics_queue: vk::Queue,
+        pub graphics_family: u32,
+        // Async compute queue when the device has one, otherwise the graphics queue.
+        // One-off dispatches (compute::dispatch_and_wait) go through its own pool.
+        // Storage buffers shared with the graphics family need no ownership transfer
+        pub compute_command_pool: vk::CommandPool,
+        pub compute_queue: vk::Queue,
+        pub compute_family: u32,
+        // Batched staging copies on the transfer queue, submitted at the latest with the next frame.
+        // The results belong to the graphics queue family
+        pub uploads: &'a vk_utils::upload::UploadManager,
+        // Shared by every pipeline the scene creates
+        pub pipeline_cache: vk::PipelineCache,
+        pub msaa_samples: vk::SampleCountFlags,
+        // Null with dynamic rendering, pipelines are built for the attachment formats instead
+        pub render_pass: vk::RenderPass,
+        pub color_format: vk::Format,
+        pub depth_format: vk::Format,
+        pub extent: vk::Extent2D,
+        // Frames recorded ahead of the GPU, each with its own copy of the frame data
+        pub frame_count: usize,
+    }
+
+    pub trait Scene {
+        // Whatever the scene writes while previous frames may still be reading it
+        // (uniform buffers, descriptor sets pointing at them)
+        type FrameData;
+
+        fn application_name(&self) -> &str;
+        fn application_version(&self) -> u32;
+
+        // Geometry, textures, uniform buffers and descriptors (created once)
+        fn create_resources(&mut self, context: &RenderContext) -> vk_utils::Result<()>;
+        // Once per frame in flight, after create_resources. Dropped before destroy_resources
+        fn create_frame_data(
+            &mut self,
+            context: &RenderContext,
+        ) -> vk_utils::Result<Self::FrameData>;
+        // Graphics pipeline (recreated along with the swap chain and when shaders change,
+        // replacing the previous one only on success)
+        fn create_pipeline(&mut self, context: &RenderContext) -> vk_utils::Result<()>;
+        // GLSL sources the pipeline is built from, watched for hot reload
+        fn shader_sources(&self) -> Vec<std::path::PathBuf> {
+            Vec::new()
+        }
+
+        // Called every frame before update, e.g. to upload assets that finished loading
+        fn poll_assets(&mut self, _context: &RenderContext) -> vk_utils::Result<()> {
+            Ok(())
+        }
+        // The frame's previous submission is done by the time this is called.
+        // `frame_index` (0..frame_count) is the frame in flight `frame` belongs to
+        fn update(
+            &mut self,
+            device: &Device,
+            frame: &mut Self::FrameData,
+            frame_index: usize,
+            delta_time: f32,
+        ) -> vk_utils::Result<()>;
+        // Called every frame, before recording
+        fn clear_color(&self) -> [f32; 4] {
+            [0.0, 0.0, 0.0, 1.0]
+        }
+        // Called every frame inside the render pass with viewport and scissor already set
+        fn record_commands(
+            &self,
+            device: &Device,
+            command_buffer: vk::CommandBuffer,
+            frame: &Self::FrameData,
+        ) -> vk_utils::Result<()>;
+
+        // Anything still held after these calls outlives the device
+        fn destroy_pipeline(&mut self, device: &Device);
+        fn destroy_resources(&mut self, device: &Device);
+    }
+
+    // Render graph of the current render target
+    struct FrameGraph {
+        graph: RenderGraph,
+        // Pass the scene draws in and the color attachment it clears
+        scene_pass: PassHandle,
+        scene_color: ImageHandle,
+    }
+
+    pub struct Renderer<S: Scene> {
+        // Dropped by hand before the device, see Drop
+        scene: ManuallyDrop<S>,
+
+        _entry: Entry,
+        instance: Instance,
+
+        debug_utils_loader: DebugUtils,
+        debug_callback: vk::DebugUtilsMessengerEXT,
+
+        // None when rendering offscreen
+        surface_info: Option<VkSurfaceInfo>,
+
+        physical_device: vk::PhysicalDevice,
+        physical_device_memory_properties: vk::PhysicalDeviceMemoryProperties,
+        device: Device,
+        allocator: ManuallyDrop<vk_utils::memory::Allocator>,
+        // Only written to disk by save_pipeline_cache
+        pipeline_cache: Option<vk_utils::pipeline::PipelineCache>,
+
+        msaa_samples: vk::SampleCountFlags,
+        dynamic_rendering: bool,
+
+        queue_family: QueueFamilyIndices,
+        graphics_queue: vk::Queue,
+        present_queue: vk::Queue,
+        compute_queue: vk::Queue,
+
+        // Render target: either the swap chain or an offscreen image
+        swapchain_info: Option<VkSwapChainInfo>,
+        swapchain_imageviews: Vec<ImageView>,
+        offscreen_info: Option<VkOffscreenInfo>,
+        format: vk::Format,
+        depth_format: vk::Format,
+        extent: vk::Extent2D,
+
+        frame_graph: Option<FrameGraph>,
+
+        // For one-off commands, frames record from their own pools
+        command_pool: vk::CommandPool,
+        compute_command_pool: vk::CommandPool,
+        // Dropped before the device, like the pipeline cache
+        uploads: Option<vk_utils::upload::UploadManager>,
+
+        frame_count: usize,
+        frames: Vec<FrameContext<S::FrameData>>,
+        images_in_flight: ImagesInFlight,
+        current_frame: usize,
+
+        is_framebuffer_resized: bool,
+
+        // Only set while shaders are hot reloaded
+        shader_watcher: Option<vk_utils::shader::ShaderWatcher>,
+    }
+
+    // Requests the device can't honour are scaled back when the renderer is built
+    #[derive(Clone, Copy)]
+    pub struct RendererOptions {
+        // None picks the highest sample count the device supports
+        pub msaa_samples: Option<vk::SampleCountFlags>,
+        pub dynamic_rendering: bool,
+        pub frames_in_flight: usize,
+    }
+
+    impl Default for RendererOptions {
+        fn default() -> Self {
+            Self {
+                msaa_samples: None,
+                dynamic_rendering: false,
+                frames_in_flight: vk_utils::constants::DEFAULT_FRAMES_IN_FLIGHT,
+            }
+        }
+    }
+
+    // Destroys an object made while building the renderer, unless it gets released into it.
+    // Guards are dropped in reverse order, so dependent objects go first
+    struct Guard<T> {
+        value: Option<T>,
+        destroy: fn(&T),
+    }
+
+    impl<T> Guard<T> {
+        fn new(value: T, destroy: fn(&T)) -> Self {
+            Self {
+                value: Some(value),
+                destroy,
+            }
+        }
+
+        fn release(mut self) -> T {
+            self.value.take().unwrap()
+        }
+    }
+
+    impl<T> std::ops::Deref for Guard<T> {
+        type Target = T;
+
+        fn deref(&self) -> &T {
+            self.value.as_ref().unwrap()
+        }
+    }
+
+    impl<T> Drop for Guard<T> {
+        fn drop(&mut self) {
+            if let Some(value) = self.value.as_ref() {
+                (self.destroy)(value);
+            }
+        }
+    }
+
+    fn guard_instance(instance: Instance) -> Guard<Instance> {
+        Guard::new(instance, |instance| unsafe {
+            instance.destroy_instance(None);
+        })
+    }
+
+    fn guard_command_pool(
+        device: &Device,
+        command_pool: vk::CommandPool,
+    ) -> Guard<(Device, vk::CommandPool)> {
+        Guard::new(
+            (device.clone(), command_pool),
+            |(device, command_pool)| unsafe {
+                device.destroy_command_pool(*command_pool, None);
+            },
+        )
+    }
+
+    impl<S: Scene> Renderer<S> {
+        pub fn new(window: &Window, scene: S, options: RendererOptions) -> vk_utils::Result<Self> {
+            use raw_window_handle::HasRawDisplayHandle;
+
+            let entry = Entry::linked();
+            let instance = guard_instance(vk_utils::instance::create_instance(
+                &entry,
+                scene.application_name(),
+                scene.application_version(),
+                Some(window.raw_display_handle()),
+            )?);
+            let surface_info = Guard::new(
+                vk_utils::surface::create_surface(&entry, &instance, window)?,
+                |surface_info: &VkSurfaceInfo| unsafe {
+                    surface_info
+                        .surface_loader
+                        .destroy_surface(surface_info.surface, None);
+                },
+            );
+
+            Self::build(entry, instance, Some(surface_info), None, scene, options)
+        }
+
+        pub fn new_headless(
+            extent: vk::Extent2D,
+            format: vk::Format,
+            scene: S,
+            options: RendererOptions,
+        ) -> vk_utils::Result<Self> {
+            let entry = Entry::linked();
+            let instance = guard_instance(vk_utils::instance::create_instance(
+                &entry,
+                scene.application_name(),
+                scene.application_version(),
+                None,
+            )?);
+
+            Self::build(
+                entry,
+                instance,
+                None,
+                Some((extent, format)),
+                scene,
+                options,
+            )
+        }
+
+        // Whatever was created is destroyed again when a step fails, the window
+        // can be used for another attempt
+        fn build(
+            entry: Entry,
+            instance: Guard<Instance>,
+            surface_info: Option<Guard<VkSurfaceInfo>>,
+            offscreen_target: Option<(vk::Extent2D, vk::Format)>,
+            scene: S,
+            options: RendererOptions,
+        ) -> vk_utils::Result<Self> {
+            let RendererOptions {
+                msaa_samples,
+                dynamic_rendering,
+                frames_in_flight,
+            } = options;
+            let debug_messenger = Guard::new(
+                vk_debug::setup_debug_callback(&entry, &instance)?,
+                |(debug_utils_loader, debug_callback)| {
+                    if VK_VALIDATION_LAYER_NAMES.is_enable {
+                        unsafe {
+                            debug_utils_loader.destroy_debug_utils_messenger(*debug_callback, None);
+                        }
+                    }
+                },
+            );
+
+            let physical_device =
+                vk_utils::device::pick_physical_device(&instance, surface_info.as_deref())?;
+            // Requested sample counts the device can't do fall back to the highest usable one
+            let max_usable_samples =
+                vk_utils::device::get_max_usable_sample_count(&instance, physical_device);
+            let msaa_samples = match msaa_samples {
+                Some(msaa_samples) if msaa_samples.as_raw() <= max_usable_samples.as_raw() => {
+                    msaa_samples
+                }
+                _ => max_usable_samples,
+            };
+            // Render pass objects are the fallback when dynamic rendering isn't supported
+            let dynamic_rendering = dynamic_rendering
+                && vk_utils::device::supports_dynamic_rendering(&entry, &instance, physical_device);
+            let physical_device_memory_properties =
+                unsafe { instance.get_physical_device_memory_properties(physical_device) };
+            let (device, family_indices) = vk_utils::device::create_logical_device(
+                &instance,
+                physical_device,
+                surface_info.as_deref(),
+                dynamic_rendering,
+            )?;
+            let device = Guard::new(device, |device| unsafe {
+                device.destroy_device(None);
+            });
+
+            let graphics_queue =
+                unsafe { device.get_device_queue(family_indices.graphics_family.unwrap(), 0) };
+            let present_queue = match family_indices.present_family {
+                Some(present_family) => unsafe { device.get_device_queue(present_family, 0) },
+                None => graphics_queue,
+            };
+            let compute_family = family_indices
+                .compute_family
+                .unwrap_or(family_indices.graphics_family.unwrap());
+            let compute_queue = unsafe { device.get_device_queue(compute_family, 0) };
+            let transfer_queue = unsafe {
+                device.get_device_queue(
+                    family_indices
+                        .transfer_family
+                        .unwrap_or(family_indices.graphics_family.unwrap()),
+                    0,
+                )
+            };
+
+            let allocator = vk_utils::memory::Allocator::new(&instance, &device, physical_device);
+            let pipeline_cache = vk_utils::pipeline::PipelineCache::new(
+                &instance,
+                &device,
+                physical_device,
+                std::path::Path::new(vk_utils::constants::pipeline::CACHE_PATH),
+            )?;
+            let command_pool = guard_command_pool(
+                &device,
+                vk_utils::command::create_command_pool(&device, &family_indices)?,
+            );
+            let compute_command_pool = guard_command_pool(
+                &device,
+                vk_utils::command::create_command_pool_for_family(&device, compute_family)?,
+            );
+            let uploads = vk_utils::upload::UploadManager::new(
+                &device,
+                &family_indices,
+                transfer_queue,
+                graphics_queue,
+            )?;
+
+            let offscreen_info = match offscreen_target {
+                Some((extent, format)) => Some(vk_utils::offscreen::create_offscreen_target(
+                    &device, &allocator, extent, format,
+                )?),
+                None => None,
+            };
+
+            // Nothing failed, the renderer owns everything from here on
+            let (_, command_pool) = command_pool.release();
+            let (_, compute_command_pool) = compute_command_pool.release();
+            let device = device.release();
+            let (debug_utils_loader, debug_callback) = debug_messenger.release();
+            let surface_info = surface_info.map(Guard::release);
+            let instance = instance.release();
+
+            let mut renderer = Self {
+                scene: ManuallyDrop::new(scene),
+
+                _entry: entry,
+                instance,
+
+                debug_utils_loader,
+                debug_callback,
+
+                surface_info,
+
+                physical_device,
+                physical_device_memory_properties,
+                device,
+                allocator: ManuallyDrop::new(allocator),
+                pipeline_cache: Some(pipeline_cache),
+
+                msaa_samples,
+                dynamic_rendering,
+
+                queue_family: family_indices,
+                graphics_queue,
+                present_queue,
+                compute_queue,
+
+                swapchain_info: None,
+                swapchain_imageviews: Vec::new(),
+                offscreen_info,
+                format: vk::Format::UNDEFINED,
+                depth_format: vk::Format::UNDEFINED,
+                extent: vk::Extent2D::default(),
+
+                frame_graph: None,
+
+                command_pool,
+                compute_command_pool,
+                uploads: Some(uploads),
+
+                // At least one, or there would be nothing to record into
+                frame_count: frames_in_flight.max(1),
+                frames: Vec::new(),
+                images_in_flight: ImagesInFlight::new(0),
+                current_frame: 0,
+
+                is_framebuffer_resized: false,
+
+                shader_watcher: None,
+            };
+
+            // From here on, Drop cleans up whatever was created if a step fails
+            renderer.create_render_target()?;
+            renderer.with_scene_context(|scene, context| {
+                scene.create_resources(context)?;
+                scene.create_pipeline(context)
+            })?;
+
+            let queue_family_index = renderer.queue_family.graphics_family.unwrap();
+            for _ in 0..renderer.frame_count {
+                let data = renderer
+                    .with_scene_context(|scene, context| scene.create_frame_data(context))?;
+                let frame = FrameContext::new(&renderer.device, queue_family_index, data)?;
+
+                renderer.frames.push(frame);
+            }
+
+            Ok(renderer)
+        }
+
+        fn with_scene_context<F, R>(&mut self, f: F) -> vk_utils::Result<R>
+        where
+            F: FnOnce(&mut S, &RenderContext) -> vk_utils::Result<R>,
+        {
+            let context = RenderContext {
+                instance: &self.instance,
+                device: &self.device,
+                physical_device: self.physical_device,
+                physical_device_memory_properties: &self.physical_device_memory_properties,
+                allocator: &self.allocator,
+                command_pool: self.command_pool,
+                graphics_queue: self.graphics_queue,
+                graphics_family: self.queue_family.graphics_family.unwrap(),
+                compute_command_pool: self.compute_command_pool,
+                compute_queue: self.compute_queue,
+                compute_family: self
+                    .queue_family
+                    .compute_family
+                    .unwrap_or(self.queue_family.graphics_family.unwrap()),
+                uploads: self.uploads.as_ref().unwrap(),
+                pipeline_cache: self
+                    .pipeline_cache
+                    .as_ref()
+                    .map_or(vk::PipelineCache::null(), |cache| cache.handle()),
+                msaa_samples: self.msaa_samples,
+                render_pass: self
+                    .frame_graph
+                    .as_ref()
+                    .map_or(vk::RenderPass::null(), |frame_graph| {
+                        frame_graph.graph.render_pass(frame_graph.scene_pass)
+                    }),
+                color_format: self.format,
+                depth_format: self.depth_format,
+                extent: self.extent,
+                frame_count: self.frame_count,
+            };
+
+            f(&mut self.scene, &context)
+        }
+
+        fn create_render_target(&mut self) -> vk_utils::Result<()> {
+            if let Some(surface_info) = self.surface_info.as_ref() {
+                let swapchain_info = vk_utils::swapchain::create_swap_chain(
+                    &self.instance,
+                    &self.device,
+                    self.physical_device,
+                    surface_info,
+                    &self.queue_family,
+                )?;
+
+                self.swapchain_imageviews = vk_utils::swapchain::create_image_views(
+                    &self.device,
+                    swapchain_info.swapchain_format,
+                    &swapchain_info.swapchain_images,
+                )?;
+                self.format = swapchain_info.swapchain_format;
+                self.extent = swapchain_info.swapchain_extent;
+                self.swapchain_info = Some(swapchain_info);
+            } else if let Some(offscreen_info) = self.offscreen_info.as_ref() {
+                self.format = offscreen_info.format;
+                self.extent = offscreen_info.extent;
+            }
+
+            // Nothing rendered to the new images yet
+            self.images_in_flight = ImagesInFlight::new(self.image_count());
+            self.depth_format =
+                vk_utils::swapchain::find_depth_format(&self.instance, self.physical_device)?;
+            let (target_images, target_image_views, final_layout) =
+                match self.offscreen_info.as_ref() {
+                    // Resolved image gets copied back to the host instead of being presented
+                    Some(offscreen_info) => (
+                        vec![offscreen_info.image.handle()],
+                        vec![offscreen_info.image_view.handle()],
+                        vk::ImageLayout::TRANSFER_SRC_OPTIMAL,
+                    ),
+                    None => (
+                        self.swapchain_info
+                            .as_ref()
+                            .map_or(Vec::new(), |info| info.swapchain_images.clone()),
+                        self.swapchain_imageviews
+                            .iter()
+                            .map(|image_view| image_view.handle())
+                            .collect(),
+                        vk::ImageLayout::PRESENT_SRC_KHR,
+                    ),
+                };
+
+            let mut graph = RenderGraphBuilder::new();
+            graph.dynamic_rendering(self.dynamic_rendering);
+            let target = graph.import_image(
+                "target",
+                ImageDesc {
+                    format: self.format,
+                    extent: self.extent,
+                    samples: vk::SampleCountFlags::TYPE_1,
+                },
+                &target_images,
+                &target_image_views,
+                vk::ImageLayout::UNDEFINED,
+                final_layout,
+            );
+            let depth = graph.create_image(
+                "depth",
+                ImageDesc {
+                    format: self.depth_format,
+                    extent: self.extent,
+                    samples: self.msaa_samples,
+                },
+            );
+
+            // Clear values (Depth buffering)
+            let clear_color = AttachmentLoad::Clear(vk::ClearValue {
+                color: vk::ClearColorValue {
+                    float32: [0.0, 0.0, 0.0, 1.0],
+                },
+            });
+            let clear_depth = AttachmentLoad::Clear(vk::ClearValue {
+                depth_stencil: vk::ClearDepthStencilValue {
+                    depth: 1.0,
+                    stencil: 0,
+                },
+            });
+
+            let (scene_pass, scene_color) = if self.msaa_samples == vk::SampleCountFlags::TYPE_1 {
+                // Nothing to resolve, so the target is the color attachment itself
+                (
+                    Pass::new("scene").color_attachment(target, clear_color),
+                    target,
+                )
+            } else {
+                let color = graph.create_image(
+                    "color",
+                    ImageDesc {
+                        format: self.format,
+                        extent: self.extent,
+                        samples: self.msaa_samples,
+                    },
+                );
+
+                (
+                    Pass::new("scene")
+                        .color_attachment(color, clear_color)
+                        .resolve_attachment(target),
+                    color,
+                )
+            };
+            let scene_pass = graph.add_pass(scene_pass.depth_attachment(depth, clear_depth));
+
+            self.frame_graph = Some(FrameGraph {
+                graph: graph.build(&self.device, &self.allocator)?,
+                scene_pass,
+                scene_color,
+            });
+
+            Ok(())
+        }
+
+        // Re-recorded every frame, so the scene can change anything between frames
+        fn record_frame(&mut self, image_index: usize) -> vk_utils::Result<vk::CommandBuffer> {
+            let Some(frame_graph) = self.frame_graph.as_mut() else {
+                return Err(vk_utils::Error::InvalidRenderGraph(
+                    "no render target to record".to_string(),
+                ));
+            };
+
+            let [red, green, blue, alpha] = self.scene.clear_color();
+            frame_graph.graph.set_clear_value(
+                frame_graph.scene_pass,
+                frame_graph.scene_color,
+                vk::ClearValue {
+                    color: vk::ClearColorValue {
+                        float32: [red, green, blue, alpha],
+                    },
+                },
+            );
+
+            let frame_graph = &*frame_graph;
+            let scene = &self.scene;
+            let device = &self.device;
+            let frame = &self.frames[self.current_frame];
+
+            frame.command_pool().record(|command_buffer| {
+                frame_graph
+                    .graph
+                    .record(command_buffer, image_index, |pass, command_buffer| {
+                        if pass == frame_graph.scene_pass {
+                            scene.record_commands(device, command_buffer, frame.data())?;
+                        }
+
+                        Ok(())
+                    })
+            })
+        }
+
+        pub fn draw_frame(&mut self, delta_time: f32) -> vk_utils::Result<()> {
+            self.with_scene_context(|scene, context| scene.poll_assets(context))?;
+
+            // Uploads recorded since the last frame are submitted ahead of it
+            if let Some(uploads) = self.uploads.as_ref() {
+                uploads.submit()?;
+                uploads.collect()?;
+            }
+
+            if self.swapchain_info.is_none() {
+                return self.draw_offscreen_frame(delta_time);
+            }
+
+            // Waiting for the previous submission of this frame
+            self.frames[self.current_frame].wait(&self.device)?;
+
+            let (swapchain_loader, swapchain) = match self.swapchain_info.as_ref() {
+                Some(swapchain_info) => (
+                    swapchain_info.swapchain_loader.clone(),
+                    swapchain_info.swapchain,
+                ),
+                None => unreachable!(),
+            };
+
+            // Acquiring an image from the swapchain
+            let (image_index, _is_sub_optimal) = unsafe {
+                // Suboptimal or out-of-date swap chain
+                let result = swapchain_loader.acquire_next_image(
+                    swapchain,
+                    u64::MAX,
+                    self.frames[self.current_frame].image_available(),
+                    vk::Fence::null(),
+                );
+                match result {
+                    Ok(image_index) => image_index,
+                    Err(vk_result) => match vk_result {
+                        vk::Result::ERROR_OUT_OF_DATE_KHR => {
+                            return self.recreate_swapchain();
+                        }
+                        _ => {
+                            return Err(vk_utils::Error::Vulkan(
+                                "failed to acquire swap chain image!",
+                                vk_result,
+                            ))
+                        }
+                    },
+                }
+            };
+
+            // The image may still be rendered to by another frame in flight
+            let in_flight = self.frames[self.current_frame].in_flight();
+            self.images_in_flight
+                .acquire(&self.device, image_index as usize, in_flight)?;
+
+            // Updating uniform data
+            let frame = &mut self.frames[self.current_frame];
+            self.scene.update(
+                &self.device,
+                frame.data_mut(),
+                self.current_frame,
+                delta_time,
+            )?;
+
+            // The fence above guarantees this frame's pool isn't in use anymore
+            let command_buffers = [self.record_frame(image_index as usize)?];
+
+            // Submitting the command buffer
+            let frame = &self.frames[self.current_frame];
+            let wait_semaphores = [frame.image_available()];
+            let wait_stages = [vk::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT];
+            let signal_semaphores = [frame.render_finished()];
+
+            let submit_infos = [vk::SubmitInfo::builder()
+                .wait_semaphores(&wait_semaphores)
+                .wait_dst_stage_mask(&wait_stages)
+                .command_buffers(&command_buffers)
+                .signal_semaphores(&signal_semaphores)
+                .build()];
+
+            frame.reset(&self.device)?;
+            unsafe {
+                self.device
+                    .queue_submit(self.graphics_queue, &submit_infos, frame.in_flight())
+                    .map_err(vk_utils::Error::vulkan(
+                        "failed to submit draw command buffer!",
+                    ))?;
+            }
+
+            // Presentation
+            let swapchains = [swapchain];
+            let image_indices = [image_index];
+            let present_info = vk::PresentInfoKHR::builder()
+                .wait_semaphores(&signal_semaphores)
+                .swapchains(&swapchains)
+                .image_indices(&image_indices);
+
+            // Handling resizes explicitly
+            let result =
+                unsafe { swapchain_loader.queue_present(self.present_queue, &present_info) };
+
+            let is_resized = match result {
+                Ok(_) => self.is_framebuffer_resized,
+                Err(vk_result) => match vk_result {
+                    vk::Result::ERROR_OUT_OF_DATE_KHR | vk::Result::SUBOPTIMAL_KHR => true,
+                    _ => {
+                        return Err(vk_utils::Error::Vulkan(
+                            "failed to present swap chain image!",
+                            vk_result,
+                        ))
+                    }
+                },
+            };
+
+            self.current_frame = (self.current_frame + 1) % self.frame_count;
+
+            if is_resized {
+                self.is_framebuffer_resized = false;
+                self.recreate_swapchain()?;
+            }
+
+            Ok(())
+        }
+
+        fn draw_offscreen_frame(&mut self, delta_time: f32) -> vk_utils::Result<()> {
+            let frame = &mut self.frames[self.current_frame];
+            frame.wait(&self.device)?;
+
+            self.scene.update(
+                &self.device,
+                frame.data_mut(),
+                self.current_frame,
+                delta_time,
+            )?;
+
+            let command_buffers = [self.record_frame(0)?];
+            let submit_infos = [vk::SubmitInfo::builder()
+                .command_buffers(&command_buffers)
+                .build()];
+
+            let frame = &self.frames[self.current_frame];
+            frame.reset(&self.device)?;
+            unsafe {
+                self.device
+                    .queue_submit(self.graphics_queue, &submit_infos, frame.in_flight())
+                    .map_err(vk_utils::Error::vulkan(
+                        "failed to submit draw command buffer!",
+                    ))?;
+            }
+
+            // Nothing is presented, so wait for the frame before it gets read back
+            frame.wait(&self.device)?;
+
+            self.current_frame = (self.current_frame + 1) % self.frame_count;
+
+            Ok(())
+        }
+
+        pub fn read_frame(&self) -> vk_utils::Result<Vec<u8>> {
+            match self.offscreen_info.as_ref() {
+                Some(offscreen_info) => vk_utils::offscreen::read_offscreen_image(
+                    &self.device,
+                    self.command_pool,
+                    self.graphics_queue,
+                    offscreen_info,
+                    &self.allocator,
+                ),
+                None => Err(vk_utils::Error::NoOffscreenTarget),
+            }
+        }
+
+        pub fn save_frame(&self, image_path: &std::path::Path) -> vk_utils::Result<()> {
+            match self.offscreen_info.as_ref() {
+                Some(offscreen_info) => vk_utils::offscreen::save_offscreen_image(
+                    &self.device,
+                    self.command_pool,
+                    self.graphics_queue,
+                    offscreen_info,
+                    &self.allocator,
+                    image_path,
+                ),
+                None => Err(vk_utils::Error::NoOffscreenTarget),
+            }
+        }
+
+        pub fn scene(&self) -> &S {
+            &self.scene
+        }
+
+        pub fn scene_mut(&mut self) -> &mut S {
+            &mut self.scene
+        }
+
+        pub fn extent(&self) -> vk::Extent2D {
+            self.extent
+        }
+
+        pub fn allocator(&self) -> &vk_utils::memory::Allocator {
+            &self.allocator
+        }
+
+        pub fn frame_count(&self) -> usize {
+            self.frame_count
+        }
+
+        // False when it was requested but the device doesn't support it
+        pub fn uses_dynamic_rendering(&self) -> bool {
+            self.dynamic_rendering
+        }
+
+        pub fn wait_for_device_idle(&self) -> vk_utils::Result<()> {
+            unsafe {
+                self.device
+                    .device_wait_idle()
+                    .map_err(vk_utils::Error::vulkan("failed to wait device idle!"))
+            }
+        }
+
+        // Pipelines built so far get reused by the next run
+        pub fn save_pipeline_cache(&self) -> vk_utils::Result<()> {
+            match self.pipeline_cache.as_ref() {
+                Some(pipeline_cache) => pipeline_cache.save(),
+                None => Ok(()),
+            }
+        }
+
+        // The new window size, in pixels
+        pub fn resize_framebuffer(&mut self, width: u32, height: u32) {
+            if let Some(surface_info) = self.surface_info.as_mut() {
+                surface_info.screen_width = width;
+                surface_info.screen_height = height;
+            }
+            self.is_framebuffer_resized = true;
+        }
+
+        // Rebuilds the scene pipeline whenever one of its shaders changes in `directory`
+        pub fn watch_shaders(&mut self, directory: &std::path::Path) -> vk_utils::Result<()> {
+            self.shader_watcher = Some(vk_utils::shader::ShaderWatcher::new(directory)?);
+
+            Ok(())
+        }
+
+        // Compile errors leave the previous pipeline in place, the scene keeps rendering with it
+        pub fn reload_changed_shaders(&mut self) -> vk_utils::Result<()> {
+            let Some(shader_watcher) = self.shader_watcher.as_mut() else {
+                return Ok(());
+            };
+            let changed = shader_watcher.poll()?;
+            let sources = self.scene.shader_sources();
+            let canonical = |path: &std::path::Path| {
+                std::fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf())
+            };
+
+            if !changed.iter().any(|path| {
+                sources
+                    .iter()
+                    .any(|source| canonical(source) == canonical(path))
+            }) {
+                return Ok(());
+            }
+
+            // Compiling up front avoids idling the device for shaders that don't build
+            for source in sources.iter() {
+                vk_utils::shader::load_shader(source)?;
+            }
+
+            self.wait_for_device_idle()?;
+
+            // The next frame gets recorded with whichever pipeline the scene holds
+            self.with_scene_context(|scene, context| scene.create_pipeline(context))
+        }
+
+        // Swap chain images, or the single offscreen image
+        fn image_count(&self) -> usize {
+            self.swapchain_info
+                .as_ref()
+                .map_or(1, |swapchain_info| swapchain_info.swapchain_images.len())
+        }
+
+        fn cleanup_render_target(&mut self) {
+            self.frame_graph = None;
+            self.swapchain_imageviews.clear();
+
+            unsafe {
+                if let Some(swapchain_info) = self.swapchain_info.take() {
+                    swapchain_info
+                        .swapchain_loader
+                        .destroy_swapchain(swapchain_info.swapchain, None);
+                }
+            }
+        }
+
+        fn recreate_swapchain(&mut self) -> vk_utils::Result<()> {
+            // Recreating the swap chain
+            self.wait_for_device_idle()?;
+
+            // Without render pass objects the pipeline only depends on the formats
+            let format = self.format;
+            let keep_pipeline = self.dynamic_rendering;
+            if !keep_pipeline {
+                self.scene.destroy_pipeline(&self.device);
+            }
+            self.cleanup_render_target();
+
+            self.create_render_target()?;
+            if !keep_pipeline || self.format != format {
+                self.with_scene_context(|scene, context| scene.create_pipeline(context))?;
+            }
+
+            Ok(())
+        }
+    }
+
+    impl<S: Scene> Drop for Renderer<S> {
+        fn drop(&mut self) {
+            // Nothing left to do about errors while tearing down
+            let _ = self.wait_for_device_idle();
+
+            // Owned objects have to go before the device does
+            self.frames.clear();
+            self.uploads = None;
+
+            self.scene.destroy_pipeline(&self.device);
+            self.cleanup_render_target();
+            self.scene.destroy_resources(&self.device);
+            self.offscreen_info = None;
+            self.allocator.free_empty_blocks();
+
+            self.pipeline_cache = None;
+
+            unsafe {
+                self.device.destroy_command_pool(self.command_pool, None);
+                self.device
+                    .destroy_command_pool(self.compute_command_pool, None);
+
+                // Whatever the scene still holds and the memory blocks are freed
+                // through the device, so they can't wait for the fields to drop
+                ManuallyDrop::drop(&mut self.scene);
+                ManuallyDrop::drop(&mut self.allocator);
+
+                self.device.destroy_device(None);
+
+                if let Some(surface_info) = self.surface_info.as_ref() {
+                    surface_info
+                        .surface_loader
+                        .destroy_surface(surface_info.surface, None);
+                }
+
+                if VK_VALIDATION_LAYER_NAMES.is_enable {
+                    self.debug_utils_loader
+                        .destroy_debug_utils_messenger(self.debug_callback, None);
+                }
+
+                self.instance.destroy_instance(None);
+            }
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        #[test]
+        fn guards_destroy_unless_released() {
+            static DESTROYED: AtomicUsize = AtomicUsize::new(0);
+            let destroy = |value: &usize| {
+                DESTROYED.fetch_add(*value, Ordering::SeqCst);
+            };
+
+            drop(Guard::new(1, destroy));
+            assert_eq!(DESTROYED.load(Ordering::SeqCst), 1);
+
+            let guard = Guard::new(10, destroy);
+            assert_eq!(*guard, 10);
+            assert_eq!(guard.release(), 10);
+            assert_eq!(DESTROYED.load(Ordering::SeqCst), 1);
+        }
+    }
+}
+
+pub use _renderer::{RenderContext, Renderer, RendererOptions, Scene};
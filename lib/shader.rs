@@ -0,0 +1,248 @@
+mod _shader {
+    use crate::{self as vk_utils, constants::shader::*};
+    use std::collections::HashMap;
+    use std::path::{Path, PathBuf};
+    use std::time::{Instant, SystemTime};
+
+    // Picks the stage from the file extension like glslc does
+    fn shader_stage(source_path: &Path) -> vk_utils::Result<naga::ShaderStage> {
+        match source_path
+            .extension()
+            .and_then(|extension| extension.to_str())
+        {
+            Some("vert") => Ok(naga::ShaderStage::Vertex),
+            Some("frag") => Ok(naga::ShaderStage::Fragment),
+            Some("comp") => Ok(naga::ShaderStage::Compute),
+            _ => Err(vk_utils::Error::ShaderCompilation(format!(
+                "{}: unknown shader stage",
+                source_path.display()
+            ))),
+        }
+    }
+
+    // GLSL for Vulkan already uses Vulkan's clip space, no y flip
+    fn spv_options() -> naga::back::spv::Options<'static> {
+        naga::back::spv::Options {
+            flags: naga::back::spv::Options::default().flags
+                - naga::back::spv::WriterFlags::ADJUST_COORDINATE_SPACE,
+            ..Default::default()
+        }
+    }
+
+    // FNV-1a, stable across runs unlike DefaultHasher. Covers everything the SPIR-V
+    // depends on besides the source, so another compiler or other options miss the cache
+    fn content_hash(stage: naga::ShaderStage, source: &str) -> u64 {
+        let stage_name = match stage {
+            naga::ShaderStage::Vertex => "vert",
+            naga::ShaderStage::Fragment => "frag",
+            naga::ShaderStage::Compute => "comp",
+        };
+        let options = spv_options();
+        let compiler = format!(
+            "{} naga {} spv {}.{} {:x}",
+            CACHE_VERSION,
+            NAGA_VERSION,
+            options.lang_version.0,
+            options.lang_version.1,
+            options.flags.bits()
+        );
+
+        compiler
+            .bytes()
+            .chain(stage_name.bytes())
+            .chain(source.bytes())
+            .fold(0xcbf29ce484222325, |hash, byte| {
+                (hash ^ byte as u64).wrapping_mul(0x100000001b3)
+            })
+    }
+
+    // Header with the magic number, then instructions filling the rest exactly
+    // (a truncated file ends in the middle of one)
+    fn is_complete_spirv(code: &[u32]) -> bool {
+        const MAGIC_NUMBER: u32 = 0x07230203;
+        const HEADER_WORDS: usize = 5;
+
+        if code.len() < HEADER_WORDS || code[0] != MAGIC_NUMBER {
+            return false;
+        }
+
+        let mut offset = HEADER_WORDS;
+
+        while offset < code.len() {
+            // The high half of an instruction's first word is its length in words
+            let word_count = (code[offset] >> 16) as usize;
+
+            if word_count == 0 {
+                return false;
+            }
+            offset += word_count;
+        }
+
+        offset == code.len()
+    }
+
+    pub fn compile_glsl(
+        source: &str,
+        stage: naga::ShaderStage,
+        name: &str,
+    ) -> vk_utils::Result<Vec<u32>> {
+        let module = naga::front::glsl::Frontend::default()
+            .parse(&naga::front::glsl::Options::from(stage), source)
+            .map_err(|err| {
+                vk_utils::Error::ShaderCompilation(format!(
+                    "{}:\n{}",
+                    name,
+                    err.emit_to_string(source)
+                ))
+            })?;
+
+        // Push constants are always available in Vulkan
+        let module_info = naga::valid::Validator::new(
+            naga::valid::ValidationFlags::all(),
+            naga::valid::Capabilities::PUSH_CONSTANT,
+        )
+        .validate(&module)
+        .map_err(|err| {
+            vk_utils::Error::ShaderCompilation(err.emit_to_string_with_path(source, name))
+        })?;
+
+        naga::back::spv::write_vec(&module, &module_info, &spv_options(), None)
+            .map_err(|err| vk_utils::Error::ShaderCompilation(format!("{}: {}", name, err)))
+    }
+
+    // Compiles a GLSL source file to SPIR-V, reusing the cached result
+    // as long as the source is unchanged
+    pub fn load_shader(source_path: &Path) -> vk_utils::Result<Vec<u32>> {
+        let stage = shader_stage(source_path)?;
+        let source = std::fs::read_to_string(source_path)?;
+        let cache_path =
+            Path::new(CACHE_DIR).join(format!("{:016x}.spv", content_hash(stage, &source)));
+
+        if let Ok(code) = vk_utils::tools::read_shader_code(&cache_path) {
+            if is_complete_spirv(&code) {
+                return Ok(code);
+            }
+        }
+
+        let code = compile_glsl(&source, stage, &source_path.to_string_lossy())?;
+        let bytes = code
+            .iter()
+            .flat_map(|word| word.to_ne_bytes())
+            .collect::<Vec<_>>();
+
+        // A missing cache only costs a recompile next time
+        let _ = write_cache_file(&cache_path, &bytes);
+
+        Ok(code)
+    }
+
+    // Written next to it first, so a crash or another run compiling the same shader
+    // never leaves half a file behind
+    fn write_cache_file(cache_path: &Path, bytes: &[u8]) -> std::io::Result<()> {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        // Unique per process and call, threads may compile the same shader too
+        static WRITE_COUNT: AtomicUsize = AtomicUsize::new(0);
+
+        let temporary_path = cache_path.with_extension(format!(
+            "{}-{}.tmp",
+            std::process::id(),
+            WRITE_COUNT.fetch_add(1, Ordering::Relaxed)
+        ));
+
+        std::fs::create_dir_all(CACHE_DIR)?;
+
+        let result = std::fs::write(&temporary_path, bytes)
+            .and_then(|_| std::fs::rename(&temporary_path, cache_path));
+
+        if result.is_err() {
+            let _ = std::fs::remove_file(&temporary_path);
+        }
+
+        result
+    }
+
+    // Polls the modification times of the files in a directory
+    pub struct ShaderWatcher {
+        directory: PathBuf,
+        modified_times: HashMap<PathBuf, SystemTime>,
+        last_poll: Instant,
+    }
+
+    impl ShaderWatcher {
+        pub fn new(directory: &Path) -> vk_utils::Result<Self> {
+            let mut watcher = Self {
+                directory: directory.to_path_buf(),
+                modified_times: HashMap::new(),
+                last_poll: Instant::now(),
+            };
+
+            watcher.scan()?;
+
+            Ok(watcher)
+        }
+
+        fn scan(&mut self) -> vk_utils::Result<Vec<PathBuf>> {
+            let mut changed = vec![];
+
+            for entry in std::fs::read_dir(&self.directory)? {
+                let path = entry?.path();
+                let Ok(modified) = std::fs::metadata(&path).and_then(|meta| meta.modified()) else {
+                    continue;
+                };
+
+                if self.modified_times.insert(path.clone(), modified) != Some(modified) {
+                    changed.push(path);
+                }
+            }
+
+            Ok(changed)
+        }
+
+        // Files added or modified since the last poll, checked at most every WATCH_INTERVAL
+        pub fn poll(&mut self) -> vk_utils::Result<Vec<PathBuf>> {
+            if self.last_poll.elapsed() < WATCH_INTERVAL {
+                return Ok(vec![]);
+            }
+            self.last_poll = Instant::now();
+
+            self.scan()
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        const SHADER: &str = "#version 450\nvoid main() {}\n";
+
+        #[test]
+        fn hashes_depend_on_stage_and_source() {
+            let hash = content_hash(naga::ShaderStage::Vertex, SHADER);
+
+            assert_eq!(hash, content_hash(naga::ShaderStage::Vertex, SHADER));
+            assert_ne!(hash, content_hash(naga::ShaderStage::Fragment, SHADER));
+            assert_ne!(
+                hash,
+                content_hash(naga::ShaderStage::Vertex, "#version 450\n")
+            );
+        }
+
+        #[test]
+        fn truncated_spirv_is_not_trusted() {
+            let code = compile_glsl(SHADER, naga::ShaderStage::Fragment, "empty.frag").unwrap();
+
+            assert!(is_complete_spirv(&code));
+            // Cut after the first word of the first instruction (OpCapability)
+            assert!(!is_complete_spirv(&code[..6]));
+            assert!(!is_complete_spirv(&code[..4]));
+            assert!(!is_complete_spirv(&[]));
+
+            let mut swapped = code.clone();
+            swapped[0] = swapped[0].swap_bytes();
+            assert!(!is_complete_spirv(&swapped));
+        }
+    }
+}
+
+pub use _shader::{compile_glsl, load_shader, ShaderWatcher};
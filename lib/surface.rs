@@ -1,6 +1,5 @@
 mod _surface {
     use crate as vk_utils;
-    use crate::constants::{HEIGHT, WIDTH};
 
     use ash::{extensions::khr::Surface, vk, Entry, Instance};
     use raw_window_handle::{HasRawDisplayHandle, HasRawWindowHandle};
@@ -10,7 +9,7 @@ mod _surface {
         entry: &Entry,
         instance: &Instance,
         window: &Window,
-    ) -> vk_utils::VkSurfaceInfo {
+    ) -> vk_utils::Result<vk_utils::VkSurfaceInfo> {
         let surface = unsafe {
             ash_window::create_surface(
                 entry,
@@ -19,34 +18,35 @@ mod _surface {
                 window.raw_window_handle(),
                 None,
             )
-            .expect("failed to create window surface!")
+            .map_err(vk_utils::Error::vulkan("failed to create window surface!"))?
         };
         let surface_loader = Surface::new(entry, instance);
+        let window_size = window.inner_size();
 
-        vk_utils::VkSurfaceInfo {
+        Ok(vk_utils::VkSurfaceInfo {
             surface_loader,
             surface,
-            screen_width: WIDTH,
-            screen_height: HEIGHT,
-        }
+            screen_width: window_size.width,
+            screen_height: window_size.height,
+        })
     }
 
     pub fn choose_swap_surface_format(
-        available_formats: &Vec<vk::SurfaceFormatKHR>,
+        available_formats: &[vk::SurfaceFormatKHR],
     ) -> vk::SurfaceFormatKHR {
         for available_format in available_formats.iter() {
             if available_format.format == vk::Format::B8G8R8A8_SRGB
                 && available_format.color_space == vk::ColorSpaceKHR::SRGB_NONLINEAR
             {
-                return available_format.clone();
+                return *available_format;
             }
         }
 
-        available_formats.first().unwrap().clone()
+        *available_formats.first().unwrap()
     }
 
     pub fn choose_swap_present_mode(
-        available_present_modes: &Vec<vk::PresentModeKHR>,
+        available_present_modes: &[vk::PresentModeKHR],
     ) -> vk::PresentModeKHR {
         available_present_modes
             .iter()
@@ -55,18 +55,22 @@ mod _surface {
             .unwrap_or(vk::PresentModeKHR::FIFO)
     }
 
-    pub fn choose_swap_extent(capabilities: &vk::SurfaceCapabilitiesKHR) -> vk::Extent2D {
+    // Surfaces that leave the extent to the swap chain (u32::MAX) get the window size
+    pub fn choose_swap_extent(
+        capabilities: &vk::SurfaceCapabilitiesKHR,
+        window_extent: vk::Extent2D,
+    ) -> vk::Extent2D {
         use num::clamp;
 
         match capabilities.current_extent.width {
-            std::u32::MAX => vk::Extent2D {
+            u32::MAX => vk::Extent2D {
                 width: clamp(
-                    WIDTH,
+                    window_extent.width,
                     capabilities.min_image_extent.width,
                     capabilities.max_image_extent.width,
                 ),
                 height: clamp(
-                    HEIGHT,
+                    window_extent.height,
                     capabilities.min_image_extent.height,
                     capabilities.max_image_extent.height,
                 ),
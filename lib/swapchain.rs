@@ -1,36 +1,42 @@
 mod _swapchain {
-    use crate::{VkSurfaceInfo, VkSwapChainInfo, VkSwapchainDetail};
+    use crate::{self as vk_utils, VkSurfaceInfo, VkSwapChainInfo, VkSwapchainDetail};
 
     use ash::{extensions::khr::Swapchain, vk};
 
     pub fn query_swapchain_support(
         physical_device: vk::PhysicalDevice,
         surface_info: &VkSurfaceInfo,
-    ) -> VkSwapchainDetail {
+    ) -> vk_utils::Result<VkSwapchainDetail> {
         let capabilities = unsafe {
             surface_info
                 .surface_loader
                 .get_physical_device_surface_capabilities(physical_device, surface_info.surface)
-                .expect("failed to query for surface capabilities.")
+                .map_err(vk_utils::Error::vulkan(
+                    "failed to query for surface capabilities.",
+                ))?
         };
         let formats = unsafe {
             surface_info
                 .surface_loader
                 .get_physical_device_surface_formats(physical_device, surface_info.surface)
-                .expect("failed to query for surface formats.")
+                .map_err(vk_utils::Error::vulkan(
+                    "failed to query for surface formats.",
+                ))?
         };
         let present_modes = unsafe {
             surface_info
                 .surface_loader
                 .get_physical_device_surface_present_modes(physical_device, surface_info.surface)
-                .expect("failed to query for surface present modes.")
+                .map_err(vk_utils::Error::vulkan(
+                    "failed to query for surface present modes.",
+                ))?
         };
 
-        VkSwapchainDetail {
+        Ok(VkSwapchainDetail {
             capabilities,
             formats,
             present_modes,
-        }
+        })
     }
 
     pub fn create_swap_chain(
@@ -39,13 +45,19 @@ mod _swapchain {
         physical_device: vk::PhysicalDevice,
         surface_info: &VkSurfaceInfo,
         queue_family: &crate::QueueFamilyIndices,
-    ) -> VkSwapChainInfo {
-        let swap_chain_support = query_swapchain_support(physical_device, surface_info);
+    ) -> vk_utils::Result<VkSwapChainInfo> {
+        let swap_chain_support = query_swapchain_support(physical_device, surface_info)?;
         let surface_format =
             crate::surface::choose_swap_surface_format(&swap_chain_support.formats);
         let present_mode =
             crate::surface::choose_swap_present_mode(&swap_chain_support.present_modes);
-        let extent = crate::surface::choose_swap_extent(&swap_chain_support.capabilities);
+        let extent = crate::surface::choose_swap_extent(
+            &swap_chain_support.capabilities,
+            vk::Extent2D {
+                width: surface_info.screen_width,
+                height: surface_info.screen_height,
+            },
+        );
 
         let image_count = if swap_chain_support.capabilities.max_image_count > 0
             && (swap_chain_support.capabilities.min_image_count + 1)
@@ -98,29 +110,29 @@ mod _swapchain {
         let swapchain = unsafe {
             swapchain_loader
                 .create_swapchain(&create_info, None)
-                .expect("failed to create swap chain!")
+                .map_err(vk_utils::Error::vulkan("failed to create swap chain!"))?
         };
         let swapchain_images = unsafe {
             swapchain_loader
                 .get_swapchain_images(swapchain)
-                .expect("failed to get swap chain images!")
+                .map_err(vk_utils::Error::vulkan("failed to get swap chain images!"))?
         };
 
-        VkSwapChainInfo {
+        Ok(VkSwapChainInfo {
             swapchain_loader,
             swapchain,
             swapchain_images,
             swapchain_format: surface_format.format,
             swapchain_extent: extent,
-        }
+        })
     }
 
     pub fn create_image_views(
         device: &ash::Device,
         surface_format: vk::Format,
-        images: &Vec<vk::Image>,
-    ) -> Vec<vk::ImageView> {
-        let swap_chain_image_views: Vec<vk::ImageView> = images
+        images: &[vk::Image],
+    ) -> vk_utils::Result<Vec<vk_utils::image::ImageView>> {
+        let swap_chain_image_views = images
             .iter()
             .map(|&image| {
                 /*
@@ -150,7 +162,7 @@ mod _swapchain {
                     1,
                 )
             })
-            .collect();
+            .collect::<vk_utils::Result<Vec<vk_utils::image::ImageView>>>();
 
         swap_chain_image_views
     }
@@ -161,7 +173,7 @@ mod _swapchain {
         format: vk::Format,
         aspect_flags: vk::ImageAspectFlags,
         mip_levels: u32,
-    ) -> vk::ImageView {
+    ) -> vk_utils::Result<vk_utils::image::ImageView> {
         let view_info = vk::ImageViewCreateInfo::builder()
             .image(texture_image)
             .view_type(vk::ImageViewType::TYPE_2D)
@@ -180,20 +192,24 @@ mod _swapchain {
                 layer_count: 1,
             });
 
-        unsafe {
+        let image_view = unsafe {
             device
                 .create_image_view(&view_info, None)
-                .expect("failed to create texture image view!")
-        }
+                .map_err(vk_utils::Error::vulkan(
+                    "failed to create texture image view!",
+                ))?
+        };
+
+        Ok(vk_utils::image::ImageView::new(device, image_view))
     }
 
     pub fn find_supported_format(
         instance: &ash::Instance,
         physical_device: vk::PhysicalDevice,
-        candidates: &Vec<vk::Format>,
+        candidates: &[vk::Format],
         tiling: vk::ImageTiling,
         features: vk::FormatFeatureFlags,
-    ) -> Result<vk::Format, String> {
+    ) -> vk_utils::Result<vk::Format> {
         for &format in candidates.iter() {
             let props =
                 unsafe { instance.get_physical_device_format_properties(physical_device, format) };
@@ -207,13 +223,13 @@ mod _swapchain {
             }
         }
 
-        Err(String::from("failed to find supported format!"))
+        Err(vk_utils::Error::MissingFormat(candidates.to_vec()))
     }
 
     pub fn find_depth_format(
         instance: &ash::Instance,
         physical_device: vk::PhysicalDevice,
-    ) -> Result<vk::Format, String> {
+    ) -> vk_utils::Result<vk::Format> {
         let formats = vec![
             vk::Format::D32_SFLOAT,
             vk::Format::D32_SFLOAT_S8_UINT,
@@ -2,7 +2,47 @@ mod _texture {
     use crate as vk_utils;
     use ash::vk;
 
-    pub fn create_descriptor_set_layout(device: &ash::Device) -> vk::DescriptorSetLayout {
+    // How the texels of a texture are meant to be read, which picks its format
+    #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+    pub enum TextureRole {
+        // Colors as painted (albedo, emissive), sRGB decoded when sampled
+        Color,
+        // Data read as stored (normal maps, roughness), never gamma decoded
+        Linear,
+        // Gray (and alpha) only: R8/RG8, R16/RG16 or R32 float
+        Mask,
+    }
+
+    // Decoded texels, rows top to bottom, in the layout of `format`
+    pub struct TexturePixels {
+        pub width: u32,
+        pub height: u32,
+        pub format: vk::Format,
+        pub data: Vec<u8>,
+    }
+
+    pub struct Sampler {
+        device: ash::Device,
+        sampler: vk::Sampler,
+    }
+
+    impl Sampler {
+        pub fn handle(&self) -> vk::Sampler {
+            self.sampler
+        }
+    }
+
+    impl Drop for Sampler {
+        fn drop(&mut self) {
+            unsafe {
+                self.device.destroy_sampler(self.sampler, None);
+            }
+        }
+    }
+
+    pub fn create_descriptor_set_layout(
+        device: &ash::Device,
+    ) -> vk_utils::Result<vk::DescriptorSetLayout> {
         // Descriptor set layout
         let ubo_layout_bindings = [
             vk::DescriptorSetLayoutBinding::builder()
@@ -11,10 +51,16 @@ mod _texture {
                 .descriptor_count(1)
                 .stage_flags(vk::ShaderStageFlags::VERTEX)
                 .build(),
-            // Updating the descriptors
+            // Updating the descriptors (image and sampler bound separately)
             vk::DescriptorSetLayoutBinding::builder()
                 .binding(1)
-                .descriptor_type(vk::DescriptorType::COMBINED_IMAGE_SAMPLER)
+                .descriptor_type(vk::DescriptorType::SAMPLED_IMAGE)
+                .descriptor_count(1)
+                .stage_flags(vk::ShaderStageFlags::FRAGMENT)
+                .build(),
+            vk::DescriptorSetLayoutBinding::builder()
+                .binding(2)
+                .descriptor_type(vk::DescriptorType::SAMPLER)
                 .descriptor_count(1)
                 .stage_flags(vk::ShaderStageFlags::FRAGMENT)
                 .build(),
@@ -25,25 +71,33 @@ mod _texture {
         let descriptor_set_layout = unsafe {
             device
                 .create_descriptor_set_layout(&layout_info, None)
-                .expect("failed to create descriptor set layout!")
+                .map_err(vk_utils::Error::vulkan(
+                    "failed to create descriptor set layout!",
+                ))?
         };
 
-        descriptor_set_layout
+        Ok(descriptor_set_layout)
     }
 
+    // UNIFORM_BUFFER_DYNAMIC when the uniform buffer is bound with dynamic offsets
     pub fn create_descriptor_pool(
         device: &ash::Device,
+        uniform_buffer_type: ash::vk::DescriptorType,
         swapchain_image_size: usize,
-    ) -> ash::vk::DescriptorPool {
+    ) -> vk_utils::Result<ash::vk::DescriptorPool> {
         // Descriptor pool
         let pool_sizes = [
             ash::vk::DescriptorPoolSize::builder()
-                .ty(ash::vk::DescriptorType::UNIFORM_BUFFER)
+                .ty(uniform_buffer_type)
                 .descriptor_count(swapchain_image_size as u32)
                 .build(),
             // Using the descriptors
             ash::vk::DescriptorPoolSize::builder()
-                .ty(ash::vk::DescriptorType::COMBINED_IMAGE_SAMPLER)
+                .ty(ash::vk::DescriptorType::SAMPLED_IMAGE)
+                .descriptor_count(swapchain_image_size as u32)
+                .build(),
+            ash::vk::DescriptorPoolSize::builder()
+                .ty(ash::vk::DescriptorType::SAMPLER)
                 .descriptor_count(swapchain_image_size as u32)
                 .build(),
         ];
@@ -55,22 +109,36 @@ mod _texture {
         unsafe {
             device
                 .create_descriptor_pool(&pool_info, None)
-                .expect("failed to create descriptor pool!")
+                .map_err(vk_utils::Error::vulkan("failed to create descriptor pool!"))
         }
     }
 
+    // What the sets from create_descriptor_sets point at, one uniform buffer per set
+    pub struct DescriptorSetResources<'a> {
+        pub uniform_buffer_type: ash::vk::DescriptorType,
+        pub uniform_buffers: &'a [vk_utils::buffer::Buffer],
+        // Bytes visible to the shader, from the (dynamic) offset on
+        pub uniform_buffer_range: ash::vk::DeviceSize,
+        pub texture_image_view: ash::vk::ImageView,
+        pub texture_sampler: ash::vk::Sampler,
+    }
+
     pub fn create_descriptor_sets(
         device: &ash::Device,
         descriptor_pool: ash::vk::DescriptorPool,
         descriptor_set_layout: ash::vk::DescriptorSetLayout,
-        uniform_buffers: &Vec<ash::vk::Buffer>,
         swapchain_image_size: usize,
-        texture_image_view: ash::vk::ImageView,
-        texture_sampler: ash::vk::Sampler,
-    ) -> Vec<ash::vk::DescriptorSet> {
-        // Descriptor set
-        use std::mem::size_of;
+        resources: &DescriptorSetResources,
+    ) -> vk_utils::Result<Vec<ash::vk::DescriptorSet>> {
+        let DescriptorSetResources {
+            uniform_buffer_type,
+            uniform_buffers,
+            uniform_buffer_range,
+            texture_image_view,
+            texture_sampler,
+        } = *resources;
 
+        // Descriptor set
         let mut layouts: Vec<ash::vk::DescriptorSetLayout> = Vec::new();
 
         for _ in 0..swapchain_image_size {
@@ -84,19 +152,23 @@ mod _texture {
         let descriptor_sets = unsafe {
             device
                 .allocate_descriptor_sets(&alloc_info)
-                .expect("failed to allocate descriptor sets!")
+                .map_err(vk_utils::Error::vulkan(
+                    "failed to allocate descriptor sets!",
+                ))?
         };
 
         for (i, &descriptor_set) in descriptor_sets.iter().enumerate() {
             let buffer_infos = [ash::vk::DescriptorBufferInfo::builder()
-                .buffer(uniform_buffers[i])
+                .buffer(uniform_buffers[i].handle())
                 .offset(0)
-                .range(size_of::<vk_utils::types::UniformBufferObject>() as u64)
+                .range(uniform_buffer_range)
                 .build()];
 
             let image_infos = [ash::vk::DescriptorImageInfo::builder()
                 .image_layout(ash::vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL)
                 .image_view(texture_image_view)
+                .build()];
+            let sampler_infos = [ash::vk::DescriptorImageInfo::builder()
                 .sampler(texture_sampler)
                 .build()];
 
@@ -105,7 +177,7 @@ mod _texture {
                     .dst_set(descriptor_set)
                     .dst_binding(0)
                     .dst_array_element(0)
-                    .descriptor_type(ash::vk::DescriptorType::UNIFORM_BUFFER)
+                    .descriptor_type(uniform_buffer_type)
                     .buffer_info(&buffer_infos)
                     .build(),
                 // Updating the descriptors
@@ -113,9 +185,16 @@ mod _texture {
                     .dst_set(descriptor_set)
                     .dst_binding(1)
                     .dst_array_element(0)
-                    .descriptor_type(ash::vk::DescriptorType::COMBINED_IMAGE_SAMPLER)
+                    .descriptor_type(ash::vk::DescriptorType::SAMPLED_IMAGE)
                     .image_info(&image_infos)
                     .build(),
+                ash::vk::WriteDescriptorSet::builder()
+                    .dst_set(descriptor_set)
+                    .dst_binding(2)
+                    .dst_array_element(0)
+                    .descriptor_type(ash::vk::DescriptorType::SAMPLER)
+                    .image_info(&sampler_infos)
+                    .build(),
             ];
 
             unsafe {
@@ -123,154 +202,460 @@ mod _texture {
             }
         }
 
-        descriptor_sets
+        Ok(descriptor_sets)
     }
 
-    pub fn create_texture_image(
+    // Points bindings 1 and 2 of a set from create_descriptor_sets at another texture.
+    // The set must not be in use by pending command buffers
+    pub fn update_texture_descriptors(
         device: &ash::Device,
-        command_pool: vk::CommandPool,
-        device_memory_properties: &vk::PhysicalDeviceMemoryProperties,
+        descriptor_set: ash::vk::DescriptorSet,
+        texture_image_view: ash::vk::ImageView,
+        texture_sampler: ash::vk::Sampler,
+    ) {
+        let image_infos = [ash::vk::DescriptorImageInfo::builder()
+            .image_layout(ash::vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL)
+            .image_view(texture_image_view)
+            .build()];
+        let sampler_infos = [ash::vk::DescriptorImageInfo::builder()
+            .sampler(texture_sampler)
+            .build()];
+
+        let descriptor_writes = [
+            ash::vk::WriteDescriptorSet::builder()
+                .dst_set(descriptor_set)
+                .dst_binding(1)
+                .dst_array_element(0)
+                .descriptor_type(ash::vk::DescriptorType::SAMPLED_IMAGE)
+                .image_info(&image_infos)
+                .build(),
+            ash::vk::WriteDescriptorSet::builder()
+                .dst_set(descriptor_set)
+                .dst_binding(2)
+                .dst_array_element(0)
+                .descriptor_type(ash::vk::DescriptorType::SAMPLER)
+                .image_info(&sampler_infos)
+                .build(),
+        ];
+
+        unsafe {
+            device.update_descriptor_sets(&descriptor_writes, &[]);
+        }
+    }
+
+    // Returns the format the image was uploaded with along with the mip level count.
+    // Waits for the graphics queue, the upload manager batches uploads instead
+    pub fn create_texture_image(
+        context: &vk_utils::renderer::RenderContext,
         image_path: &std::path::Path,
-        graphics_queue: vk::Queue,
-    ) -> Result<(vk::Image, vk::DeviceMemory, u32), String> {
-        use std::mem::size_of;
+        role: TextureRole,
+    ) -> vk_utils::Result<(vk_utils::image::Image, vk::Format, u32)> {
+        let mut pixels = load_image_pixels(image_path, role)?;
+        if let Some(supported) =
+            to_supported_format(context.instance, context.physical_device, &pixels)
+        {
+            pixels = supported;
+        }
+        let mip_levels = texture_mip_levels(
+            context.instance,
+            context.physical_device,
+            pixels.format,
+            pixels.width,
+            pixels.height,
+        );
+
+        let texture_image = create_texture_image_from_pixels(
+            context.device,
+            context.command_pool,
+            context.allocator,
+            &pixels,
+            mip_levels,
+            context.graphics_queue,
+        )?;
+
+        Ok((texture_image, pixels.format, mip_levels))
+    }
+
+    // Decodes an image file, flipped to match the texture coordinates.
+    // 8 bit images become RGBA8 (sRGB for colors), 16 bit ones RGBA16 unless they are colors
+    // (there is no 16 bit sRGB format), and HDR ones (.hdr, .exr) RGBA16 float.
+    // Masks keep a single channel, or two with alpha
+    pub fn load_image_pixels(
+        image_path: &std::path::Path,
+        role: TextureRole,
+    ) -> vk_utils::Result<TexturePixels> {
+        use image::DynamicImage;
 
         // Loading an image
-        let mut image_obj = image::open(image_path).unwrap();
+        let mut image_obj = image::open(image_path)?;
         image_obj = image_obj.flipv();
 
-        let (tex_width, tex_height) = (image_obj.width(), image_obj.height());
-        let image_size = (size_of::<u8>() as u32 * tex_width * tex_height * 4) as vk::DeviceSize;
-        let image_data = match &image_obj {
-            image::DynamicImage::ImageLuma8(_) | image::DynamicImage::ImageRgb8(_) => {
-                Some(image_obj.to_rgba8().into_raw())
+        let (width, height) = (image_obj.width(), image_obj.height());
+        let is_hdr = matches!(
+            image_obj,
+            DynamicImage::ImageRgb32F(_) | DynamicImage::ImageRgba32F(_)
+        );
+        let is_16_bit = matches!(
+            image_obj,
+            DynamicImage::ImageLuma16(_)
+                | DynamicImage::ImageLumaA16(_)
+                | DynamicImage::ImageRgb16(_)
+                | DynamicImage::ImageRgba16(_)
+        );
+
+        let (format, data) = match role {
+            TextureRole::Mask => match &image_obj {
+                DynamicImage::ImageLumaA8(_) => (
+                    vk::Format::R8G8_UNORM,
+                    image_obj.to_luma_alpha8().into_raw(),
+                ),
+                DynamicImage::ImageLumaA16(_) => (
+                    vk::Format::R16G16_UNORM,
+                    to_ne_bytes(image_obj.to_luma_alpha16().into_raw(), u16::to_ne_bytes),
+                ),
+                _ if is_16_bit => (
+                    vk::Format::R16_UNORM,
+                    to_ne_bytes(image_obj.to_luma16().into_raw(), u16::to_ne_bytes),
+                ),
+                // The red channel, there is no float gray
+                _ if is_hdr => (
+                    vk::Format::R32_SFLOAT,
+                    to_ne_bytes(
+                        image_obj.to_rgb32f().into_raw().into_iter().step_by(3),
+                        f32::to_ne_bytes,
+                    ),
+                ),
+                _ => (vk::Format::R8_UNORM, image_obj.to_luma8().into_raw()),
+            },
+            _ if is_hdr => (
+                vk::Format::R16G16B16A16_SFLOAT,
+                to_ne_bytes(
+                    image_obj
+                        .to_rgba32f()
+                        .into_raw()
+                        .into_iter()
+                        .map(f32_to_f16),
+                    u16::to_ne_bytes,
+                ),
+            ),
+            TextureRole::Linear if is_16_bit => (
+                vk::Format::R16G16B16A16_UNORM,
+                to_ne_bytes(image_obj.to_rgba16().into_raw(), u16::to_ne_bytes),
+            ),
+            TextureRole::Linear => (vk::Format::R8G8B8A8_UNORM, image_obj.to_rgba8().into_raw()),
+            TextureRole::Color => (vk::Format::R8G8B8A8_SRGB, image_obj.to_rgba8().into_raw()),
+        };
+
+        Ok(TexturePixels {
+            width,
+            height,
+            format,
+            data,
+        })
+    }
+
+    fn to_ne_bytes<T, const N: usize>(
+        values: impl IntoIterator<Item = T>,
+        to_bytes: fn(T) -> [u8; N],
+    ) -> Vec<u8> {
+        values.into_iter().flat_map(to_bytes).collect()
+    }
+
+    // Bits of the nearest half precision float (ties to even), for R16..._SFLOAT texels
+    fn f32_to_f16(value: f32) -> u16 {
+        let bits = value.to_bits();
+        let sign = ((bits >> 16) & 0x8000) as u16;
+        let exponent = ((bits >> 23) & 0xff) as i32;
+        let mantissa = bits & 0x007f_ffff;
+
+        let round = |value: u32, shift: u32| -> u32 {
+            let (truncated, remainder) = (value >> shift, value & ((1 << shift) - 1));
+            let halfway = 1 << (shift - 1);
+
+            if remainder > halfway || (remainder == halfway && truncated & 1 == 1) {
+                truncated + 1
+            } else {
+                truncated
             }
-            image::DynamicImage::ImageLumaA8(_) | image::DynamicImage::ImageRgba8(_) => {
-                Some(image_obj.into_bytes())
+        };
+
+        // Infinity and NaN
+        if exponent == 0xff {
+            return sign | 0x7c00 | if mantissa != 0 { 0x0200 } else { 0 };
+        }
+
+        let exponent = exponent - 127 + 15;
+        if exponent >= 0x1f {
+            // Too large
+            sign | 0x7c00
+        } else if exponent <= 0 {
+            // Subnormal, or too small
+            if exponent < -10 {
+                sign
+            } else {
+                sign | round(mantissa | 0x0080_0000, (14 - exponent) as u32) as u16
             }
-            &_ => None,
+        } else {
+            // Rounding up may carry into the exponent, up to infinity, which is right too
+            sign | round(((exponent as u32) << 23) | mantissa, 13) as u16
+        }
+    }
+
+    fn f16_to_f32(bits: u16) -> f32 {
+        let sign = if bits & 0x8000 != 0 { -1.0 } else { 1.0 };
+        let exponent = ((bits >> 10) & 0x1f) as i32;
+        let mantissa = (bits & 0x03ff) as f32;
+
+        match exponent {
+            0 => sign * mantissa * 2.0_f32.powi(-24),
+            0x1f if mantissa == 0.0 => sign * f32::INFINITY,
+            0x1f => f32::NAN,
+            _ => sign * (1.0 + mantissa / 1024.0) * 2.0_f32.powi(exponent - 15),
+        }
+    }
+
+    // Same texels in the 8 bit format of as many channels, None unless `pixels` are wider.
+    // Floats are clamped to [0, 1]
+    fn to_8_bit(pixels: &TexturePixels) -> Option<TexturePixels> {
+        let unorm16 = |bytes: &[u8]| {
+            let value = u16::from_ne_bytes([bytes[0], bytes[1]]) as u32;
+
+            ((value * 255 + 32767) / 65535) as u8
+        };
+        let float = |value: f32| (value.clamp(0.0, 1.0) * 255.0).round() as u8;
+
+        let (format, data) = match pixels.format {
+            vk::Format::R16_UNORM => (
+                vk::Format::R8_UNORM,
+                pixels.data.chunks_exact(2).map(unorm16).collect(),
+            ),
+            vk::Format::R16G16_UNORM => (
+                vk::Format::R8G8_UNORM,
+                pixels.data.chunks_exact(2).map(unorm16).collect(),
+            ),
+            vk::Format::R16G16B16A16_UNORM => (
+                vk::Format::R8G8B8A8_UNORM,
+                pixels.data.chunks_exact(2).map(unorm16).collect(),
+            ),
+            vk::Format::R16G16B16A16_SFLOAT => (
+                vk::Format::R8G8B8A8_UNORM,
+                pixels
+                    .data
+                    .chunks_exact(2)
+                    .map(|bytes| float(f16_to_f32(u16::from_ne_bytes([bytes[0], bytes[1]]))))
+                    .collect(),
+            ),
+            vk::Format::R32_SFLOAT => (
+                vk::Format::R8_UNORM,
+                pixels
+                    .data
+                    .chunks_exact(4)
+                    .map(|bytes| {
+                        float(f32::from_ne_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+                    })
+                    .collect(),
+            ),
+            _ => return None,
         };
 
-        let mip_levels = (std::cmp::max(tex_width, tex_height) as f32).log2().floor() as u32 + 1;
+        Some(TexturePixels {
+            width: pixels.width,
+            height: pixels.height,
+            format,
+            data,
+        })
+    }
 
-        if image_data.is_none() {
-            return Err(String::from("invalid image format!"));
+    // 16 and 32 bit pixels converted to 8 bits when the device can't sample their format
+    // or blit mip levels of it. None when they can be uploaded as they are
+    pub fn to_supported_format(
+        instance: &ash::Instance,
+        physical_device: vk::PhysicalDevice,
+        pixels: &TexturePixels,
+    ) -> Option<TexturePixels> {
+        let features = unsafe {
+            instance.get_physical_device_format_properties(physical_device, pixels.format)
         }
+        .optimal_tiling_features;
+
+        if features.contains(
+            vk::FormatFeatureFlags::SAMPLED_IMAGE
+                | vk::FormatFeatureFlags::BLIT_SRC
+                | vk::FormatFeatureFlags::BLIT_DST,
+        ) {
+            return None;
+        }
+
+        to_8_bit(pixels)
+    }
+
+    // Bytes per texel of the formats load_image_pixels produces, and a few alike
+    pub fn texel_size(format: vk::Format) -> vk_utils::Result<u32> {
+        match format {
+            vk::Format::R8_UNORM | vk::Format::R8_SRGB => Ok(1),
+            vk::Format::R8G8_UNORM
+            | vk::Format::R8G8_SRGB
+            | vk::Format::R16_UNORM
+            | vk::Format::R16_SFLOAT => Ok(2),
+            vk::Format::R8G8B8A8_UNORM
+            | vk::Format::R8G8B8A8_SRGB
+            | vk::Format::B8G8R8A8_UNORM
+            | vk::Format::B8G8R8A8_SRGB
+            | vk::Format::R16G16_UNORM
+            | vk::Format::R16G16_SFLOAT
+            | vk::Format::R32_SFLOAT => Ok(4),
+            vk::Format::R16G16B16A16_UNORM
+            | vk::Format::R16G16B16A16_SFLOAT
+            | vk::Format::R32G32_SFLOAT => Ok(8),
+            vk::Format::R32G32B32A32_SFLOAT => Ok(16),
+            _ => Err(vk_utils::Error::InvalidImage("unsupported texture format!")),
+        }
+    }
 
-        if image_size <= 0 {
-            return Err(String::from("failed to load texture image!"));
+    // The whole mip chain, or only the base level when `format` can't be blitted with
+    // linear filtering (check_mipmap_support)
+    pub fn texture_mip_levels(
+        instance: &ash::Instance,
+        physical_device: vk::PhysicalDevice,
+        format: vk::Format,
+        tex_width: u32,
+        tex_height: u32,
+    ) -> u32 {
+        match check_mipmap_support(instance, physical_device, format) {
+            Ok(()) => (std::cmp::max(tex_width, tex_height) as f32).log2().floor() as u32 + 1,
+            Err(_) => 1,
+        }
+    }
+
+    // Uploads already decoded pixels, the levels past the first are blitted from it
+    pub fn create_texture_image_from_pixels(
+        device: &ash::Device,
+        command_pool: vk::CommandPool,
+        allocator: &vk_utils::memory::Allocator,
+        pixels: &TexturePixels,
+        mip_levels: u32,
+        graphics_queue: vk::Queue,
+    ) -> vk_utils::Result<vk_utils::image::Image> {
+        let (tex_width, tex_height, format) = (pixels.width, pixels.height, pixels.format);
+        let image_size = tex_width as vk::DeviceSize
+            * tex_height as vk::DeviceSize
+            * texel_size(format)? as vk::DeviceSize;
+
+        if pixels.data.len() as vk::DeviceSize != image_size {
+            return Err(vk_utils::Error::InvalidImage(
+                "pixel data does not match the texture size!",
+            ));
+        }
+
+        if image_size == 0 {
+            return Err(vk_utils::Error::InvalidImage(
+                "failed to load texture image!",
+            ));
         }
 
         // Staging buffer
-        let (staging_buffer, staging_buffer_memory) = vk_utils::buffer::create_buffer(
+        let staging_buffer = vk_utils::buffer::create_buffer(
             device,
+            allocator,
             image_size,
             vk::BufferUsageFlags::TRANSFER_SRC,
             vk::MemoryPropertyFlags::HOST_VISIBLE | vk::MemoryPropertyFlags::HOST_COHERENT,
-            device_memory_properties,
-        );
+        )?;
 
-        let data = unsafe {
-            device
-                .map_memory(
-                    staging_buffer_memory,
-                    0,
-                    image_size,
-                    vk::MemoryMapFlags::empty(),
-                )
-                .expect("failed to map memory!") as *mut u8
-        };
-
-        let image_data = image_data.unwrap();
+        staging_buffer.write(&pixels.data)?;
 
-        unsafe {
-            data.copy_from_nonoverlapping(image_data.as_ptr(), image_data.len());
-            device.unmap_memory(staging_buffer_memory);
-        }
-
-        let (texture_image, texture_image_memory) = vk_utils::image::create_image(
+        let texture_image = vk_utils::image::create_image(
             device,
-            tex_width,
-            tex_height,
-            mip_levels,
-            vk::SampleCountFlags::TYPE_1,
-            vk::Format::R8G8B8A8_SRGB,
-            vk::ImageTiling::OPTIMAL,
-            vk::ImageUsageFlags::TRANSFER_SRC
-                | vk::ImageUsageFlags::TRANSFER_DST
-                | vk::ImageUsageFlags::SAMPLED,
-            vk::MemoryPropertyFlags::DEVICE_LOCAL,
-            device_memory_properties,
-        );
+            allocator,
+            &vk_utils::image::ImageCreateInfo {
+                extent: vk::Extent2D {
+                    width: tex_width,
+                    height: tex_height,
+                },
+                mip_levels,
+                samples: vk::SampleCountFlags::TYPE_1,
+                format,
+                tiling: vk::ImageTiling::OPTIMAL,
+                usage: vk::ImageUsageFlags::TRANSFER_SRC
+                    | vk::ImageUsageFlags::TRANSFER_DST
+                    | vk::ImageUsageFlags::SAMPLED,
+                properties: vk::MemoryPropertyFlags::DEVICE_LOCAL,
+            },
+        )?;
 
         // Preparing the texture image
         vk_utils::image::transition_image_layout(
             device,
             command_pool,
-            texture_image,
-            vk::Format::R8G8B8A8_SRGB,
-            vk::ImageLayout::UNDEFINED,
-            vk::ImageLayout::TRANSFER_DST_OPTIMAL,
             graphics_queue,
-            mip_levels,
-        );
+            &vk_utils::image::LayoutTransition {
+                image: texture_image.handle(),
+                format,
+                old_layout: vk::ImageLayout::UNDEFINED,
+                new_layout: vk::ImageLayout::TRANSFER_DST_OPTIMAL,
+                mip_levels,
+            },
+        )?;
 
         vk_utils::image::copy_buffer_to_image(
             device,
             command_pool,
-            staging_buffer,
-            texture_image,
+            staging_buffer.handle(),
+            texture_image.handle(),
             graphics_queue,
             tex_width,
             tex_height,
-        );
+        )?;
 
-        unsafe {
-            device.destroy_buffer(staging_buffer, None);
-            device.free_memory(staging_buffer_memory, None);
-        }
+        // Staging buffer is no longer needed
+        drop(staging_buffer);
 
         generate_mipmaps(
             device,
             command_pool,
-            texture_image,
+            texture_image.handle(),
             tex_width as i32,
             tex_height as i32,
             mip_levels,
             graphics_queue,
-        );
+        )?;
 
-        Ok((texture_image, texture_image_memory, mip_levels))
+        Ok(texture_image)
     }
 
     pub fn create_texture_image_view(
         device: &ash::Device,
         texture_image: vk::Image,
+        format: vk::Format,
         mip_levels: u32,
-    ) -> vk::ImageView {
+    ) -> vk_utils::Result<vk_utils::image::ImageView> {
         // Texture image view
-        let texture_image_view = vk_utils::swapchain::create_image_view(
+        vk_utils::swapchain::create_image_view(
             device,
             texture_image,
-            vk::Format::R8G8B8A8_SRGB,
+            format,
             vk::ImageAspectFlags::COLOR,
             mip_levels,
-        );
-
-        texture_image_view
+        )
     }
 
+    // Filters linearly when `format` supports it, the nearest texel otherwise (e.g. R32 float)
     pub fn create_texture_sampler(
         instance: &ash::Instance,
         device: &ash::Device,
         physical_device: vk::PhysicalDevice,
+        format: vk::Format,
         mip_levels: u32,
-    ) -> vk::Sampler {
+    ) -> vk_utils::Result<Sampler> {
         // Samplers
         let properties = unsafe { instance.get_physical_device_properties(physical_device) };
+        let (filter, mipmap_mode) = match check_mipmap_support(instance, physical_device, format) {
+            Ok(()) => (vk::Filter::LINEAR, vk::SamplerMipmapMode::LINEAR),
+            Err(_) => (vk::Filter::NEAREST, vk::SamplerMipmapMode::NEAREST),
+        };
         let sampler_info = vk::SamplerCreateInfo::builder()
-            .mag_filter(vk::Filter::LINEAR)
-            .min_filter(vk::Filter::LINEAR)
+            .mag_filter(filter)
+            .min_filter(filter)
             .address_mode_u(vk::SamplerAddressMode::REPEAT)
             .address_mode_v(vk::SamplerAddressMode::REPEAT)
             .address_mode_w(vk::SamplerAddressMode::REPEAT)
@@ -280,16 +665,21 @@ mod _texture {
             .unnormalized_coordinates(false)
             .compare_enable(false)
             .compare_op(vk::CompareOp::ALWAYS)
-            .mipmap_mode(vk::SamplerMipmapMode::LINEAR)
+            .mipmap_mode(mipmap_mode)
             .mip_lod_bias(0.0)
             .min_lod(0.0)
             .max_lod(mip_levels as f32);
 
-        unsafe {
+        let sampler = unsafe {
             device
                 .create_sampler(&sampler_info, None)
-                .expect("failed to create texture sampler!")
-        }
+                .map_err(vk_utils::Error::vulkan("failed to create texture sampler!"))?
+        };
+
+        Ok(Sampler {
+            device: device.clone(),
+            sampler,
+        })
     }
 
     pub fn generate_mipmaps(
@@ -300,12 +690,34 @@ mod _texture {
         tex_height: i32,
         mip_levels: u32,
         graphics_queue: vk::Queue,
-    ) {
+    ) -> vk_utils::Result<()> {
         use crate::command::{begin_single_time_commands, end_single_time_commands};
 
-        let (mut mip_width, mut mip_height) = (tex_width, tex_height);
+        let command_buffer = begin_single_time_commands(device, command_pool)?;
 
-        let command_buffer = begin_single_time_commands(device, command_pool);
+        cmd_generate_mipmaps(
+            device,
+            command_buffer,
+            image,
+            tex_width,
+            tex_height,
+            mip_levels,
+        );
+
+        end_single_time_commands(device, command_pool, command_buffer, graphics_queue)
+    }
+
+    // Records the blits into a command buffer of a graphics queue. All levels start out
+    // in TRANSFER_DST_OPTIMAL and end up in SHADER_READ_ONLY_OPTIMAL
+    pub fn cmd_generate_mipmaps(
+        device: &ash::Device,
+        command_buffer: vk::CommandBuffer,
+        image: vk::Image,
+        tex_width: i32,
+        tex_height: i32,
+        mip_levels: u32,
+    ) {
+        let (mut mip_width, mut mip_height) = (tex_width, tex_height);
 
         let mut barriers = [vk::ImageMemoryBarrier::builder()
             .image(image)
@@ -429,34 +841,146 @@ mod _texture {
                 &barriers,
             );
         }
-
-        end_single_time_commands(device, command_pool, command_buffer, graphics_queue);
     }
 
+    // Mip levels are blitted from one another and sampled with linear filtering.
+    // The error carries the features `image_format` is missing
     pub fn check_mipmap_support(
         instance: &ash::Instance,
         physical_device: vk::PhysicalDevice,
         image_format: vk::Format,
-    ) -> Result<(), String> {
+    ) -> vk_utils::Result<()> {
+        let required = vk::FormatFeatureFlags::SAMPLED_IMAGE
+            | vk::FormatFeatureFlags::SAMPLED_IMAGE_FILTER_LINEAR
+            | vk::FormatFeatureFlags::BLIT_SRC
+            | vk::FormatFeatureFlags::BLIT_DST;
         let format_properties = unsafe {
             instance.get_physical_device_format_properties(physical_device, image_format)
         };
+        let missing = required & !format_properties.optimal_tiling_features;
 
-        if !format_properties
-            .optimal_tiling_features
-            .contains(vk::FormatFeatureFlags::SAMPLED_IMAGE_FILTER_LINEAR)
-        {
-            Err(String::from(
-                "texture image format does not support linear blitting!",
-            ))
+        if !missing.is_empty() {
+            Err(vk_utils::Error::UnsupportedFormat(image_format, missing))
         } else {
             Ok(())
         }
     }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        #[test]
+        fn normal_floats_convert_exactly() {
+            assert_eq!(f32_to_f16(0.0), 0x0000);
+            assert_eq!(f32_to_f16(-0.0), 0x8000);
+            assert_eq!(f32_to_f16(1.0), 0x3c00);
+            assert_eq!(f32_to_f16(-2.0), 0xc000);
+            assert_eq!(f32_to_f16(0.5), 0x3800);
+            assert_eq!(f32_to_f16(65504.0), 0x7bff);
+            assert_eq!(f32_to_f16(6.1035156e-5), 0x0400);
+        }
+
+        #[test]
+        fn small_floats_become_denormals_or_zero() {
+            // Smallest half denormal, 2^-24, and the largest one
+            assert_eq!(f32_to_f16(5.9604645e-8), 0x0001);
+            assert_eq!(f32_to_f16(6.097555e-5), 0x03ff);
+            assert_eq!(f32_to_f16(-5.9604645e-8), 0x8001);
+            // Half of the smallest denormal ties to even (zero), anything past it rounds up
+            assert_eq!(f32_to_f16(2.9802322e-8), 0x0000);
+            assert_eq!(f32_to_f16(2.9802326e-8), 0x0001);
+            assert_eq!(f32_to_f16(1.0e-10), 0x0000);
+            assert_eq!(f32_to_f16(-1.0e-10), 0x8000);
+        }
+
+        #[test]
+        fn large_floats_become_infinity() {
+            assert_eq!(f32_to_f16(f32::INFINITY), 0x7c00);
+            assert_eq!(f32_to_f16(f32::NEG_INFINITY), 0xfc00);
+            assert_eq!(f32_to_f16(65536.0), 0x7c00);
+            assert_eq!(f32_to_f16(-1.0e10), 0xfc00);
+            // 65520 is halfway between 65504 and the next step, which is infinity
+            assert_eq!(f32_to_f16(65519.0), 0x7bff);
+            assert_eq!(f32_to_f16(65520.0), 0x7c00);
+        }
+
+        #[test]
+        fn nan_stays_nan() {
+            let half = f32_to_f16(f32::NAN);
+
+            assert_eq!(half & 0x7c00, 0x7c00);
+            assert_ne!(half & 0x03ff, 0);
+            assert!(f16_to_f32(half).is_nan());
+        }
+
+        #[test]
+        fn mantissas_round_to_nearest_even() {
+            // Halfway between 1.0 and the next half (1 + 2^-10) stays even
+            assert_eq!(f32_to_f16(1.0 + 2.0_f32.powi(-11)), 0x3c00);
+            // Halfway between 1 + 2^-10 and 1 + 2^-9 rounds up to the even one
+            assert_eq!(f32_to_f16(1.0 + 3.0 * 2.0_f32.powi(-11)), 0x3c02);
+            // Just past halfway rounds up
+            assert_eq!(
+                f32_to_f16(1.0 + 2.0_f32.powi(-11) + 2.0_f32.powi(-20)),
+                0x3c01
+            );
+            // Rounding up carries into the exponent
+            assert_eq!(f32_to_f16(2.0 - 2.0_f32.powi(-12)), 0x4000);
+        }
+
+        #[test]
+        fn halves_round_trip() {
+            for half in (0..=u16::MAX).filter(|half| half & 0x7c00 != 0x7c00) {
+                assert_eq!(f32_to_f16(f16_to_f32(half)), half);
+            }
+        }
+
+        #[test]
+        fn wide_pixels_narrow_to_8_bit() {
+            let pixels = |format, data: Vec<u8>| TexturePixels {
+                width: 2,
+                height: 1,
+                format,
+                data,
+            };
+
+            let unorm = to_8_bit(&pixels(
+                vk::Format::R16_UNORM,
+                to_ne_bytes([0xffff_u16, 0x8080], u16::to_ne_bytes),
+            ))
+            .unwrap();
+            assert_eq!(unorm.format, vk::Format::R8_UNORM);
+            assert_eq!(unorm.data, vec![0xff, 0x80]);
+
+            let float = to_8_bit(&pixels(
+                vk::Format::R32_SFLOAT,
+                to_ne_bytes([2.0_f32, 0.5], f32::to_ne_bytes),
+            ))
+            .unwrap();
+            assert_eq!(float.format, vk::Format::R8_UNORM);
+            assert_eq!(float.data, vec![0xff, 0x80]);
+
+            let half = to_8_bit(&pixels(
+                vk::Format::R16G16B16A16_SFLOAT,
+                to_ne_bytes(
+                    [-1.0, 0.0, 0.5, 1.0, 4.0, 0.25, 1.0, 1.0].map(f32_to_f16),
+                    u16::to_ne_bytes,
+                ),
+            ))
+            .unwrap();
+            assert_eq!(half.format, vk::Format::R8G8B8A8_UNORM);
+            assert_eq!(half.data, vec![0, 0, 0x80, 0xff, 0xff, 0x40, 0xff, 0xff]);
+
+            assert!(to_8_bit(&pixels(vk::Format::R8G8B8A8_SRGB, vec![0; 8])).is_none());
+        }
+    }
 }
 
 pub use _texture::{
-    check_mipmap_support, create_descriptor_pool, create_descriptor_set_layout,
-    create_descriptor_sets, create_texture_image, create_texture_image_view,
-    create_texture_sampler, generate_mipmaps,
+    check_mipmap_support, cmd_generate_mipmaps, create_descriptor_pool,
+    create_descriptor_set_layout, create_descriptor_sets, create_texture_image,
+    create_texture_image_from_pixels, create_texture_image_view, create_texture_sampler,
+    generate_mipmaps, load_image_pixels, texel_size, texture_mip_levels, to_supported_format,
+    update_texture_descriptors, DescriptorSetResources, Sampler, TexturePixels, TextureRole,
 };
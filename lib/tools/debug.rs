@@ -1,9 +1,12 @@
 mod _debug {
+    use crate as vk_utils;
     use crate::constants::VK_VALIDATION_LAYER_NAMES;
     use ash::{extensions::ext::DebugUtils, vk, Entry, Instance};
     use std::ffi::CStr;
     use std::os::raw::c_void;
 
+    /// # Safety
+    /// Only to be called by the validation layers, with valid callback data
     pub unsafe extern "system" fn vulkan_debug_callback(
         message_severity: vk::DebugUtilsMessageSeverityFlagsEXT,
         message_type: vk::DebugUtilsMessageTypeFlagsEXT,
@@ -30,15 +33,18 @@ mod _debug {
         vk::FALSE
     }
 
-    pub fn check_validation_layer_support(entry: &Entry) -> bool {
-        let layer_properties = entry
-            .enumerate_instance_layer_properties()
-            .expect("failed to enumerate Instance Layer Properties!");
+    pub fn check_validation_layer_support(entry: &Entry) -> vk_utils::Result<bool> {
+        let layer_properties =
+            entry
+                .enumerate_instance_layer_properties()
+                .map_err(vk_utils::Error::vulkan(
+                    "failed to enumerate Instance Layer Properties!",
+                ))?;
 
-        if layer_properties.len() <= 0 {
+        if layer_properties.is_empty() {
             eprintln!("No available layers.");
 
-            return false;
+            return Ok(false);
         } else {
             println!("Instance Available Layers:");
 
@@ -63,18 +69,18 @@ mod _debug {
             }
 
             if !is_layer_found {
-                return false;
+                return Ok(false);
             }
         }
 
-        true
+        Ok(true)
     }
 
     pub fn setup_debug_callback(
         entry: &Entry,
         instance: &Instance,
-    ) -> (DebugUtils, vk::DebugUtilsMessengerEXT) {
-        let debug_utils_loader = DebugUtils::new(&entry, &instance);
+    ) -> vk_utils::Result<(DebugUtils, vk::DebugUtilsMessengerEXT)> {
+        let debug_utils_loader = DebugUtils::new(entry, instance);
 
         if VK_VALIDATION_LAYER_NAMES.is_enable {
             let debug_info = vk::DebugUtilsMessengerCreateInfoEXT::builder()
@@ -92,12 +98,12 @@ mod _debug {
             let debug_callback = unsafe {
                 debug_utils_loader
                     .create_debug_utils_messenger(&debug_info, None)
-                    .expect("failed to set up debug messenger!")
+                    .map_err(vk_utils::Error::vulkan("failed to set up debug messenger!"))?
             };
 
-            (debug_utils_loader, debug_callback)
+            Ok((debug_utils_loader, debug_callback))
         } else {
-            (debug_utils_loader, vk::DebugUtilsMessengerEXT::null())
+            Ok((debug_utils_loader, vk::DebugUtilsMessengerEXT::null()))
         }
     }
 }
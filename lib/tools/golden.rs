@@ -0,0 +1,214 @@
+mod _golden {
+    use image::{Rgba, RgbaImage};
+    use std::path::Path;
+
+    pub struct ImageComparison {
+        pub mismatched_pixels: usize,
+        pub total_pixels: usize,
+        pub max_channel_difference: u8,
+    }
+
+    impl ImageComparison {
+        pub fn mismatch_ratio(&self) -> f32 {
+            self.mismatched_pixels as f32 / self.total_pixels as f32
+        }
+    }
+
+    pub fn compare_images(
+        actual: &RgbaImage,
+        reference: &RgbaImage,
+        tolerance: u8,
+    ) -> crate::Result<(ImageComparison, RgbaImage)> {
+        if actual.dimensions() != reference.dimensions() {
+            return Err(crate::Error::ImageMismatch(format!(
+                "image size mismatch! (actual: {:?}, reference: {:?})",
+                actual.dimensions(),
+                reference.dimensions()
+            )));
+        }
+
+        let mut comparison = ImageComparison {
+            mismatched_pixels: 0,
+            total_pixels: (actual.width() * actual.height()) as usize,
+            max_channel_difference: 0,
+        };
+
+        // Mismatched pixels are painted red over a dimmed copy of the reference
+        let diff_image = RgbaImage::from_fn(actual.width(), actual.height(), |x, y| {
+            let actual_pixel = actual.get_pixel(x, y);
+            let reference_pixel = reference.get_pixel(x, y);
+            let difference = actual_pixel
+                .0
+                .iter()
+                .zip(reference_pixel.0.iter())
+                .map(|(&a, &b)| a.abs_diff(b))
+                .max()
+                .unwrap_or(0);
+
+            comparison.max_channel_difference =
+                std::cmp::max(comparison.max_channel_difference, difference);
+
+            if difference > tolerance {
+                comparison.mismatched_pixels += 1;
+
+                Rgba([255, 0, 0, 255])
+            } else {
+                let [r, g, b, _] = reference_pixel.0;
+                let luma = ((r as u32 + g as u32 + b as u32) / 3 / 4) as u8;
+
+                Rgba([luma, luma, luma, 255])
+            }
+        });
+
+        Ok((comparison, diff_image))
+    }
+
+    pub fn check_against_reference(
+        actual_pixels: Vec<u8>,
+        width: u32,
+        height: u32,
+        reference_path: &Path,
+        output_dir: &Path,
+        tolerance: u8,
+        max_mismatch_ratio: f32,
+    ) -> crate::Result<ImageComparison> {
+        let actual = RgbaImage::from_raw(width, height, actual_pixels).ok_or(
+            crate::Error::InvalidImage("pixel buffer does not match image size!"),
+        )?;
+        let name = reference_path
+            .file_stem()
+            .and_then(|stem| stem.to_str())
+            .unwrap_or("image");
+
+        // Setting UPDATE_GOLDEN (re)writes the reference from the current output
+        if std::env::var_os("UPDATE_GOLDEN").is_some() || !reference_path.exists() {
+            let was_missing = !reference_path.exists();
+
+            if let Some(parent) = reference_path.parent() {
+                std::fs::create_dir_all(parent)?;
+            }
+            actual.save(reference_path)?;
+
+            if was_missing {
+                return Err(crate::Error::ImageMismatch(format!(
+                    "reference image was missing, wrote {:?}. Review it and commit it.",
+                    reference_path
+                )));
+            }
+        }
+
+        let reference = image::open(reference_path)?.to_rgba8();
+        let (comparison, diff_image) = compare_images(&actual, &reference, tolerance)?;
+
+        if comparison.mismatch_ratio() > max_mismatch_ratio {
+            std::fs::create_dir_all(output_dir)?;
+
+            let actual_path = output_dir.join(format!("{}_actual.png", name));
+            let diff_path = output_dir.join(format!("{}_diff.png", name));
+
+            actual.save(&actual_path)?;
+            diff_image.save(&diff_path)?;
+
+            return Err(crate::Error::ImageMismatch(format!(
+                "{}: {} of {} pixels differ by more than {} (max difference: {}). See {:?}",
+                name,
+                comparison.mismatched_pixels,
+                comparison.total_pixels,
+                tolerance,
+                comparison.max_channel_difference,
+                diff_path
+            )));
+        }
+
+        Ok(comparison)
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        fn solid(width: u32, height: u32, color: [u8; 4]) -> RgbaImage {
+            RgbaImage::from_pixel(width, height, Rgba(color))
+        }
+
+        fn scratch_dir(name: &str) -> std::path::PathBuf {
+            let dir = std::env::temp_dir().join(format!("vk_utils_golden_{}", name));
+
+            let _ = std::fs::remove_dir_all(&dir);
+            std::fs::create_dir_all(&dir).unwrap();
+
+            dir
+        }
+
+        #[test]
+        fn differences_within_tolerance_match() {
+            let reference = solid(4, 4, [100, 100, 100, 255]);
+            let mut actual = reference.clone();
+            actual.put_pixel(1, 2, Rgba([108, 92, 100, 255]));
+
+            let (comparison, _) = compare_images(&actual, &reference, 8).unwrap();
+
+            assert_eq!(comparison.mismatched_pixels, 0);
+            assert_eq!(comparison.total_pixels, 16);
+            assert_eq!(comparison.max_channel_difference, 8);
+        }
+
+        #[test]
+        fn differences_past_tolerance_are_painted_red() {
+            let reference = solid(4, 4, [120, 120, 120, 255]);
+            let mut actual = reference.clone();
+            actual.put_pixel(3, 0, Rgba([120, 120, 129, 255]));
+
+            let (comparison, diff_image) = compare_images(&actual, &reference, 8).unwrap();
+
+            assert_eq!(comparison.mismatched_pixels, 1);
+            assert_eq!(comparison.max_channel_difference, 9);
+            assert_eq!(comparison.mismatch_ratio(), 1.0 / 16.0);
+            assert_eq!(*diff_image.get_pixel(3, 0), Rgba([255, 0, 0, 255]));
+            // The rest is the reference, dimmed to a quarter
+            assert_eq!(*diff_image.get_pixel(0, 0), Rgba([30, 30, 30, 255]));
+        }
+
+        #[test]
+        fn size_mismatch_is_an_error() {
+            let result = compare_images(&solid(4, 4, [0; 4]), &solid(4, 3, [0; 4]), 8);
+
+            assert!(matches!(result, Err(crate::Error::ImageMismatch(_))));
+        }
+
+        #[test]
+        fn missing_reference_is_written_and_fails() {
+            let dir = scratch_dir("missing");
+            let reference_path = dir.join("missing.png");
+            let pixels = solid(2, 2, [10, 20, 30, 255]).into_raw();
+
+            let result =
+                check_against_reference(pixels.clone(), 2, 2, &reference_path, &dir, 8, 0.0);
+
+            assert!(matches!(result, Err(crate::Error::ImageMismatch(_))));
+            assert!(reference_path.exists());
+
+            // From then on the same output matches it
+            let comparison =
+                check_against_reference(pixels, 2, 2, &reference_path, &dir, 8, 0.0).unwrap();
+
+            assert_eq!(comparison.mismatched_pixels, 0);
+        }
+
+        #[test]
+        fn mismatch_writes_actual_and_diff_images() {
+            let dir = scratch_dir("mismatch");
+            let reference_path = dir.join("mismatch.png");
+            solid(2, 2, [0, 0, 0, 255]).save(&reference_path).unwrap();
+
+            let pixels = solid(2, 2, [255, 255, 255, 255]).into_raw();
+            let result = check_against_reference(pixels, 2, 2, &reference_path, &dir, 8, 0.5);
+
+            assert!(matches!(result, Err(crate::Error::ImageMismatch(_))));
+            assert!(dir.join("mismatch_actual.png").exists());
+            assert!(dir.join("mismatch_diff.png").exists());
+        }
+    }
+}
+
+pub use _golden::{check_against_reference, compare_images, ImageComparison};
@@ -1,4 +1,5 @@
 pub mod debug;
+pub mod golden;
 
 pub fn vk_to_string(raw_string_array: &[std::os::raw::c_char]) -> String {
     use std::ffi::CStr;
@@ -9,20 +10,13 @@ pub fn vk_to_string(raw_string_array: &[std::os::raw::c_char]) -> String {
         CStr::from_ptr(pointer)
     };
 
-    raw_string
-        .to_str()
-        .expect("Failed to convert raw_char_array to String.")
-        .to_owned()
+    raw_string.to_string_lossy().into_owned()
 }
 
-pub fn read_shader_code(shader_path: &std::path::Path) -> Vec<u8> {
-    use std::fs::File;
-    use std::io::Read;
+// SPIR-V words, also fixes up the alignment and endianness of the file contents
+pub fn read_shader_code(shader_path: &std::path::Path) -> crate::Result<Vec<u32>> {
+    let mut file = std::fs::File::open(shader_path)?;
+    let code = ash::util::read_spv(&mut file)?;
 
-    let spv_file =
-        File::open(shader_path).expect(&format!("Failed to find spv file at {:?}", shader_path));
-
-    let byte_codes: Vec<u8> = spv_file.bytes().filter_map(|byte| byte.ok()).collect();
-
-    byte_codes
+    Ok(code)
 }
@@ -0,0 +1,13 @@
+// Per-frame part of the transform, the model matrix is pushed per draw (see DrawPushConstants)
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub struct CameraUniformObject {
+    pub view: cgmath::Matrix4<f32>,
+    pub proj: cgmath::Matrix4<f32>,
+}
+
+impl CameraUniformObject {
+    pub fn new(view: cgmath::Matrix4<f32>, proj: cgmath::Matrix4<f32>) -> Self {
+        Self { view, proj }
+    }
+}
@@ -0,0 +1,16 @@
+// Matches the push_constant block of the vertex shader (68 of the guaranteed 128 bytes)
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub struct DrawPushConstants {
+    pub model: cgmath::Matrix4<f32>,
+    pub material_index: u32,
+}
+
+impl DrawPushConstants {
+    pub fn new(model: cgmath::Matrix4<f32>, material_index: u32) -> Self {
+        Self {
+            model,
+            material_index,
+        }
+    }
+}
@@ -0,0 +1,57 @@
+use crate::attributes::InstanceData;
+
+// Placement and color of one instance (4 locations for the matrix columns, then the tint)
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub struct InstanceTransform {
+    pub model: [[f32; 4]; 4], // column major, like cgmath
+    pub tint: [f32; 4],       // multiplied with the sampled color
+}
+
+impl InstanceTransform {
+    #[inline]
+    pub fn new(model: cgmath::Matrix4<f32>, tint: [f32; 4]) -> Self {
+        Self {
+            model: model.into(),
+            tint,
+        }
+    }
+}
+
+impl InstanceData for InstanceTransform {
+    fn get_binding_description() -> Vec<ash::vk::VertexInputBindingDescription> {
+        use ash::vk;
+        use std::mem::size_of;
+
+        vec![vk::VertexInputBindingDescription::builder()
+            .binding(1)
+            .stride(size_of::<Self>() as u32)
+            .input_rate(vk::VertexInputRate::INSTANCE)
+            .build()]
+    }
+
+    fn get_attribute_descriptions(
+        first_location: u32,
+    ) -> Vec<ash::vk::VertexInputAttributeDescription> {
+        use ash::vk;
+        use memoffset::offset_of;
+        use std::mem::size_of;
+
+        let model_columns = (0..4).map(|column| {
+            vk::VertexInputAttributeDescription::builder()
+                .binding(1)
+                .location(first_location + column)
+                .format(vk::Format::R32G32B32A32_SFLOAT)
+                .offset((offset_of!(Self, model) + column as usize * size_of::<[f32; 4]>()) as u32)
+                .build()
+        });
+        let tint = vk::VertexInputAttributeDescription::builder()
+            .binding(1)
+            .location(first_location + 4)
+            .format(vk::Format::R32G32B32A32_SFLOAT)
+            .offset(offset_of!(Self, tint) as u32)
+            .build();
+
+        model_columns.chain(std::iter::once(tint)).collect()
+    }
+}
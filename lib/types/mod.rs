@@ -1,12 +1,20 @@
+mod camera_uniform_object;
+mod draw_push_constants;
+mod instance_transform;
 mod uniform_buffer_object;
 mod vertex2d;
 mod vertex_with_texture2d;
 mod vertex_with_texture3d;
 
 pub use uniform_buffer_object::UniformBufferObject;
+// Per-frame view/projection and per-draw model transform
+pub use camera_uniform_object::CameraUniformObject;
+pub use draw_push_constants::DrawPushConstants;
 
 // 2D vertices
 pub use vertex2d::Vertex2D;
 pub use vertex_with_texture2d::VertexWithTexture2D;
 // 3D vertices
 pub use vertex_with_texture3d::VertexWithTexture3D;
+// Per-instance data
+pub use instance_transform::InstanceTransform;
@@ -17,38 +17,36 @@ impl UniformBufferObject {
 
     pub fn create_uniform_buffer(
         device: &ash::Device,
-        device_memory_properties: &ash::vk::PhysicalDeviceMemoryProperties,
+        allocator: &crate::memory::Allocator,
         swapchain_image_size: usize,
-    ) -> (Vec<ash::vk::Buffer>, Vec<ash::vk::DeviceMemory>) {
+    ) -> crate::Result<Vec<crate::buffer::Buffer>> {
         // Uniform buffer
         use std::mem::size_of;
 
         let buffer_size = size_of::<Self>();
 
-        let mut uniform_buffers: Vec<ash::vk::Buffer> = Vec::new();
-        let mut uniform_buffers_memory: Vec<ash::vk::DeviceMemory> = Vec::new();
+        let mut uniform_buffers: Vec<crate::buffer::Buffer> = Vec::new();
 
         for _ in 0..swapchain_image_size {
-            let (uniform_buffer, uniform_buffer_memory) = crate::buffer::create_buffer(
+            let uniform_buffer = crate::buffer::create_buffer(
                 device,
+                allocator,
                 buffer_size as u64,
                 ash::vk::BufferUsageFlags::UNIFORM_BUFFER,
                 ash::vk::MemoryPropertyFlags::HOST_VISIBLE
                     | ash::vk::MemoryPropertyFlags::HOST_COHERENT,
-                device_memory_properties,
-            );
+            )?;
 
             uniform_buffers.push(uniform_buffer);
-            uniform_buffers_memory.push(uniform_buffer_memory);
         }
 
-        (uniform_buffers, uniform_buffers_memory)
+        Ok(uniform_buffers)
     }
 
     pub fn create_descriptor_pool(
         device: &ash::Device,
         swapchain_image_size: usize,
-    ) -> ash::vk::DescriptorPool {
+    ) -> crate::Result<ash::vk::DescriptorPool> {
         // Descriptor pool
         let pool_sizes = [ash::vk::DescriptorPoolSize::builder()
             .ty(ash::vk::DescriptorType::UNIFORM_BUFFER)
@@ -62,7 +60,7 @@ impl UniformBufferObject {
         unsafe {
             device
                 .create_descriptor_pool(&pool_info, None)
-                .expect("failed to create descriptor pool!")
+                .map_err(crate::Error::vulkan("failed to create descriptor pool!"))
         }
     }
 
@@ -70,9 +68,9 @@ impl UniformBufferObject {
         device: &ash::Device,
         descriptor_pool: ash::vk::DescriptorPool,
         descriptor_set_layout: ash::vk::DescriptorSetLayout,
-        uniform_buffers: &Vec<ash::vk::Buffer>,
+        uniform_buffers: &[crate::buffer::Buffer],
         swapchain_image_size: usize,
-    ) -> Vec<ash::vk::DescriptorSet> {
+    ) -> crate::Result<Vec<ash::vk::DescriptorSet>> {
         // Descriptor set
         use std::mem::size_of;
 
@@ -89,12 +87,12 @@ impl UniformBufferObject {
         let descriptor_sets = unsafe {
             device
                 .allocate_descriptor_sets(&alloc_info)
-                .expect("failed to allocate descriptor sets!")
+                .map_err(crate::Error::vulkan("failed to allocate descriptor sets!"))?
         };
 
         for (i, &descriptor_set) in descriptor_sets.iter().enumerate() {
             let buffer_infos = [ash::vk::DescriptorBufferInfo::builder()
-                .buffer(uniform_buffers[i])
+                .buffer(uniform_buffers[i].handle())
                 .offset(0)
                 .range(size_of::<Self>() as u64)
                 .build()];
@@ -112,6 +110,6 @@ impl UniformBufferObject {
             }
         }
 
-        descriptor_sets
+        Ok(descriptor_sets)
     }
 }
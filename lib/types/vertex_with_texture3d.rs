@@ -1,4 +1,4 @@
-use crate::attributes::{Pipeline, Vertex};
+use crate::attributes::Vertex;
 
 #[repr(C)]
 #[derive(Clone, Copy)]
@@ -7,6 +7,7 @@ pub struct VertexWithTexture3D {
     pub pos: [f32; 4],       // position for each point
     pub color: [f32; 3],     // color (R, G, B)
     pub tex_coord: [f32; 2], // coordinate for texture
+    pub normal: [f32; 3],    // surface normal
 }
 
 impl VertexWithTexture3D {
@@ -16,13 +17,43 @@ impl VertexWithTexture3D {
             pos,
             color,
             tex_coord: texture_coordinate,
+            normal: [0.0, 0.0, 0.0],
         }
     }
+
+    #[inline]
+    pub fn with_normal(mut self, normal: [f32; 3]) -> Self {
+        self.normal = normal;
+        self
+    }
+
+    fn components(&self) -> impl Iterator<Item = &f32> {
+        self.pos
+            .iter()
+            .chain(self.color.iter())
+            .chain(self.tex_coord.iter())
+            .chain(self.normal.iter())
+    }
 }
 
 impl PartialEq for VertexWithTexture3D {
     fn eq(&self, other: &Self) -> bool {
-        self.pos == other.pos && self.color == other.color && self.tex_coord == other.tex_coord
+        self.pos == other.pos
+            && self.color == other.color
+            && self.tex_coord == other.tex_coord
+            && self.normal == other.normal
+    }
+}
+
+// Vertex deduplication (model loading), vertices are never NaN there
+impl Eq for VertexWithTexture3D {}
+
+impl std::hash::Hash for VertexWithTexture3D {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        for component in self.components() {
+            // 0.0 and -0.0 compare equal, so they have to hash the same
+            (component + 0.0).to_bits().hash(state);
+        }
     }
 }
 
@@ -62,167 +93,52 @@ impl Vertex for VertexWithTexture3D {
                 .format(vk::Format::R32G32_SFLOAT)
                 .offset(offset_of!(Self, tex_coord) as u32)
                 .build(),
+            vk::VertexInputAttributeDescription::builder()
+                .binding(0)
+                .location(3)
+                .format(vk::Format::R32G32B32_SFLOAT)
+                .offset(offset_of!(Self, normal) as u32)
+                .build(),
         ]
     }
 }
 
-impl Pipeline for VertexWithTexture3D {
-    fn create_graphics_pipeline(
-        device: &ash::Device,
-        msaa_samples: ash::vk::SampleCountFlags,
-        swapchain_extent: ash::vk::Extent2D,
-        render_pass: ash::vk::RenderPass,
-        descriptor_set_layout: ash::vk::DescriptorSetLayout,
-    ) -> (ash::vk::Pipeline, ash::vk::PipelineLayout) {
-        use ash::vk;
-        use std::ffi::CString;
-        use std::path::Path;
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::hash_map::DefaultHasher;
+    use std::hash::{Hash, Hasher};
+
+    fn hash_of(vertex: &VertexWithTexture3D) -> u64 {
+        let mut hasher = DefaultHasher::new();
 
-        let vert_shader_code =
-            crate::tools::read_shader_code(Path::new("shaders/spv/hello-triangle_vert.spv"));
-        let frag_shader_code =
-            crate::tools::read_shader_code(Path::new("shaders/spv/hello-triangle_frag.spv"));
+        vertex.hash(&mut hasher);
+        hasher.finish()
+    }
 
-        let vert_shader_module = crate::pipeline::create_shader_module(device, &vert_shader_code);
-        let frag_shader_module = crate::pipeline::create_shader_module(device, &frag_shader_code);
+    #[test]
+    fn negative_zero_equals_and_hashes_like_zero() {
+        let vertex = VertexWithTexture3D::new([0.0, 1.0, 0.0, 1.0], [1.0; 3], [0.0, 0.5])
+            .with_normal([0.0, 0.0, 1.0]);
+        let negative = VertexWithTexture3D::new([-0.0, 1.0, -0.0, 1.0], [1.0; 3], [-0.0, 0.5])
+            .with_normal([-0.0, -0.0, 1.0]);
 
-        let main_function_name = CString::new("main").unwrap();
+        assert!(vertex == negative);
+        assert_eq!(hash_of(&vertex), hash_of(&negative));
+    }
 
-        let shader_stages = [
-            // Vertex shader
-            vk::PipelineShaderStageCreateInfo::builder()
-                .stage(vk::ShaderStageFlags::VERTEX)
-                .module(vert_shader_module)
-                .name(&main_function_name)
-                .build(),
-            // Fragment shader
-            vk::PipelineShaderStageCreateInfo::builder()
-                .stage(vk::ShaderStageFlags::FRAGMENT)
-                .module(frag_shader_module)
-                .name(&main_function_name)
-                .build(),
-        ];
-
-        // Dynamic State
-        let dynamic_states = [vk::DynamicState::VIEWPORT, vk::DynamicState::SCISSOR];
-        let dynamic_state =
-            vk::PipelineDynamicStateCreateInfo::builder().dynamic_states(&dynamic_states);
-
-        // Pipeline vertex input
-        let binding_descriptions = Self::get_binding_description();
-        let attribute_descriptions = Self::get_attribute_descriptions();
-
-        // Vertex input
-        let vertex_input_info = vk::PipelineVertexInputStateCreateInfo::builder()
-            .vertex_binding_descriptions(&binding_descriptions)
-            .vertex_attribute_descriptions(&attribute_descriptions);
-
-        // Input assembly
-        let input_assembly = vk::PipelineInputAssemblyStateCreateInfo::builder()
-            .topology(vk::PrimitiveTopology::TRIANGLE_LIST)
-            .primitive_restart_enable(false);
-
-        // Viewports and scissors
-        /*
-        let viewports = [vk::Viewport::builder()
-            .x(0.0)
-            .y(0.0)
-            .width(swapchain_extent.width as f32)
-            .height(swapchain_extent.height as f32)
-            .min_depth(0.0)
-            .max_depth(1.0)
-            .build()];
-        let scissors = [vk::Rect2D::builder()
-            .offset(vk::Offset2D { x: 0, y: 0 })
-            .extent(swapchain_extent)
-            .build()];
-         */
-        let viewport_state = vk::PipelineViewportStateCreateInfo::builder();
-
-        // Rasterizer
-        let rasterizer = vk::PipelineRasterizationStateCreateInfo::builder()
-            .depth_clamp_enable(false)
-            .rasterizer_discard_enable(false)
-            .polygon_mode(vk::PolygonMode::FILL)
-            .line_width(1.0)
-            .cull_mode(vk::CullModeFlags::BACK)
-            .front_face(vk::FrontFace::COUNTER_CLOCKWISE)
-            .depth_bias_enable(false);
-
-        // Multisampling
-        let multisampling = vk::PipelineMultisampleStateCreateInfo::builder()
-            .sample_shading_enable(false)
-            .rasterization_samples(msaa_samples)
-            .min_sample_shading(0.2) // Optional
-            .alpha_to_coverage_enable(false) // Optional
-            .alpha_to_one_enable(false); // Optional
-
-        // Depth and stencil testing
-        // Skipping...
-
-        // Color blending
-        let color_blend_attachments = [vk::PipelineColorBlendAttachmentState::builder()
-            .color_write_mask(vk::ColorComponentFlags::RGBA)
-            .blend_enable(false)
-            .src_color_blend_factor(vk::BlendFactor::ONE) // Optional
-            .dst_color_blend_factor(vk::BlendFactor::ZERO) // Optional
-            .color_blend_op(vk::BlendOp::ADD) // Optional
-            .src_alpha_blend_factor(vk::BlendFactor::ONE) // Optional
-            .dst_alpha_blend_factor(vk::BlendFactor::ZERO) // Optional
-            .alpha_blend_op(vk::BlendOp::ADD) // Optional
-            .build()];
-        let color_blending = vk::PipelineColorBlendStateCreateInfo::builder()
-            .logic_op_enable(false)
-            .logic_op(vk::LogicOp::COPY) // Optional
-            .attachments(&color_blend_attachments)
-            .blend_constants([0.0, 0.0, 0.0, 0.0]); // Optional
-
-        // Pipeline layout
-        let set_layouts = [descriptor_set_layout];
-        let pipeline_layout_info =
-            vk::PipelineLayoutCreateInfo::builder().set_layouts(&set_layouts);
-        let pipeline_layout = unsafe {
-            device
-                .create_pipeline_layout(&pipeline_layout_info, None)
-                .expect("failed to create pipeline layout!")
-        };
-
-        // Depth and stencil state
-        let depth_stencil = vk::PipelineDepthStencilStateCreateInfo::builder()
-            .depth_test_enable(true)
-            .depth_write_enable(true)
-            .depth_compare_op(vk::CompareOp::LESS)
-            .depth_bounds_test_enable(false)
-            .min_depth_bounds(0.0) // Optional
-            .max_depth_bounds(1.0) // Optional
-            .stencil_test_enable(false);
-
-        // Conclusion
-        let pipeline_infos = [vk::GraphicsPipelineCreateInfo::builder()
-            .stages(&shader_stages)
-            .vertex_input_state(&vertex_input_info)
-            .input_assembly_state(&input_assembly)
-            .viewport_state(&viewport_state)
-            .rasterization_state(&rasterizer)
-            .multisample_state(&multisampling)
-            .color_blend_state(&color_blending)
-            .dynamic_state(&dynamic_state)
-            .layout(pipeline_layout)
-            .render_pass(render_pass)
-            .subpass(0)
-            .depth_stencil_state(&depth_stencil)
-            .build()];
-        let graphics_pipeline = unsafe {
-            device
-                .create_graphics_pipelines(vk::PipelineCache::null(), &pipeline_infos, None)
-                .expect("failed to create graphics pipeline!")
-        };
-
-        unsafe {
-            device.destroy_shader_module(vert_shader_module, None);
-            device.destroy_shader_module(frag_shader_module, None);
+    #[test]
+    fn every_component_takes_part() {
+        let vertex = VertexWithTexture3D::new([0.0, 0.0, 0.0, 1.0], [1.0; 3], [0.0, 0.0]);
+
+        for other in [
+            VertexWithTexture3D::new([0.0, 0.0, 0.5, 1.0], [1.0; 3], [0.0, 0.0]),
+            VertexWithTexture3D::new([0.0, 0.0, 0.0, 1.0], [1.0, 0.5, 1.0], [0.0, 0.0]),
+            VertexWithTexture3D::new([0.0, 0.0, 0.0, 1.0], [1.0; 3], [0.0, 0.5]),
+            vertex.with_normal([0.0, 1.0, 0.0]),
+        ] {
+            assert!(vertex != other);
+            assert_ne!(hash_of(&vertex), hash_of(&other));
         }
-
-        (graphics_pipeline[0], pipeline_layout)
     }
 }
@@ -0,0 +1,598 @@
+mod _upload {
+    use crate::{
+        self as vk_utils,
+        buffer::Buffer,
+        framebuffer::{Fence, Semaphore},
+        image::Image,
+        texture::TexturePixels,
+    };
+
+    use ash::vk;
+    use std::{cell::RefCell, collections::VecDeque};
+
+    // Identifies one submission of the upload manager. 0 is never submitted, so it is always complete
+    pub type UploadBatchId = u64;
+
+    // Command buffers collecting the copies of the next submission
+    struct Recording {
+        transfer_command_buffer: vk::CommandBuffer,
+        // Ownership acquires and mipmap blits, only when the transfer family isn't the graphics family
+        graphics_command_buffer: Option<vk::CommandBuffer>,
+        staging_buffers: Vec<Buffer>,
+    }
+
+    impl Recording {
+        // Where work needing a graphics queue goes
+        fn graphics_command_buffer(&self) -> vk::CommandBuffer {
+            self.graphics_command_buffer
+                .unwrap_or(self.transfer_command_buffer)
+        }
+    }
+
+    // Submitted, the staging buffers stay alive until the fence signals
+    struct PendingBatch {
+        id: UploadBatchId,
+        transfer_command_buffer: vk::CommandBuffer,
+        graphics_command_buffer: Option<vk::CommandBuffer>,
+        fence: Fence,
+        _transfer_finished: Option<Semaphore>,
+        _staging_buffers: Vec<Buffer>,
+    }
+
+    struct UploadState {
+        recording: Option<Recording>,
+        pending: VecDeque<PendingBatch>,
+        last_submitted: UploadBatchId,
+    }
+
+    // Batches staging copies into one submission on the transfer queue instead of
+    // waiting for the queue to go idle after every copy.
+    // Uploaded buffers and images can be used by any submission to the graphics queue made
+    // after the batch is submitted, the GPU waits for the copies. Ownership only goes to the
+    // graphics family, so a compute queue of another family has to acquire them itself.
+    // Only the staging memory lingers until `collect` sees the batch's fence signaled
+    pub struct UploadManager {
+        device: ash::Device,
+        transfer_family: u32,
+        graphics_family: u32,
+        transfer_queue: vk::Queue,
+        graphics_queue: vk::Queue,
+        transfer_command_pool: vk::CommandPool,
+        graphics_command_pool: vk::CommandPool,
+        state: RefCell<UploadState>,
+    }
+
+    impl UploadManager {
+        pub fn new(
+            device: &ash::Device,
+            queue_families: &vk_utils::QueueFamilyIndices,
+            transfer_queue: vk::Queue,
+            graphics_queue: vk::Queue,
+        ) -> vk_utils::Result<Self> {
+            let graphics_family = queue_families.graphics_family.unwrap();
+
+            // Owns everything from here on, so Drop cleans up after failures
+            let mut upload_manager = Self {
+                device: device.clone(),
+                transfer_family: queue_families.transfer_family.unwrap_or(graphics_family),
+                graphics_family,
+                transfer_queue,
+                graphics_queue,
+                transfer_command_pool: vk::CommandPool::null(),
+                graphics_command_pool: vk::CommandPool::null(),
+                state: RefCell::new(UploadState {
+                    recording: None,
+                    pending: VecDeque::new(),
+                    last_submitted: 0,
+                }),
+            };
+
+            upload_manager.transfer_command_pool =
+                vk_utils::command::create_command_pool_for_family(
+                    device,
+                    upload_manager.transfer_family,
+                )?;
+            if upload_manager.has_ownership_transfer() {
+                upload_manager.graphics_command_pool =
+                    vk_utils::command::create_command_pool_for_family(device, graphics_family)?;
+            }
+
+            Ok(upload_manager)
+        }
+
+        // Resources created with exclusive sharing belong to one queue family at a time
+        pub fn has_ownership_transfer(&self) -> bool {
+            self.transfer_family != self.graphics_family
+        }
+
+        fn recording<'a>(&self, state: &'a mut UploadState) -> vk_utils::Result<&'a mut Recording> {
+            use vk_utils::command::begin_single_time_commands;
+
+            if state.recording.is_none() {
+                let transfer_command_buffer =
+                    begin_single_time_commands(&self.device, self.transfer_command_pool)?;
+                let graphics_command_buffer = if self.has_ownership_transfer() {
+                    match begin_single_time_commands(&self.device, self.graphics_command_pool) {
+                        Ok(command_buffer) => Some(command_buffer),
+                        Err(err) => {
+                            unsafe {
+                                self.device.free_command_buffers(
+                                    self.transfer_command_pool,
+                                    &[transfer_command_buffer],
+                                );
+                            }
+
+                            return Err(err);
+                        }
+                    }
+                } else {
+                    None
+                };
+
+                state.recording = Some(Recording {
+                    transfer_command_buffer,
+                    graphics_command_buffer,
+                    staging_buffers: Vec::new(),
+                });
+            }
+
+            Ok(state.recording.as_mut().unwrap())
+        }
+
+        fn create_staging_buffer<T: Copy>(
+            &self,
+            allocator: &vk_utils::memory::Allocator,
+            data: &[T],
+        ) -> vk_utils::Result<Buffer> {
+            let staging_buffer = vk_utils::buffer::create_buffer(
+                &self.device,
+                allocator,
+                std::mem::size_of_val(data) as vk::DeviceSize,
+                vk::BufferUsageFlags::TRANSFER_SRC,
+                vk::MemoryPropertyFlags::HOST_VISIBLE | vk::MemoryPropertyFlags::HOST_COHERENT,
+            )?;
+
+            staging_buffer.write(data)?;
+
+            Ok(staging_buffer)
+        }
+
+        // Device local buffer with `usage` (vertex, index, storage...), filled by the next batch
+        pub fn upload_buffer<T: Copy>(
+            &self,
+            allocator: &vk_utils::memory::Allocator,
+            data: &[T],
+            usage: vk::BufferUsageFlags,
+        ) -> vk_utils::Result<Buffer> {
+            let buffer_size = std::mem::size_of_val(data) as vk::DeviceSize;
+            let staging_buffer = self.create_staging_buffer(allocator, data)?;
+            let buffer = vk_utils::buffer::create_buffer(
+                &self.device,
+                allocator,
+                buffer_size,
+                usage | vk::BufferUsageFlags::TRANSFER_DST,
+                vk::MemoryPropertyFlags::DEVICE_LOCAL,
+            )?;
+
+            let mut state = self.state.borrow_mut();
+            let recording = self.recording(&mut state)?;
+
+            let copy_regions = [vk::BufferCopy::builder()
+                .src_offset(0)
+                .dst_offset(0)
+                .size(buffer_size)
+                .build()];
+
+            unsafe {
+                self.device.cmd_copy_buffer(
+                    recording.transfer_command_buffer,
+                    staging_buffer.handle(),
+                    buffer.handle(),
+                    &copy_regions,
+                );
+            }
+
+            // Whatever a draw or dispatch might read the buffer as
+            let (dst_stage, dst_access_mask) = (
+                vk::PipelineStageFlags::VERTEX_INPUT
+                    | vk::PipelineStageFlags::VERTEX_SHADER
+                    | vk::PipelineStageFlags::FRAGMENT_SHADER
+                    | vk::PipelineStageFlags::COMPUTE_SHADER,
+                vk::AccessFlags::VERTEX_ATTRIBUTE_READ
+                    | vk::AccessFlags::INDEX_READ
+                    | vk::AccessFlags::UNIFORM_READ
+                    | vk::AccessFlags::SHADER_READ,
+            );
+            let barrier = vk::BufferMemoryBarrier::builder()
+                .buffer(buffer.handle())
+                .offset(0)
+                .size(vk::WHOLE_SIZE)
+                .src_queue_family_index(vk::QUEUE_FAMILY_IGNORED)
+                .dst_queue_family_index(vk::QUEUE_FAMILY_IGNORED)
+                .build();
+
+            if self.has_ownership_transfer() {
+                let barrier = vk::BufferMemoryBarrier {
+                    src_queue_family_index: self.transfer_family,
+                    dst_queue_family_index: self.graphics_family,
+                    ..barrier
+                };
+                // Released by the transfer queue...
+                let release_barriers = [vk::BufferMemoryBarrier {
+                    src_access_mask: vk::AccessFlags::TRANSFER_WRITE,
+                    ..barrier
+                }];
+                // ...and acquired by the graphics queue with matching families
+                let acquire_barriers = [vk::BufferMemoryBarrier {
+                    dst_access_mask,
+                    ..barrier
+                }];
+
+                unsafe {
+                    self.device.cmd_pipeline_barrier(
+                        recording.transfer_command_buffer,
+                        vk::PipelineStageFlags::TRANSFER,
+                        vk::PipelineStageFlags::BOTTOM_OF_PIPE,
+                        vk::DependencyFlags::empty(),
+                        &[],
+                        &release_barriers,
+                        &[],
+                    );
+                    self.device.cmd_pipeline_barrier(
+                        recording.graphics_command_buffer(),
+                        vk::PipelineStageFlags::TOP_OF_PIPE,
+                        dst_stage,
+                        vk::DependencyFlags::empty(),
+                        &[],
+                        &acquire_barriers,
+                        &[],
+                    );
+                }
+            } else {
+                let barriers = [vk::BufferMemoryBarrier {
+                    src_access_mask: vk::AccessFlags::TRANSFER_WRITE,
+                    dst_access_mask,
+                    ..barrier
+                }];
+
+                unsafe {
+                    self.device.cmd_pipeline_barrier(
+                        recording.transfer_command_buffer,
+                        vk::PipelineStageFlags::TRANSFER,
+                        dst_stage,
+                        vk::DependencyFlags::empty(),
+                        &[],
+                        &barriers,
+                        &[],
+                    );
+                }
+            }
+
+            recording.staging_buffers.push(staging_buffer);
+
+            Ok(buffer)
+        }
+
+        // Sampled image in SHADER_READ_ONLY_OPTIMAL once the batch is done, in the format of
+        // `pixels`. Levels past the first (texture::texture_mip_levels) are blitted on the
+        // graphics queue
+        pub fn upload_texture(
+            &self,
+            allocator: &vk_utils::memory::Allocator,
+            pixels: &TexturePixels,
+            mip_levels: u32,
+        ) -> vk_utils::Result<Image> {
+            let (tex_width, tex_height, format) = (pixels.width, pixels.height, pixels.format);
+            let image_size = tex_width as vk::DeviceSize
+                * tex_height as vk::DeviceSize
+                * vk_utils::texture::texel_size(format)? as vk::DeviceSize;
+
+            if pixels.data.len() as vk::DeviceSize != image_size {
+                return Err(vk_utils::Error::InvalidImage(
+                    "pixel data does not match the texture size!",
+                ));
+            }
+
+            if image_size == 0 {
+                return Err(vk_utils::Error::InvalidImage(
+                    "failed to load texture image!",
+                ));
+            }
+
+            let staging_buffer = self.create_staging_buffer(allocator, &pixels.data)?;
+            let texture_image = vk_utils::image::create_image(
+                &self.device,
+                allocator,
+                &vk_utils::image::ImageCreateInfo {
+                    extent: vk::Extent2D {
+                        width: tex_width,
+                        height: tex_height,
+                    },
+                    mip_levels,
+                    samples: vk::SampleCountFlags::TYPE_1,
+                    format,
+                    tiling: vk::ImageTiling::OPTIMAL,
+                    usage: vk::ImageUsageFlags::TRANSFER_SRC
+                        | vk::ImageUsageFlags::TRANSFER_DST
+                        | vk::ImageUsageFlags::SAMPLED,
+                    properties: vk::MemoryPropertyFlags::DEVICE_LOCAL,
+                },
+            )?;
+
+            let mut state = self.state.borrow_mut();
+            let recording = self.recording(&mut state)?;
+
+            let barrier = vk::ImageMemoryBarrier::builder()
+                .image(texture_image.handle())
+                .subresource_range(vk::ImageSubresourceRange {
+                    aspect_mask: vk::ImageAspectFlags::COLOR,
+                    base_mip_level: 0,
+                    level_count: mip_levels,
+                    base_array_layer: 0,
+                    layer_count: 1,
+                })
+                .src_queue_family_index(vk::QUEUE_FAMILY_IGNORED)
+                .dst_queue_family_index(vk::QUEUE_FAMILY_IGNORED)
+                .build();
+
+            // Every level is written, either by the copy or by a blit
+            let to_transfer_dst_barriers = [vk::ImageMemoryBarrier {
+                old_layout: vk::ImageLayout::UNDEFINED,
+                new_layout: vk::ImageLayout::TRANSFER_DST_OPTIMAL,
+                dst_access_mask: vk::AccessFlags::TRANSFER_WRITE,
+                ..barrier
+            }];
+
+            unsafe {
+                self.device.cmd_pipeline_barrier(
+                    recording.transfer_command_buffer,
+                    vk::PipelineStageFlags::TOP_OF_PIPE,
+                    vk::PipelineStageFlags::TRANSFER,
+                    vk::DependencyFlags::empty(),
+                    &[],
+                    &[],
+                    &to_transfer_dst_barriers,
+                );
+            }
+
+            vk_utils::image::cmd_copy_buffer_to_image(
+                &self.device,
+                recording.transfer_command_buffer,
+                staging_buffer.handle(),
+                texture_image.handle(),
+                tex_width,
+                tex_height,
+            );
+
+            if self.has_ownership_transfer() {
+                // Same layout on both sides, the blits continue from TRANSFER_DST_OPTIMAL
+                let barrier = vk::ImageMemoryBarrier {
+                    old_layout: vk::ImageLayout::TRANSFER_DST_OPTIMAL,
+                    new_layout: vk::ImageLayout::TRANSFER_DST_OPTIMAL,
+                    src_queue_family_index: self.transfer_family,
+                    dst_queue_family_index: self.graphics_family,
+                    ..barrier
+                };
+                let release_barriers = [vk::ImageMemoryBarrier {
+                    src_access_mask: vk::AccessFlags::TRANSFER_WRITE,
+                    ..barrier
+                }];
+                let acquire_barriers = [vk::ImageMemoryBarrier {
+                    dst_access_mask: vk::AccessFlags::TRANSFER_READ
+                        | vk::AccessFlags::TRANSFER_WRITE,
+                    ..barrier
+                }];
+
+                unsafe {
+                    self.device.cmd_pipeline_barrier(
+                        recording.transfer_command_buffer,
+                        vk::PipelineStageFlags::TRANSFER,
+                        vk::PipelineStageFlags::BOTTOM_OF_PIPE,
+                        vk::DependencyFlags::empty(),
+                        &[],
+                        &[],
+                        &release_barriers,
+                    );
+                    self.device.cmd_pipeline_barrier(
+                        recording.graphics_command_buffer(),
+                        vk::PipelineStageFlags::TOP_OF_PIPE,
+                        vk::PipelineStageFlags::TRANSFER,
+                        vk::DependencyFlags::empty(),
+                        &[],
+                        &[],
+                        &acquire_barriers,
+                    );
+                }
+            }
+
+            vk_utils::texture::cmd_generate_mipmaps(
+                &self.device,
+                recording.graphics_command_buffer(),
+                texture_image.handle(),
+                tex_width as i32,
+                tex_height as i32,
+                mip_levels,
+            );
+
+            recording.staging_buffers.push(staging_buffer);
+
+            Ok(texture_image)
+        }
+
+        // Submits everything recorded since the last call, without waiting for it.
+        // Returns the last submitted batch when nothing was recorded
+        pub fn submit(&self) -> vk_utils::Result<UploadBatchId> {
+            let mut state = self.state.borrow_mut();
+            let recording = match state.recording.take() {
+                Some(recording) => recording,
+                None => return Ok(state.last_submitted),
+            };
+
+            let command_buffers = std::iter::once(recording.transfer_command_buffer)
+                .chain(recording.graphics_command_buffer)
+                .collect::<Vec<_>>();
+            for &command_buffer in command_buffers.iter() {
+                unsafe {
+                    self.device
+                        .end_command_buffer(command_buffer)
+                        .map_err(vk_utils::Error::vulkan("failed to end command buffer!"))?;
+                }
+            }
+
+            let fence = Fence::new(&self.device, false)?;
+            // The graphics side waits on the GPU for the copies, not on the host
+            let transfer_finished = match recording.graphics_command_buffer {
+                Some(_) => Some(Semaphore::new(&self.device)?),
+                None => None,
+            };
+
+            let transfer_command_buffers = [recording.transfer_command_buffer];
+            let signal_semaphores = transfer_finished
+                .iter()
+                .map(|semaphore| semaphore.handle())
+                .collect::<Vec<_>>();
+            let transfer_submit_infos = [vk::SubmitInfo::builder()
+                .command_buffers(&transfer_command_buffers)
+                .signal_semaphores(&signal_semaphores)
+                .build()];
+
+            unsafe {
+                self.device
+                    .queue_submit(
+                        self.transfer_queue,
+                        &transfer_submit_infos,
+                        match recording.graphics_command_buffer {
+                            Some(_) => vk::Fence::null(),
+                            None => fence.handle(),
+                        },
+                    )
+                    .map_err(vk_utils::Error::vulkan("failed to submit upload commands!"))?;
+            }
+
+            if let Some(graphics_command_buffer) = recording.graphics_command_buffer {
+                let graphics_command_buffers = [graphics_command_buffer];
+                let wait_stages = [vk::PipelineStageFlags::ALL_COMMANDS];
+                let graphics_submit_infos = [vk::SubmitInfo::builder()
+                    .wait_semaphores(&signal_semaphores)
+                    .wait_dst_stage_mask(&wait_stages)
+                    .command_buffers(&graphics_command_buffers)
+                    .build()];
+
+                unsafe {
+                    self.device
+                        .queue_submit(self.graphics_queue, &graphics_submit_infos, fence.handle())
+                        .map_err(vk_utils::Error::vulkan("failed to submit upload commands!"))?;
+                }
+            }
+
+            state.last_submitted += 1;
+            let id = state.last_submitted;
+            state.pending.push_back(PendingBatch {
+                id,
+                transfer_command_buffer: recording.transfer_command_buffer,
+                graphics_command_buffer: recording.graphics_command_buffer,
+                fence,
+                _transfer_finished: transfer_finished,
+                _staging_buffers: recording.staging_buffers,
+            });
+
+            Ok(id)
+        }
+
+        // Frees the command buffers and staging buffers of finished batches, never blocks
+        pub fn collect(&self) -> vk_utils::Result<()> {
+            let mut state = self.state.borrow_mut();
+
+            while let Some(batch) = state.pending.front() {
+                let is_signaled = unsafe {
+                    self.device
+                        .get_fence_status(batch.fence.handle())
+                        .map_err(vk_utils::Error::vulkan("failed to get fence status!"))?
+                };
+                if !is_signaled {
+                    break;
+                }
+
+                let batch = state.pending.pop_front().unwrap();
+                self.free_command_buffers(&batch);
+            }
+
+            Ok(())
+        }
+
+        pub fn is_complete(&self, id: UploadBatchId) -> vk_utils::Result<bool> {
+            self.collect()?;
+
+            let state = self.state.borrow();
+
+            Ok(state.pending.front().is_none_or(|batch| batch.id > id))
+        }
+
+        // Blocks until batch `id` and everything submitted before it is done
+        pub fn wait(&self, id: UploadBatchId) -> vk_utils::Result<()> {
+            {
+                let state = self.state.borrow();
+                let fences = state
+                    .pending
+                    .iter()
+                    .take_while(|batch| batch.id <= id)
+                    .map(|batch| batch.fence.handle())
+                    .collect::<Vec<_>>();
+
+                if !fences.is_empty() {
+                    unsafe {
+                        self.device
+                            .wait_for_fences(&fences, true, u64::MAX)
+                            .map_err(vk_utils::Error::vulkan("failed to wait for fence!"))?;
+                    }
+                }
+            }
+
+            self.collect()
+        }
+
+        // Submits whatever is still recorded and waits for all of it
+        pub fn flush(&self) -> vk_utils::Result<()> {
+            let id = self.submit()?;
+
+            self.wait(id)
+        }
+
+        fn free_command_buffers(&self, batch: &PendingBatch) {
+            unsafe {
+                self.device.free_command_buffers(
+                    self.transfer_command_pool,
+                    &[batch.transfer_command_buffer],
+                );
+                if let Some(graphics_command_buffer) = batch.graphics_command_buffer {
+                    self.device.free_command_buffers(
+                        self.graphics_command_pool,
+                        &[graphics_command_buffer],
+                    );
+                }
+            }
+        }
+    }
+
+    impl Drop for UploadManager {
+        fn drop(&mut self) {
+            // Nothing left to do about errors while tearing down
+            let last_submitted = self.state.borrow().last_submitted;
+            let _ = self.wait(last_submitted);
+
+            let state = self.state.get_mut();
+            // Never submitted, the command buffers go with their pools
+            state.recording = None;
+            state.pending.clear();
+
+            unsafe {
+                self.device
+                    .destroy_command_pool(self.transfer_command_pool, None);
+                self.device
+                    .destroy_command_pool(self.graphics_command_pool, None);
+            }
+        }
+    }
+}
+
+pub use _upload::{UploadBatchId, UploadManager};
@@ -4,18 +4,32 @@ pub mod tools;
 
 pub mod types;
 
+pub mod assets;
 pub mod attributes;
 pub mod buffer;
 pub mod command;
+pub mod compute;
 pub mod device;
+pub mod error;
+pub mod frame;
 pub mod framebuffer;
 pub mod image;
+pub mod instance;
+pub mod loader;
+pub mod memory;
 pub mod model;
+pub mod offscreen;
 pub mod pipeline;
-pub mod render_pass;
+pub mod reflection;
+pub mod render_graph;
+pub mod renderer;
+pub mod shader;
 pub mod surface;
 pub mod swapchain;
 pub mod texture;
+pub mod upload;
+
+pub use error::{Error, Result};
 
 // Types and objects made just to make things work will be under here!
 
@@ -43,6 +57,13 @@ pub struct VkSwapChainInfo {
     pub swapchain_extent: ash::vk::Extent2D,
 }
 
+pub struct VkOffscreenInfo {
+    pub image: image::Image,
+    pub image_view: image::ImageView,
+    pub format: ash::vk::Format,
+    pub extent: ash::vk::Extent2D,
+}
+
 pub struct VkSwapchainDetail {
     pub capabilities: ash::vk::SurfaceCapabilitiesKHR,
     pub formats: Vec<ash::vk::SurfaceFormatKHR>,
@@ -52,13 +73,24 @@ pub struct VkSwapchainDetail {
 pub struct QueueFamilyIndices {
     pub graphics_family: Option<u32>,
     pub present_family: Option<u32>,
+    // A compute-only family when there is one (async compute), the graphics family otherwise
+    pub compute_family: Option<u32>,
+    // A transfer-only family when there is one, the graphics family otherwise
+    pub transfer_family: Option<u32>,
 }
 
 impl QueueFamilyIndices {
-    pub fn new(graphics_family: Option<u32>, present_family: Option<u32>) -> Self {
+    pub fn new(
+        graphics_family: Option<u32>,
+        present_family: Option<u32>,
+        compute_family: Option<u32>,
+        transfer_family: Option<u32>,
+    ) -> Self {
         Self {
             graphics_family,
             present_family,
+            compute_family,
+            transfer_family,
         }
     }
 
@@ -66,9 +98,3 @@ impl QueueFamilyIndices {
         self.graphics_family.is_some() && self.present_family.is_some()
     }
 }
-
-pub struct SyncObjects {
-    pub image_available_semaphores: Vec<ash::vk::Semaphore>,
-    pub render_finished_semaphores: Vec<ash::vk::Semaphore>,
-    pub in_flight_fences: Vec<ash::vk::Fence>,
-}
@@ -1,13 +1,16 @@
 #version 450
 
-layout(binding = 1) uniform sampler2D texSampler;
+// Separate image and sampler, the in-process compiler has no combined samplers
+layout(binding = 1) uniform texture2D texImage;
+layout(binding = 2) uniform sampler texSampler;
 
 layout(location = 0) in vec3 fragColor;
 layout(location = 1) in vec2 fragTexCoord;
+layout(location = 2) in vec4 fragTint;
 
 layout(location = 0) out vec4 outColor;
 
 void main() {
     // outColor = vec4(fragTexCoord, 0.0, 1.0);
-    outColor = texture(texSampler, fragTexCoord);
+    outColor = texture(sampler2D(texImage, texSampler), fragTexCoord) * fragTint;
 }
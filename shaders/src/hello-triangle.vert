@@ -1,17 +1,29 @@
 #version 450
 
-layout(binding = 0) uniform UniformBufferObject {
-    mat4 model;
+layout(binding = 0) uniform CameraUniformObject {
     mat4 view;
     mat4 proj;
-} ubo;
+} camera;
+
+// Per draw call, so many copies of a mesh can be drawn from one uniform buffer
+layout(push_constant) uniform DrawPushConstants {
+    mat4 model;
+    uint materialIndex;
+} draw;
 
 layout(location = 0) in vec4 inPosition;
 layout(location = 1) in vec3 inColor;
 layout(location = 2) in vec2 inTexCoord;
+// Per instance (binding 1), the model matrix column by column
+layout(location = 4) in vec4 inInstanceModel0;
+layout(location = 5) in vec4 inInstanceModel1;
+layout(location = 6) in vec4 inInstanceModel2;
+layout(location = 7) in vec4 inInstanceModel3;
+layout(location = 8) in vec4 inInstanceTint;
 
 layout(location = 0) out vec3 fragColor;
 layout(location = 1) out vec2 fragTexCoord;
+layout(location = 2) out vec4 fragTint;
 
 /*
 vec2 positions[3] = vec2[](
@@ -27,7 +39,10 @@ vec3 colors[3] = vec3[](
 */
 
 void main() {
-    gl_Position = ubo.proj * ubo.view * ubo.model * inPosition;
+    mat4 instanceModel = mat4(inInstanceModel0, inInstanceModel1, inInstanceModel2, inInstanceModel3);
+
+    gl_Position = camera.proj * camera.view * instanceModel * draw.model * inPosition;
     fragColor = inColor;
     fragTexCoord = inTexCoord;
+    fragTint = inInstanceTint;
 }
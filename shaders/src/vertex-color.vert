@@ -0,0 +1,32 @@
+#version 450
+
+layout(binding = 0) uniform CameraUniformObject {
+    mat4 view;
+    mat4 proj;
+} camera;
+
+layout(push_constant) uniform DrawPushConstants {
+    mat4 model;
+    uint materialIndex;
+} draw;
+
+// Vertex2D, colors only
+layout(location = 0) in vec2 inPosition;
+layout(location = 1) in vec3 inColor;
+// Per instance (binding 1, right after the vertex attributes), the model matrix column by column
+layout(location = 2) in vec4 inInstanceModel0;
+layout(location = 3) in vec4 inInstanceModel1;
+layout(location = 4) in vec4 inInstanceModel2;
+layout(location = 5) in vec4 inInstanceModel3;
+layout(location = 6) in vec4 inInstanceTint;
+
+layout(location = 0) out vec3 fragColor;
+layout(location = 1) out vec4 fragTint;
+
+void main() {
+    mat4 instanceModel = mat4(inInstanceModel0, inInstanceModel1, inInstanceModel2, inInstanceModel3);
+
+    gl_Position = camera.proj * camera.view * instanceModel * draw.model * vec4(inPosition, 0.0, 1.0);
+    fragColor = inColor;
+    fragTint = inInstanceTint;
+}
@@ -0,0 +1,121 @@
+// Golden image regression tests
+// Each sample is rendered offscreen with a fixed transform and compared against
+// the reference images under results/golden.
+// Run with `cargo test -- --ignored` on a machine with a Vulkan device (lavapipe works).
+// Set UPDATE_GOLDEN=1 to regenerate the reference images.
+
+use crate::sample_scene::{Sample, SampleScene};
+
+use ash::vk;
+use std::path::Path;
+use vk_utils::{
+    constants::{offscreen, HEIGHT, WIDTH},
+    renderer::{Renderer, RendererOptions},
+    types::UniformBufferObject,
+};
+
+const REFERENCE_DIR: &str = "results/golden";
+const OUTPUT_DIR: &str = "target/golden";
+
+const FRAME_COUNT: u32 = 3;
+const TOLERANCE: u8 = 8; // per channel
+const MAX_MISMATCH_RATIO: f32 = 0.001;
+
+fn fixed_uniform_transform(width: u32, height: u32) -> UniformBufferObject {
+    use cgmath::SquareMatrix;
+
+    let mut proj = cgmath::perspective(cgmath::Deg(45.0), width as f32 / height as f32, 0.1, 10.0);
+    proj[1][1] = -proj[1][1];
+
+    UniformBufferObject::new(
+        cgmath::Matrix4::<f32>::identity(),
+        cgmath::Matrix4::look_at_rh(
+            cgmath::Point3::new(2.0, 2.0, 2.0),
+            cgmath::Point3::new(0.0, 0.0, 0.0),
+            cgmath::Vector3::new(0.0, 0.0, 1.0),
+        ),
+        proj,
+    )
+}
+
+fn check_sample(sample: Sample, msaa_samples: vk::SampleCountFlags, reference_name: &str) {
+    let mut app = Renderer::new_headless(
+        vk::Extent2D {
+            width: WIDTH,
+            height: HEIGHT,
+        },
+        offscreen::COLOR_FORMAT,
+        SampleScene::new(sample),
+        RendererOptions {
+            msaa_samples: Some(msaa_samples),
+            ..Default::default()
+        },
+    )
+    .unwrap_or_else(|err| panic!("{}", err));
+    let extent = app.extent();
+
+    app.scene_mut()
+        .set_uniform_transform(fixed_uniform_transform(extent.width, extent.height));
+    for _ in 0..FRAME_COUNT {
+        if let Err(err) = app.draw_frame(0.0) {
+            panic!("{}", err);
+        }
+    }
+
+    let pixels = match app.read_frame() {
+        Ok(pixels) => pixels,
+        Err(err) => panic!("{}", err),
+    };
+
+    let reference_path = Path::new(REFERENCE_DIR).join(format!("{}.png", reference_name));
+    let result = vk_utils::tools::golden::check_against_reference(
+        pixels,
+        extent.width,
+        extent.height,
+        &reference_path,
+        Path::new(OUTPUT_DIR),
+        TOLERANCE,
+        MAX_MISMATCH_RATIO,
+    );
+
+    if let Err(err) = result {
+        panic!("{}", err);
+    }
+}
+
+#[test]
+#[ignore = "requires a Vulkan device"]
+fn triangle() {
+    check_sample(Sample::Triangle, vk::SampleCountFlags::TYPE_1, "triangle");
+}
+
+#[test]
+#[ignore = "requires a Vulkan device"]
+fn textured_square() {
+    check_sample(
+        Sample::TexturedSquare,
+        vk::SampleCountFlags::TYPE_1,
+        "textured_square",
+    );
+}
+
+#[test]
+#[ignore = "requires a Vulkan device"]
+fn textured_cube() {
+    check_sample(
+        Sample::TexturedCube,
+        vk::SampleCountFlags::TYPE_1,
+        "textured_cube",
+    );
+}
+
+#[test]
+#[ignore = "requires a Vulkan device"]
+fn viking_room_msaa() {
+    // 4 samples is guaranteed by the spec, keeping the output device independent
+    check_sample(
+        Sample::VikingRoom,
+        vk::SampleCountFlags::TYPE_4,
+        "viking_room_msaa",
+    );
+}
@@ -1,11 +1,16 @@
-mod hello_triangle_application;
-mod loading_models;
-mod multisampling;
-mod textures;
+#[cfg(test)]
+mod golden;
+mod options;
+mod sample_scene;
 
-use vk_utils::constants::*;
+use vk_utils::{
+    constants::*,
+    renderer::{Renderer, RendererOptions},
+};
 
-use multisampling::Multisampling;
+use ash::vk;
+use options::Options;
+use sample_scene::SampleScene;
 
 use winit::{
     dpi::PhysicalSize,
@@ -15,20 +20,60 @@ use winit::{
 };
 
 fn main() {
+    let options = match Options::parse(std::env::args().skip(1)) {
+        Ok(options) => options,
+        Err(err) => {
+            eprintln!("{}\n\n{}", err, options::USAGE);
+            std::process::exit(1);
+        }
+    };
+
+    if options.show_help {
+        println!("{}", options::USAGE);
+        return;
+    }
+
+    // Render without a window (e.g. CI on a software rasterizer)
+    if options.headless {
+        if let Err(err) = run_headless(&options) {
+            eprintln!("{}", err);
+            std::process::exit(1);
+        }
+        return;
+    }
+
     let event_loop = EventLoop::new();
     let window = WindowBuilder::new()
         .with_title("Vulkan")
-        .with_inner_size(PhysicalSize::new(WIDTH, HEIGHT))
+        .with_inner_size(PhysicalSize::new(options.width, options.height))
         .with_min_inner_size(PhysicalSize::new(MINIMAL_WIDTH, MINIMAL_HEIGHT))
         .with_max_inner_size(PhysicalSize::new(MAXIMUM_WIDTH, MAXIMUM_HEIGHT))
         .build(&event_loop)
         .unwrap();
     let mut tick_counter = vk_utils::fps::FPSLimiter::new();
-    let mut app = Multisampling::new(&window);
+    let mut app = match create_renderer(&window, &options) {
+        Ok(app) => app,
+        Err(err) => {
+            eprintln!("{}", err);
+            std::process::exit(1);
+        }
+    };
+
+    if options.dynamic_rendering && !app.uses_dynamic_rendering() {
+        eprintln!("dynamic rendering is not supported, using render passes");
+    }
+    if options.wireframe && !app.scene().is_wireframe() {
+        eprintln!("wireframe rendering is not supported, filling polygons");
+    }
+
+    // Editing shaders/src while running rebuilds the pipeline
+    if let Err(err) = app.watch_shaders(std::path::Path::new(shader::SOURCE_DIR)) {
+        eprintln!("{}, shader hot reload disabled", err);
+    }
 
     // Application loop
     event_loop.run(move |event, _, control_flow| {
-        app.wait_for_device_idle();
+        // The device is only idled on resize (swap chain recreation), close and exit
         *control_flow = ControlFlow::Poll;
 
         match event {
@@ -36,43 +81,178 @@ fn main() {
             // app
             Event::WindowEvent { event, .. } => match event {
                 WindowEvent::CloseRequested => {
-                    app.wait_for_device_idle();
-                    *control_flow = ControlFlow::Exit;
+                    exit(&app, control_flow);
                 }
-                WindowEvent::KeyboardInput { input, .. } => match input {
-                    KeyboardInput {
-                        virtual_keycode,
-                        state,
-                        ..
-                    } => match (virtual_keycode, state) {
-                        (Some(VirtualKeyCode::Escape), ElementState::Pressed) => {
-                            app.wait_for_device_idle();
-                            *control_flow = ControlFlow::Exit;
-                        }
-                        _ => {}
-                    },
-                },
-                WindowEvent::Resized(_new_size) => {
-                    app.wait_for_device_idle();
-                    app.resize_framebuffer();
+                WindowEvent::KeyboardInput {
+                    input:
+                        KeyboardInput {
+                            virtual_keycode: Some(VirtualKeyCode::Escape),
+                            state: ElementState::Pressed,
+                            ..
+                        },
+                    ..
+                } => {
+                    exit(&app, control_flow);
+                }
+                WindowEvent::Resized(new_size) => {
+                    app.resize_framebuffer(new_size.width, new_size.height);
                 }
                 _ => {}
             },
             // Main event for app
             Event::MainEventsCleared => {
+                if let Err(err) = app.reload_changed_shaders() {
+                    eprintln!("{}\nkeeping the previous pipeline", err);
+                }
                 window.request_redraw();
             }
             Event::RedrawRequested(_window_id) => {
                 let delta_time = tick_counter.delta_time();
 
-                app.draw_frame(delta_time);
+                if let Err(err) = app.draw_frame(delta_time) {
+                    eprintln!("{}", err);
+                    *control_flow = ControlFlow::Exit;
+                    return;
+                }
                 tick_counter.tick_frame();
             }
             Event::LoopDestroyed => {
-                app.wait_for_device_idle();
+                if let Err(err) = app.wait_for_device_idle() {
+                    eprintln!("{}", err);
+                }
+                // Only costs the next startup some time
+                if let Err(err) = app.save_pipeline_cache() {
+                    eprintln!("failed to save pipeline cache: {}", err);
+                }
             }
             // Some other random events
             _ => (),
         }
     });
 }
+
+// Frames still in flight finish before the window goes away
+fn exit(app: &Renderer<SampleScene>, control_flow: &mut ControlFlow) {
+    if let Err(err) = app.wait_for_device_idle() {
+        eprintln!("{}", err);
+    }
+    *control_flow = ControlFlow::Exit;
+}
+
+fn create_scene(options: &Options) -> SampleScene {
+    let mut scene = SampleScene::new(options.sample);
+
+    if let Some(model_path) = options.model_path.as_ref() {
+        scene.set_model_path(model_path);
+    }
+    if let Some(texture_path) = options.texture_path.as_ref() {
+        scene.set_texture_path(texture_path);
+    }
+    scene.set_wireframe(options.wireframe);
+    // Saved frames should show the real assets
+    scene.set_streaming(!options.headless);
+    scene.set_instances(&instance_grid(options.instances));
+    scene.set_draw_transforms(&draw_row(options.draws, options.instances));
+
+    scene
+}
+
+const GRID_SPACING: f32 = 2.0;
+
+fn grid_columns(count: u32) -> u32 {
+    (count as f32).sqrt().ceil() as u32
+}
+
+// Square grid in the ground plane, centered on the origin
+fn instance_grid(count: u32) -> Vec<vk_utils::types::InstanceTransform> {
+    let columns = grid_columns(count);
+    let rows = count.div_ceil(columns);
+    let center = cgmath::Vector3::new(
+        (columns - 1) as f32 * GRID_SPACING / 2.0,
+        (rows - 1) as f32 * GRID_SPACING / 2.0,
+        0.0,
+    );
+
+    (0..count)
+        .map(|index| {
+            let position = cgmath::Vector3::new(
+                (index % columns) as f32 * GRID_SPACING,
+                (index / columns) as f32 * GRID_SPACING,
+                0.0,
+            ) - center;
+
+            vk_utils::types::InstanceTransform::new(
+                cgmath::Matrix4::from_translation(position),
+                [1.0, 1.0, 1.0, 1.0],
+            )
+        })
+        .collect()
+}
+
+// Row along the x axis, centered on the origin, with room for each draw's instance grid
+fn draw_row(count: u32, instances: u32) -> Vec<cgmath::Matrix4<f32>> {
+    let spacing = grid_columns(instances) as f32 * GRID_SPACING;
+    let center = (count - 1) as f32 * spacing / 2.0;
+
+    (0..count)
+        .map(|index| {
+            cgmath::Matrix4::from_translation(cgmath::Vector3::new(
+                index as f32 * spacing - center,
+                0.0,
+                0.0,
+            ))
+        })
+        .collect()
+}
+
+// Falls back to rendering without MSAA if the requested setup fails
+fn create_renderer(
+    window: &winit::window::Window,
+    options: &Options,
+) -> vk_utils::Result<Renderer<SampleScene>> {
+    match Renderer::new(window, create_scene(options), options.renderer_options()) {
+        Err(err) if options.msaa_samples != Some(vk::SampleCountFlags::TYPE_1) => {
+            eprintln!("{}, retrying without MSAA", err);
+
+            Renderer::new(
+                window,
+                create_scene(options),
+                RendererOptions {
+                    msaa_samples: Some(vk::SampleCountFlags::TYPE_1),
+                    ..options.renderer_options()
+                },
+            )
+        }
+        result => result,
+    }
+}
+
+fn run_headless(options: &Options) -> vk_utils::Result<()> {
+    use std::path::Path;
+
+    let mut app = Renderer::new_headless(
+        vk::Extent2D {
+            width: options.width,
+            height: options.height,
+        },
+        offscreen::COLOR_FORMAT,
+        create_scene(options),
+        options.renderer_options(),
+    )?;
+
+    if options.wireframe && !app.scene().is_wireframe() {
+        eprintln!("wireframe rendering is not supported, filling polygons");
+    }
+
+    std::fs::create_dir_all(offscreen::OUTPUT_DIR)?;
+
+    for frame in 0..offscreen::FRAME_COUNT {
+        app.draw_frame(offscreen::FRAME_DELTA_TIME)?;
+
+        let image_path = Path::new(offscreen::OUTPUT_DIR).join(format!("frame_{:04}.png", frame));
+        app.save_frame(&image_path)?;
+    }
+
+    app.wait_for_device_idle()?;
+    app.save_pipeline_cache()
+}
@@ -0,0 +1,281 @@
+mod _options {
+    use crate::sample_scene::Sample;
+
+    use ash::vk;
+    use std::path::PathBuf;
+    use vk_utils::{
+        constants::{
+            DEFAULT_FRAMES_IN_FLIGHT, HEIGHT, MAXIMUM_HEIGHT, MAXIMUM_WIDTH, MINIMAL_HEIGHT,
+            MINIMAL_WIDTH, WIDTH,
+        },
+        renderer::RendererOptions,
+    };
+
+    pub const USAGE: &str = "\
+usage: learning_vulkan [options]
+
+options:
+    --scene <name>      triangle | square | textures | model | msaa (default: msaa)
+    --model <path>      OBJ or glTF model to load (model and msaa scenes)
+    --texture <path>    texture image to sample
+    --width <pixels>    window (or offscreen image) width
+    --height <pixels>   window (or offscreen image) height
+    --msaa <samples>    1 | 2 | 4 | 8 | 16 | 32 | 64 (default: per scene)
+    --wireframe         draw polygon edges only
+    --dynamic-rendering use dynamic rendering instead of render passes, if supported
+    --instances <count> copies of the mesh drawn on a grid, 1 to 65536 (default: 1)
+    --draws <count>     copies of the grid drawn in a row, one draw each with its own
+                        push constant transform, 1 to 256 (default: 1)
+    --frames-in-flight <count>
+                        frames recorded ahead of the GPU, 1 to 8 (default: 2)
+    --headless          render offscreen and save frames as PNG
+    --help              print this message";
+
+    pub struct Options {
+        pub sample: Sample,
+        pub model_path: Option<PathBuf>,
+        pub texture_path: Option<PathBuf>,
+        pub width: u32,
+        pub height: u32,
+        // None picks the highest sample count the device supports
+        pub msaa_samples: Option<vk::SampleCountFlags>,
+        pub wireframe: bool,
+        pub dynamic_rendering: bool,
+        pub frames_in_flight: usize,
+        pub instances: u32,
+        pub draws: u32,
+        pub headless: bool,
+        pub show_help: bool,
+    }
+
+    impl Options {
+        pub fn parse<I: Iterator<Item = String>>(mut args: I) -> Result<Self, String> {
+            let mut options = Self {
+                sample: Sample::VikingRoom,
+                model_path: None,
+                texture_path: None,
+                width: WIDTH,
+                height: HEIGHT,
+                msaa_samples: None,
+                wireframe: false,
+                dynamic_rendering: false,
+                frames_in_flight: DEFAULT_FRAMES_IN_FLIGHT,
+                instances: 1,
+                draws: 1,
+                headless: false,
+                show_help: false,
+            };
+            let mut msaa_samples = None;
+            let mut scene_msaa_samples = None;
+
+            while let Some(arg) = args.next() {
+                match arg.as_str() {
+                    "--scene" => {
+                        (options.sample, scene_msaa_samples) = match Self::value(&mut args, &arg)?
+                            .as_str()
+                        {
+                            "triangle" => (Sample::Triangle, Some(vk::SampleCountFlags::TYPE_1)),
+                            "square" => {
+                                (Sample::TexturedSquare, Some(vk::SampleCountFlags::TYPE_1))
+                            }
+                            "textures" => {
+                                (Sample::TexturedCube, Some(vk::SampleCountFlags::TYPE_1))
+                            }
+                            "model" => (Sample::VikingRoom, Some(vk::SampleCountFlags::TYPE_1)),
+                            "msaa" => (Sample::VikingRoom, None),
+                            scene => return Err(format!("unknown scene: {}", scene)),
+                        }
+                    }
+                    "--model" => {
+                        options.model_path = Some(PathBuf::from(Self::value(&mut args, &arg)?))
+                    }
+                    "--texture" => {
+                        options.texture_path = Some(PathBuf::from(Self::value(&mut args, &arg)?))
+                    }
+                    "--width" => {
+                        options.width = Self::size(
+                            &Self::value(&mut args, &arg)?,
+                            MINIMAL_WIDTH,
+                            MAXIMUM_WIDTH,
+                        )?
+                    }
+                    "--height" => {
+                        options.height = Self::size(
+                            &Self::value(&mut args, &arg)?,
+                            MINIMAL_HEIGHT,
+                            MAXIMUM_HEIGHT,
+                        )?
+                    }
+                    "--msaa" => {
+                        msaa_samples = Some(Self::sample_count(&Self::value(&mut args, &arg)?)?)
+                    }
+                    "--wireframe" => options.wireframe = true,
+                    "--dynamic-rendering" => options.dynamic_rendering = true,
+                    "--instances" => {
+                        options.instances = Self::instance_count(&Self::value(&mut args, &arg)?)?
+                    }
+                    "--draws" => options.draws = Self::draw_count(&Self::value(&mut args, &arg)?)?,
+                    "--frames-in-flight" => {
+                        options.frames_in_flight =
+                            Self::frame_count(&Self::value(&mut args, &arg)?)?
+                    }
+                    "--headless" => options.headless = true,
+                    "--help" | "-h" => options.show_help = true,
+                    _ => return Err(format!("unknown option: {}", arg)),
+                }
+            }
+
+            // An explicit --msaa overrides the scene's sample count
+            options.msaa_samples = msaa_samples.or(scene_msaa_samples);
+
+            Ok(options)
+        }
+
+        pub fn renderer_options(&self) -> RendererOptions {
+            RendererOptions {
+                msaa_samples: self.msaa_samples,
+                dynamic_rendering: self.dynamic_rendering,
+                frames_in_flight: self.frames_in_flight,
+            }
+        }
+
+        fn value<I: Iterator<Item = String>>(args: &mut I, option: &str) -> Result<String, String> {
+            args.next()
+                .ok_or_else(|| format!("missing value for {}", option))
+        }
+
+        fn size(value: &str, minimum: u32, maximum: u32) -> Result<u32, String> {
+            match value.parse::<u32>() {
+                Ok(size) if (minimum..=maximum).contains(&size) => Ok(size),
+                _ => Err(format!(
+                    "invalid size: {} (expected {} to {})",
+                    value, minimum, maximum
+                )),
+            }
+        }
+
+        fn instance_count(value: &str) -> Result<u32, String> {
+            match value.parse::<u32>() {
+                Ok(count) if (1..=65536).contains(&count) => Ok(count),
+                _ => Err(format!(
+                    "invalid instance count: {} (expected 1 to 65536)",
+                    value
+                )),
+            }
+        }
+
+        fn draw_count(value: &str) -> Result<u32, String> {
+            match value.parse::<u32>() {
+                Ok(count) if (1..=256).contains(&count) => Ok(count),
+                _ => Err(format!("invalid draw count: {} (expected 1 to 256)", value)),
+            }
+        }
+
+        fn frame_count(value: &str) -> Result<usize, String> {
+            match value.parse::<usize>() {
+                Ok(count) if (1..=8).contains(&count) => Ok(count),
+                _ => Err(format!(
+                    "invalid frames in flight: {} (expected 1 to 8)",
+                    value
+                )),
+            }
+        }
+
+        fn sample_count(value: &str) -> Result<vk::SampleCountFlags, String> {
+            match value {
+                "1" => Ok(vk::SampleCountFlags::TYPE_1),
+                "2" => Ok(vk::SampleCountFlags::TYPE_2),
+                "4" => Ok(vk::SampleCountFlags::TYPE_4),
+                "8" => Ok(vk::SampleCountFlags::TYPE_8),
+                "16" => Ok(vk::SampleCountFlags::TYPE_16),
+                "32" => Ok(vk::SampleCountFlags::TYPE_32),
+                "64" => Ok(vk::SampleCountFlags::TYPE_64),
+                _ => Err(format!("invalid MSAA sample count: {}", value)),
+            }
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        fn parse(args: &[&str]) -> Result<Options, String> {
+            Options::parse(args.iter().map(|arg| arg.to_string()))
+        }
+
+        #[test]
+        fn defaults() {
+            let options = parse(&[]).unwrap();
+
+            assert_eq!(options.sample, Sample::VikingRoom);
+            assert_eq!((options.width, options.height), (WIDTH, HEIGHT));
+            assert_eq!(options.msaa_samples, None);
+            assert_eq!(options.frames_in_flight, DEFAULT_FRAMES_IN_FLIGHT);
+        }
+
+        #[test]
+        fn scene_sample_count_can_be_overridden() {
+            let options = parse(&["--msaa", "4", "--scene", "textures"]).unwrap();
+
+            assert_eq!(options.sample, Sample::TexturedCube);
+            assert_eq!(options.msaa_samples, Some(vk::SampleCountFlags::TYPE_4));
+
+            let options = parse(&["--scene", "triangle"]).unwrap();
+
+            assert_eq!(options.msaa_samples, Some(vk::SampleCountFlags::TYPE_1));
+        }
+
+        #[test]
+        fn unknown_scene() {
+            assert_eq!(
+                parse(&["--scene", "teapot"]).err(),
+                Some(String::from("unknown scene: teapot"))
+            );
+        }
+
+        #[test]
+        fn bad_sample_count() {
+            assert_eq!(
+                parse(&["--msaa", "3"]).err(),
+                Some(String::from("invalid MSAA sample count: 3"))
+            );
+        }
+
+        #[test]
+        fn missing_values() {
+            for option in [
+                "--scene",
+                "--model",
+                "--width",
+                "--msaa",
+                "--instances",
+                "--draws",
+            ] {
+                assert_eq!(
+                    parse(&["--wireframe", option]).err(),
+                    Some(format!("missing value for {}", option))
+                );
+            }
+        }
+
+        #[test]
+        fn sizes_out_of_range() {
+            let too_small = (MINIMAL_WIDTH - 1).to_string();
+
+            assert!(parse(&["--width", &too_small]).is_err());
+            assert!(parse(&["--height", "tall"]).is_err());
+            assert!(parse(&["--frames-in-flight", "0"]).is_err());
+            assert!(parse(&["--draws", "257"]).is_err());
+        }
+
+        #[test]
+        fn unknown_option() {
+            assert_eq!(
+                parse(&["--fullscreen"]).err(),
+                Some(String::from("unknown option: --fullscreen"))
+            );
+        }
+    }
+}
+
+pub use _options::{Options, USAGE};
@@ -0,0 +1,769 @@
+mod _sample_scene {
+    use vk_utils::{
+        assets::{AssetManager, MeshHandle, TextureHandle},
+        buffer::{Buffer, RingBuffer},
+        constants::{hello_triangle, loader, model, shader, texture},
+        loader::AssetLoader,
+        model::{Model, SubMesh},
+        pipeline::{Pipeline, PipelineBuilder},
+        renderer::{RenderContext, Scene},
+        texture::{TexturePixels, TextureRole},
+        types as vk_types,
+    };
+
+    use ash::{vk, Device};
+    use std::path::{Path, PathBuf};
+
+    #[derive(Clone, Copy, PartialEq, Eq, Debug)]
+    pub enum Sample {
+        Triangle,
+        TexturedSquare,
+        TexturedCube,
+        VikingRoom,
+    }
+
+    impl Sample {
+        fn application_name(&self) -> &'static str {
+            match self {
+                Sample::Triangle => hello_triangle::APPLICATION_NAME,
+                Sample::TexturedSquare | Sample::TexturedCube => texture::APPLICATION_NAME,
+                Sample::VikingRoom => model::APPLICATION_NAME,
+            }
+        }
+
+        fn application_version(&self) -> u32 {
+            match self {
+                Sample::Triangle => hello_triangle::APPLICATION_VERSION,
+                Sample::TexturedSquare | Sample::TexturedCube => texture::APPLICATION_VERSION,
+                Sample::VikingRoom => model::APPLICATION_VERSION,
+            }
+        }
+
+        // Loaded from a file (--model), the other samples draw built-in geometry
+        fn has_model_file(&self) -> bool {
+            *self == Sample::VikingRoom
+        }
+
+        // The triangle keeps its vertex colored pipeline, the others sample a texture
+        fn is_textured(&self) -> bool {
+            *self != Sample::Triangle
+        }
+
+        fn shader_sources(&self) -> [&'static str; 2] {
+            match self {
+                Sample::Triangle => [shader::VERTEX_COLOR_VERT, shader::VERTEX_COLOR_FRAG],
+                _ => [shader::HELLO_TRIANGLE_VERT, shader::HELLO_TRIANGLE_FRAG],
+            }
+        }
+
+        fn texture_path(&self) -> &'static str {
+            match self {
+                Sample::VikingRoom => model::TEXTURE_PATH,
+                _ => texture::TEXTURE_PATH,
+            }
+        }
+
+        fn load_geometry(&self, model_path: &Path) -> vk_utils::Result<Model> {
+            let (vertices, indices) = match self {
+                // Uploaded as is by SampleScene::upload_triangle
+                Sample::Triangle => {
+                    return Err(vk_utils::Error::InvalidModel(
+                        "the triangle is drawn from Vertex2D vertices!",
+                    ))
+                }
+                Sample::TexturedSquare => (
+                    texture::SQUARE_VERTICES.to_vec(),
+                    texture::SQUARE_INDICES.to_vec(),
+                ),
+                Sample::TexturedCube => (texture::VERTICES.to_vec(), texture::INDICES.to_vec()),
+                // glTF scenes are flattened, the texture still comes from --texture
+                Sample::VikingRoom => {
+                    let extension = model_path
+                        .extension()
+                        .and_then(|extension| extension.to_str())
+                        .map(|extension| extension.to_ascii_lowercase());
+
+                    return match extension.as_deref() {
+                        Some("gltf") | Some("glb") => {
+                            let mut model = vk_utils::model::load_gltf(model_path)?.to_model();
+
+                            // glTF texture coordinates start at the top, the texture is flipped
+                            for vertex in model.vertices.iter_mut() {
+                                vertex.tex_coord[1] = 1.0 - vertex.tex_coord[1];
+                            }
+
+                            Ok(model)
+                        }
+                        _ => vk_utils::model::load_model(model_path),
+                    };
+                }
+            };
+
+            // Built-in geometry is drawn in one go
+            Ok(Model {
+                submeshes: vec![SubMesh {
+                    name: String::from(self.application_name()),
+                    first_index: 0,
+                    index_count: indices.len() as u32,
+                    material_id: None,
+                }],
+                vertices,
+                indices,
+                materials: Vec::new(),
+            })
+        }
+    }
+
+    // CPU data decoded by the asset loader's workers
+    enum SampleAsset {
+        Model(Model),
+        Texture(TexturePixels),
+    }
+
+    // Grey and white checkerboard, RGBA8
+    fn placeholder_pixels(size: u32) -> TexturePixels {
+        let data = (0..size * size)
+            .flat_map(|index| {
+                let value = if (index % size + index / size).is_multiple_of(2) {
+                    0xff
+                } else {
+                    0x80
+                };
+
+                [value, value, value, 0xff]
+            })
+            .collect();
+
+        TexturePixels {
+            width: size,
+            height: size,
+            format: vk::Format::R8G8B8A8_SRGB,
+            data,
+        }
+    }
+
+    // Binding 0 only, for the untextured triangle
+    fn create_camera_descriptor_set(
+        device: &Device,
+        descriptor_pool: vk::DescriptorPool,
+        descriptor_set_layout: vk::DescriptorSetLayout,
+        camera_buffer: &Buffer,
+        camera_size: vk::DeviceSize,
+    ) -> vk_utils::Result<vk::DescriptorSet> {
+        let set_layouts = [descriptor_set_layout];
+        let alloc_info = vk::DescriptorSetAllocateInfo::builder()
+            .descriptor_pool(descriptor_pool)
+            .set_layouts(&set_layouts);
+        let descriptor_set = unsafe {
+            device
+                .allocate_descriptor_sets(&alloc_info)
+                .map_err(vk_utils::Error::vulkan(
+                    "failed to allocate descriptor sets!",
+                ))?
+        }[0];
+
+        let buffer_infos = [vk::DescriptorBufferInfo::builder()
+            .buffer(camera_buffer.handle())
+            .offset(0)
+            .range(camera_size)
+            .build()];
+        let descriptor_writes = [vk::WriteDescriptorSet::builder()
+            .dst_set(descriptor_set)
+            .dst_binding(0)
+            .dst_array_element(0)
+            .descriptor_type(vk::DescriptorType::UNIFORM_BUFFER_DYNAMIC)
+            .buffer_info(&buffer_infos)
+            .build()];
+
+        unsafe {
+            device.update_descriptor_sets(&descriptor_writes, &[]);
+        }
+
+        Ok(descriptor_set)
+    }
+
+    // Textured geometry with a rotating model transform (vertex colored for the triangle)
+    pub struct SampleScene {
+        sample: Sample,
+        model_path: PathBuf,
+        texture_path: PathBuf,
+        wireframe: bool,
+        // Placeholders are drawn until the loader hands over the model and texture
+        streaming: bool,
+        loader: Option<AssetLoader<SampleAsset>>,
+
+        descriptor_set_layout: vk::DescriptorSetLayout,
+        graphics_pipeline: Option<Pipeline>,
+
+        // Shared by path, released when replaced
+        assets: AssetManager,
+        texture: Option<TextureHandle>,
+        mesh: Option<MeshHandle>,
+
+        // Binding 1, stepped per instance
+        instance_buffer: Option<Buffer>,
+        instances: Vec<vk_types::InstanceTransform>,
+        // One draw each, pushed as the draw's model matrix
+        draw_transforms: Vec<cgmath::Matrix4<f32>>,
+
+        // Model goes into push constants, view and projection into the uniform ring
+        uniform_transform: vk_types::UniformBufferObject,
+        // A region per frame in flight, bound with dynamic offsets
+        uniform_ring: Option<RingBuffer>,
+
+        descriptor_pool: vk::DescriptorPool,
+        descriptor_set: vk::DescriptorSet,
+    }
+
+    pub struct SampleFrame {
+        // Where this frame's camera was pushed to the uniform ring
+        uniform_offset: u32,
+        model: cgmath::Matrix4<f32>,
+    }
+
+    impl SampleScene {
+        pub fn new(sample: Sample) -> Self {
+            use cgmath::SquareMatrix;
+
+            Self {
+                sample,
+                model_path: PathBuf::from(model::MODEL_PATH),
+                texture_path: PathBuf::from(sample.texture_path()),
+                wireframe: false,
+                streaming: false,
+                loader: None,
+
+                descriptor_set_layout: vk::DescriptorSetLayout::null(),
+                graphics_pipeline: None,
+
+                assets: AssetManager::new(),
+                texture: None,
+                mesh: None,
+
+                instance_buffer: None,
+                instances: vec![vk_types::InstanceTransform::new(
+                    cgmath::Matrix4::<f32>::identity(),
+                    [1.0, 1.0, 1.0, 1.0],
+                )],
+                draw_transforms: vec![cgmath::Matrix4::<f32>::identity()],
+
+                uniform_transform: vk_types::UniformBufferObject::new(
+                    cgmath::Matrix4::<f32>::identity(),
+                    cgmath::Matrix4::<f32>::identity(),
+                    cgmath::Matrix4::<f32>::identity(),
+                ),
+                uniform_ring: None,
+
+                descriptor_pool: vk::DescriptorPool::null(),
+                descriptor_set: vk::DescriptorSet::null(),
+            }
+        }
+
+        pub fn set_model_path(&mut self, model_path: &Path) {
+            self.model_path = model_path.to_path_buf();
+        }
+
+        pub fn set_texture_path(&mut self, texture_path: &Path) {
+            self.texture_path = texture_path.to_path_buf();
+        }
+
+        pub fn set_wireframe(&mut self, wireframe: bool) {
+            self.wireframe = wireframe;
+        }
+
+        // False once the device turned out not to support line polygons
+        pub fn is_wireframe(&self) -> bool {
+            self.wireframe
+        }
+
+        // Starts drawing right away instead of waiting for the model and texture files
+        pub fn set_streaming(&mut self, streaming: bool) {
+            self.streaming = streaming;
+        }
+
+        // Every instance is drawn with the same rotating model transform on top of its own
+        pub fn set_instances(&mut self, instances: &[vk_types::InstanceTransform]) {
+            self.instances = instances.to_vec();
+        }
+
+        // Places separate copies of the mesh (and its instances), rotating in place
+        pub fn set_draw_transforms(&mut self, draw_transforms: &[cgmath::Matrix4<f32>]) {
+            self.draw_transforms = draw_transforms.to_vec();
+        }
+
+        // Only the golden tests pin the transform
+        #[cfg(test)]
+        pub fn set_uniform_transform(&mut self, uniform_transform: vk_types::UniformBufferObject) {
+            self.uniform_transform = uniform_transform;
+        }
+
+        // Replaces the current texture, which no frame may be using anymore.
+        // Without a path (placeholders), the texture isn't shared
+        fn upload_texture(
+            &mut self,
+            context: &RenderContext,
+            path: Option<&Path>,
+            pixels: &TexturePixels,
+        ) -> vk_utils::Result<()> {
+            let texture = self
+                .assets
+                .insert_texture(context, path, TextureRole::Color, pixels)?;
+
+            if let Some(previous) = self.texture.replace(texture) {
+                self.assets.release_texture(previous);
+            }
+
+            // Only once the descriptor set exists, it is written with the first texture otherwise
+            if let Some(texture) = self.assets.texture(texture) {
+                if self.descriptor_set != vk::DescriptorSet::null() {
+                    vk_utils::texture::update_texture_descriptors(
+                        context.device,
+                        self.descriptor_set,
+                        texture.image_view(),
+                        texture.sampler(),
+                    );
+                }
+            }
+
+            Ok(())
+        }
+
+        // Replaces the current geometry, which no frame may be using anymore
+        fn upload_model(
+            &mut self,
+            context: &RenderContext,
+            path: Option<&Path>,
+            model: &Model,
+        ) -> vk_utils::Result<()> {
+            let mesh = self.assets.insert_mesh(context, path, model)?;
+
+            if let Some(previous) = self.mesh.replace(mesh) {
+                self.assets.release_mesh(previous);
+            }
+
+            Ok(())
+        }
+
+        // Vertex2D vertices, colors only
+        fn upload_triangle(&mut self, context: &RenderContext) -> vk_utils::Result<()> {
+            let submeshes = vec![SubMesh {
+                name: String::from(self.sample.application_name()),
+                first_index: 0,
+                index_count: 3,
+                material_id: None,
+            }];
+            let mesh = self.assets.insert_geometry(
+                context,
+                &hello_triangle::TRIANGLE_VERTEX,
+                &[0, 1, 2],
+                submeshes,
+            )?;
+
+            if let Some(previous) = self.mesh.replace(mesh) {
+                self.assets.release_mesh(previous);
+            }
+
+            Ok(())
+        }
+
+        fn receive_asset(
+            &mut self,
+            context: &RenderContext,
+            asset: SampleAsset,
+        ) -> vk_utils::Result<()> {
+            match asset {
+                SampleAsset::Model(model) => {
+                    let model_path = self.model_path.clone();
+
+                    self.upload_model(context, Some(&model_path), &model)
+                }
+                SampleAsset::Texture(pixels) => {
+                    let texture_path = self.texture_path.clone();
+
+                    self.upload_texture(context, Some(&texture_path), &pixels)
+                }
+            }
+        }
+
+        fn default_uniform_transform(extent: vk::Extent2D) -> vk_types::UniformBufferObject {
+            use cgmath::SquareMatrix;
+
+            let mut proj = cgmath::perspective(
+                cgmath::Deg(45.0),
+                extent.width as f32 / extent.height as f32,
+                0.1,
+                10.0,
+            );
+            proj[1][1] = -proj[1][1];
+
+            vk_types::UniformBufferObject::new(
+                cgmath::Matrix4::<f32>::identity(),
+                cgmath::Matrix4::look_at_rh(
+                    cgmath::Point3::new(2.0, 2.0, 2.0),
+                    cgmath::Point3::new(0.0, 0.0, 0.0),
+                    cgmath::Vector3::new(0.0, 0.0, 1.0),
+                ),
+                proj,
+            )
+        }
+    }
+
+    impl Scene for SampleScene {
+        type FrameData = SampleFrame;
+
+        fn application_name(&self) -> &str {
+            self.sample.application_name()
+        }
+
+        fn application_version(&self) -> u32 {
+            self.sample.application_version()
+        }
+
+        fn create_resources(&mut self, context: &RenderContext) -> vk_utils::Result<()> {
+            // The device only enables fillModeNonSolid where available
+            let features = unsafe {
+                context
+                    .instance
+                    .get_physical_device_features(context.physical_device)
+            };
+            if features.fill_mode_non_solid != vk::TRUE {
+                self.wireframe = false;
+            }
+
+            // Files are decoded on the loader's workers, both at once
+            let mut asset_loader = AssetLoader::new(loader::WORKER_COUNT)?;
+            if self.sample.is_textured() {
+                let texture_path = self.texture_path.clone();
+                asset_loader.load(move || {
+                    let pixels =
+                        vk_utils::texture::load_image_pixels(&texture_path, TextureRole::Color)?;
+
+                    Ok(SampleAsset::Texture(pixels))
+                });
+            }
+            if self.sample.has_model_file() {
+                let (sample, model_path) = (self.sample, self.model_path.clone());
+                asset_loader
+                    .load(move || Ok(SampleAsset::Model(sample.load_geometry(&model_path)?)));
+            } else if self.sample.is_textured() {
+                let model = self.sample.load_geometry(&self.model_path)?;
+
+                self.upload_model(context, None, &model)?;
+            } else {
+                self.upload_triangle(context)?;
+            }
+
+            // Uploaded in one batch, submitted right before the first frame
+            if self.streaming {
+                if self.sample.is_textured() {
+                    let placeholder = placeholder_pixels(loader::PLACEHOLDER_TEXTURE_SIZE);
+
+                    self.upload_texture(context, None, &placeholder)?;
+                }
+                if self.sample.has_model_file() {
+                    let placeholder_model = Sample::TexturedCube.load_geometry(&self.model_path)?;
+
+                    self.upload_model(context, None, &placeholder_model)?;
+                }
+                self.loader = Some(asset_loader);
+            } else {
+                for (_, result) in asset_loader.wait() {
+                    self.receive_asset(context, result?)?;
+                }
+            }
+
+            self.instance_buffer = Some(context.uploads.upload_buffer(
+                context.allocator,
+                &self.instances,
+                vk::BufferUsageFlags::VERTEX_BUFFER,
+            )?);
+
+            self.uniform_transform = Self::default_uniform_transform(context.extent);
+
+            let uniform_ring = RingBuffer::new(
+                context.instance,
+                context.device,
+                context.physical_device,
+                context.allocator,
+                vk::BufferUsageFlags::UNIFORM_BUFFER,
+                std::mem::size_of::<vk_types::CameraUniformObject>() as vk::DeviceSize,
+                context.frame_count,
+            )?;
+
+            // Descriptor set layout as declared by the shaders
+            let mut reflections = self
+                .shader_sources()
+                .iter()
+                .map(|source| {
+                    vk_utils::reflection::ShaderReflection::new(&vk_utils::shader::load_shader(
+                        source,
+                    )?)
+                })
+                .collect::<vk_utils::Result<Vec<_>>>()?;
+            for reflection in reflections.iter_mut() {
+                reflection.use_dynamic_offsets(0, 0);
+            }
+            let mut set_layouts =
+                vk_utils::reflection::create_descriptor_set_layouts(context.device, &reflections)?;
+
+            if set_layouts.len() != 1 {
+                for set_layout in set_layouts {
+                    unsafe {
+                        context
+                            .device
+                            .destroy_descriptor_set_layout(set_layout, None);
+                    }
+                }
+
+                return Err(vk_utils::Error::ShaderInterfaceMismatch(String::from(
+                    "the sample scene binds exactly one descriptor set",
+                )));
+            }
+            self.descriptor_set_layout = set_layouts.remove(0);
+            // The same set for every frame, only the dynamic offset changes
+            self.descriptor_pool = vk_utils::texture::create_descriptor_pool(
+                context.device,
+                vk::DescriptorType::UNIFORM_BUFFER_DYNAMIC,
+                1,
+            )?;
+            let camera_size =
+                std::mem::size_of::<vk_types::CameraUniformObject>() as vk::DeviceSize;
+            self.descriptor_set = match self
+                .texture
+                .and_then(|texture| self.assets.texture(texture))
+            {
+                Some(texture) => vk_utils::texture::create_descriptor_sets(
+                    context.device,
+                    self.descriptor_pool,
+                    self.descriptor_set_layout,
+                    1,
+                    &vk_utils::texture::DescriptorSetResources {
+                        uniform_buffer_type: vk::DescriptorType::UNIFORM_BUFFER_DYNAMIC,
+                        uniform_buffers: std::slice::from_ref(uniform_ring.buffer()),
+                        uniform_buffer_range: camera_size,
+                        texture_image_view: texture.image_view(),
+                        texture_sampler: texture.sampler(),
+                    },
+                )?[0],
+                None if !self.sample.is_textured() => create_camera_descriptor_set(
+                    context.device,
+                    self.descriptor_pool,
+                    self.descriptor_set_layout,
+                    uniform_ring.buffer(),
+                    camera_size,
+                )?,
+                None => {
+                    return Err(vk_utils::Error::InvalidImage(
+                        "the sample scene has no texture!",
+                    ))
+                }
+            };
+
+            self.uniform_ring = Some(uniform_ring);
+
+            Ok(())
+        }
+
+        fn poll_assets(&mut self, context: &RenderContext) -> vk_utils::Result<()> {
+            let Some(asset_loader) = self.loader.as_mut() else {
+                return Ok(());
+            };
+            let results = asset_loader.poll();
+
+            // Everything arrived, the workers can go
+            if asset_loader.pending() == 0 {
+                self.loader = None;
+            }
+            if results.is_empty() {
+                return Ok(());
+            }
+
+            // Frames in flight may still draw with the placeholders
+            unsafe {
+                context
+                    .device
+                    .device_wait_idle()
+                    .map_err(vk_utils::Error::vulkan("failed to wait device idle!"))?;
+            }
+            for (_, result) in results {
+                self.receive_asset(context, result?)?;
+            }
+
+            Ok(())
+        }
+
+        fn create_frame_data(&mut self, _context: &RenderContext) -> vk_utils::Result<SampleFrame> {
+            use cgmath::SquareMatrix;
+
+            Ok(SampleFrame {
+                uniform_offset: 0,
+                model: cgmath::Matrix4::identity(),
+            })
+        }
+
+        fn create_pipeline(&mut self, context: &RenderContext) -> vk_utils::Result<()> {
+            let [vertex_shader, fragment_shader] = self.sample.shader_sources();
+            let mut builder = PipelineBuilder::new()
+                .shader(vertex_shader)
+                .shader(fragment_shader)
+                .msaa_samples(context.msaa_samples)
+                .descriptor_set_layouts(&[self.descriptor_set_layout])
+                .pipeline_cache(context.pipeline_cache)
+                .attachment_formats(&[context.color_format], context.depth_format);
+
+            // Edges only, so the back faces stay visible
+            if self.wireframe {
+                builder = builder
+                    .polygon_mode(vk::PolygonMode::LINE)
+                    .cull_mode(vk::CullModeFlags::NONE);
+            }
+
+            self.graphics_pipeline = Some(match self.sample {
+                Sample::Triangle => builder
+                    .build_instanced::<vk_types::Vertex2D, vk_types::InstanceTransform>(
+                        context.device,
+                        context.render_pass,
+                    )?,
+                _ => builder
+                    .build_instanced::<vk_types::VertexWithTexture3D, vk_types::InstanceTransform>(
+                        context.device,
+                        context.render_pass,
+                    )?,
+            });
+
+            Ok(())
+        }
+
+        fn shader_sources(&self) -> Vec<PathBuf> {
+            self.sample
+                .shader_sources()
+                .iter()
+                .map(PathBuf::from)
+                .collect()
+        }
+
+        fn update(
+            &mut self,
+            _device: &Device,
+            frame: &mut SampleFrame,
+            frame_index: usize,
+            delta_time: f32,
+        ) -> vk_utils::Result<()> {
+            use cgmath::{Deg, Matrix4, Vector3};
+
+            self.uniform_transform.model =
+                Matrix4::from_axis_angle(Vector3::new(0.0, 0.0, 1.0), Deg(90.0) * delta_time)
+                    * self.uniform_transform.model;
+
+            let cameras = [vk_types::CameraUniformObject::new(
+                self.uniform_transform.view,
+                self.uniform_transform.proj,
+            )];
+            frame.model = self.uniform_transform.model;
+
+            // The ring is host visible and stays mapped
+            let Some(uniform_ring) = self.uniform_ring.as_mut() else {
+                return Ok(());
+            };
+            uniform_ring.begin_frame(frame_index);
+            frame.uniform_offset = uniform_ring.push(&cameras)?;
+
+            Ok(())
+        }
+
+        fn record_commands(
+            &self,
+            device: &Device,
+            command_buffer: vk::CommandBuffer,
+            frame: &SampleFrame,
+        ) -> vk_utils::Result<()> {
+            let (Some(graphics_pipeline), Some(mesh), Some(instance_buffer)) = (
+                self.graphics_pipeline.as_ref(),
+                self.mesh.and_then(|mesh| self.assets.mesh(mesh)),
+                self.instance_buffer.as_ref(),
+            ) else {
+                return Ok(());
+            };
+
+            let vertex_buffers = [mesh.vertex_buffer().handle(), instance_buffer.handle()];
+            let offsets = [0_u64, 0_u64];
+            let descriptor_sets_to_bind = [self.descriptor_set];
+            let dynamic_offsets = [frame.uniform_offset];
+
+            unsafe {
+                device.cmd_bind_pipeline(
+                    command_buffer,
+                    vk::PipelineBindPoint::GRAPHICS,
+                    graphics_pipeline.handle(),
+                );
+                device.cmd_bind_vertex_buffers(command_buffer, 0, &vertex_buffers, &offsets);
+                device.cmd_bind_index_buffer(
+                    command_buffer,
+                    mesh.index_buffer().handle(),
+                    0,
+                    vk::IndexType::UINT32,
+                );
+                device.cmd_bind_descriptor_sets(
+                    command_buffer,
+                    vk::PipelineBindPoint::GRAPHICS,
+                    graphics_pipeline.layout(),
+                    0,
+                    &descriptor_sets_to_bind,
+                    &dynamic_offsets,
+                );
+            }
+
+            // One draw per copy and submesh covers every instance
+            for draw_transform in self.draw_transforms.iter() {
+                for submesh in mesh.submeshes().iter() {
+                    let push_constants = vk_types::DrawPushConstants::new(
+                        draw_transform * frame.model,
+                        submesh.material_id.unwrap_or(0) as u32,
+                    );
+                    graphics_pipeline.push_constants(command_buffer, 0, &push_constants)?;
+
+                    unsafe {
+                        device.cmd_draw_indexed(
+                            command_buffer,
+                            submesh.index_count,
+                            self.instances.len() as u32,
+                            submesh.first_index,
+                            0,
+                            0,
+                        );
+                    }
+                }
+            }
+
+            Ok(())
+        }
+
+        fn destroy_pipeline(&mut self, _device: &Device) {
+            self.graphics_pipeline = None;
+        }
+
+        fn destroy_resources(&mut self, device: &Device) {
+            self.loader = None;
+
+            unsafe {
+                device.destroy_descriptor_pool(self.descriptor_pool, None);
+            }
+            self.descriptor_pool = vk::DescriptorPool::null();
+            self.descriptor_set = vk::DescriptorSet::null();
+
+            self.uniform_ring = None;
+            self.instance_buffer = None;
+
+            self.texture = None;
+            self.mesh = None;
+            self.assets.clear();
+
+            unsafe {
+                device.destroy_descriptor_set_layout(self.descriptor_set_layout, None);
+            }
+            self.descriptor_set_layout = vk::DescriptorSetLayout::null();
+        }
+    }
+}
+
+pub use _sample_scene::{Sample, SampleScene};